@@ -0,0 +1,172 @@
+//! Bell 202 AFSK at 1200 baud, the modem of 2 m packet radio and APRS.
+use crate::hdlc::{self, HdlcDecoder};
+use std::{f64::consts::PI, time::Duration};
+
+pub const BAUD: f64 = 1200.0;
+pub const MARK: f64 = 1200.0;
+pub const SPACE: f64 = 2200.0;
+
+/// Turns frames into audio, NRZI coded: a zero changes the tone.
+pub struct AfskModulator {
+  sample_rate: u32,
+  level: f32,
+  preamble: Duration,
+  tail: usize,
+}
+
+impl AfskModulator {
+  pub fn new(sample_rate: u32) -> Self {
+    Self {
+      sample_rate,
+      level: 0.5,
+      preamble: Duration::from_millis(300),
+      tail: 2,
+    }
+  }
+
+  /// Amplitude relative to full scale.
+  pub fn level(mut self, level: f32) -> Self {
+    self.level = level;
+    self
+  }
+
+  /// Flags sent ahead of each frame for the receivers to lock on, the
+  /// KISS TXDELAY. Defaults to 300 ms.
+  pub fn preamble(mut self, preamble: Duration) -> Self {
+    self.preamble = preamble;
+    self
+  }
+
+  /// Flags sent after each frame, 2 by default.
+  pub fn tail(mut self, tail: usize) -> Self {
+    self.tail = tail;
+    self
+  }
+
+  /// Audio of raw line bits, continuous in phase.
+  pub fn modulate(&self, bits: &[bool]) -> Vec<f32> {
+    let rate = self.sample_rate as f64;
+    let samples_per_bit = rate / BAUD;
+    let mut samples = Vec::with_capacity((bits.len() as f64 * samples_per_bit) as usize + 1);
+    let mut phase = 0.0f64;
+    let mut mark = true;
+    let mut clock = 0.0f64;
+    for &bit in bits {
+      if !bit {
+        mark = !mark;
+      }
+      let step = 2.0 * PI * if mark { MARK } else { SPACE } / rate;
+      clock += samples_per_bit;
+      while (samples.len() as f64) < clock.round() {
+        samples.push(self.level * phase.sin() as f32);
+        phase = (phase + step) % (2.0 * PI);
+      }
+    }
+    samples
+  }
+
+  /// Audio of an AX.25 frame, without FCS, with preamble and tail.
+  pub fn modulate_frame(&self, frame: &[u8]) -> Vec<f32> {
+    let preamble = (self.preamble.as_secs_f64() * BAUD / 8.0).ceil() as usize;
+    self.modulate(&hdlc::encode(frame, preamble, self.tail))
+  }
+}
+
+/// Recovers frames from audio, fed in blocks of any size.
+pub struct AfskDemodulator {
+  /// Mark and space tone per sample, as (cos, sin) steps.
+  tones: [(f64, f64); 2],
+  /// Per tone the correlation of the last bit's worth of audio, and the
+  /// products making it up.
+  sums: [(f64, f64); 2],
+  products: Vec<[(f64, f64); 2]>,
+  oscillators: [(f64, f64); 2],
+  position: usize,
+  step: f64,
+  clock: f64,
+  last_mark: bool,
+  last_bit_mark: bool,
+  samples: u64,
+  hdlc: HdlcDecoder,
+}
+
+impl AfskDemodulator {
+  pub fn new(sample_rate: u32) -> Self {
+    let rate = sample_rate as f64;
+    let window = (rate / BAUD).round().max(1.0) as usize;
+    let tone = |hz: f64| ((2.0 * PI * hz / rate).cos(), (2.0 * PI * hz / rate).sin());
+    Self {
+      tones: [tone(MARK), tone(SPACE)],
+      sums: [(0.0, 0.0); 2],
+      products: vec![[(0.0, 0.0); 2]; window],
+      oscillators: [(1.0, 0.0); 2],
+      position: 0,
+      step: BAUD / rate,
+      clock: 0.0,
+      last_mark: true,
+      last_bit_mark: true,
+      samples: 0,
+      hdlc: HdlcDecoder::new(),
+    }
+  }
+
+  /// Feed audio, returning the frames, without FCS, completed in it.
+  pub fn push(&mut self, samples: &[f32]) -> Vec<Vec<u8>> {
+    let mut frames = Vec::new();
+    for &sample in samples {
+      if let Some(frame) = self.push_sample(sample as f64) {
+        frames.push(frame);
+      }
+    }
+    frames
+  }
+
+  fn push_sample(&mut self, sample: f64) -> Option<Vec<u8>> {
+    let mut energy = [0.0; 2];
+    let old = self.products[self.position];
+    for tone in 0..2 {
+      let (c, s) = self.oscillators[tone];
+      let product = (sample * c, sample * s);
+      self.sums[tone].0 += product.0 - old[tone].0;
+      self.sums[tone].1 += product.1 - old[tone].1;
+      self.products[self.position][tone] = product;
+      energy[tone] = self.sums[tone].0.powi(2) + self.sums[tone].1.powi(2);
+
+      let (dc, ds) = self.tones[tone];
+      self.oscillators[tone] = (c * dc - s * ds, c * ds + s * dc);
+    }
+    self.position = (self.position + 1) % self.products.len();
+    self.samples += 1;
+    // Keep the oscillators on the unit circle and the sums from drifting.
+    if self.samples.is_multiple_of(4096) {
+      self.renormalize();
+    }
+
+    let mark = energy[0] > energy[1];
+    if mark != self.last_mark {
+      // Tone changes belong on bit boundaries, pull the clock towards them.
+      self.clock *= 0.75;
+      self.last_mark = mark;
+    }
+    self.clock += self.step;
+    if self.clock < 0.5 {
+      return None;
+    }
+    self.clock -= 1.0;
+    let bit = mark == self.last_bit_mark;
+    self.last_bit_mark = mark;
+    self.hdlc.push(bit)
+  }
+
+  fn renormalize(&mut self) {
+    for tone in 0..2 {
+      let (c, s) = self.oscillators[tone];
+      let norm = (c * c + s * s).sqrt();
+      self.oscillators[tone] = (c / norm, s / norm);
+      self.sums[tone] = self
+        .products
+        .iter()
+        .fold((0.0, 0.0), |(c, s), p| (c + p[tone].0, s + p[tone].1));
+    }
+  }
+}
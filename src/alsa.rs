@@ -0,0 +1,202 @@
+//! Sound card audio through ALSA, built with the `alsa` feature and linked
+//! against libasound.
+use crate::audio::{AudioSink, AudioSource};
+use libc::{c_char, c_int, c_long, c_uint, c_ulong, c_void};
+use std::{
+  ffi::{CStr, CString},
+  ptr,
+};
+
+#[repr(C)]
+struct SndPcm {
+  _private: [u8; 0],
+}
+
+const STREAM_PLAYBACK: c_int = 0;
+const STREAM_CAPTURE: c_int = 1;
+const FORMAT_S16_LE: c_int = 2;
+const ACCESS_RW_INTERLEAVED: c_int = 3;
+/// Buffer latency asked for, in microseconds.
+const LATENCY: c_uint = 100_000;
+
+#[link(name = "asound")]
+extern "C" {
+  fn snd_pcm_open(pcm: *mut *mut SndPcm, name: *const c_char, stream: c_int, mode: c_int) -> c_int;
+  fn snd_pcm_set_params(
+    pcm: *mut SndPcm,
+    format: c_int,
+    access: c_int,
+    channels: c_uint,
+    rate: c_uint,
+    soft_resample: c_int,
+    latency: c_uint,
+  ) -> c_int;
+  fn snd_pcm_readi(pcm: *mut SndPcm, buffer: *mut c_void, frames: c_ulong) -> c_long;
+  fn snd_pcm_writei(pcm: *mut SndPcm, buffer: *const c_void, frames: c_ulong) -> c_long;
+  fn snd_pcm_recover(pcm: *mut SndPcm, error: c_int, silent: c_int) -> c_int;
+  fn snd_pcm_drain(pcm: *mut SndPcm) -> c_int;
+  fn snd_pcm_prepare(pcm: *mut SndPcm) -> c_int;
+  fn snd_pcm_close(pcm: *mut SndPcm) -> c_int;
+  fn snd_strerror(error: c_int) -> *const c_char;
+}
+
+fn error(what: &str, code: c_int) -> String {
+  // SAFETY: snd_strerror returns a static string for any code.
+  let message = unsafe { CStr::from_ptr(snd_strerror(code)) };
+  format!("{}: {}", what, message.to_string_lossy())
+}
+
+/// An open PCM, mono signed 16 bit at a fixed rate.
+struct Pcm {
+  pcm: *mut SndPcm,
+  sample_rate: u32,
+}
+
+// SAFETY: a PCM handle may move between threads, it is only used through
+// &mut self.
+unsafe impl Send for Pcm {}
+
+impl Pcm {
+  fn open(device: &str, stream: c_int, sample_rate: u32) -> Result<Self, String> {
+    let name = CString::new(device).map_err(|_| format!("Invalid ALSA device {}", device))?;
+    let mut pcm = ptr::null_mut();
+    // SAFETY: pcm is only used once open succeeds, and closed on drop.
+    unsafe {
+      let code = snd_pcm_open(&mut pcm, name.as_ptr(), stream, 0);
+      if code < 0 {
+        return Err(error(&format!("Failed to open {}", device), code));
+      }
+      let pcm = Self { pcm, sample_rate };
+      let code = snd_pcm_set_params(
+        pcm.pcm,
+        FORMAT_S16_LE,
+        ACCESS_RW_INTERLEAVED,
+        1,
+        sample_rate,
+        1,
+        LATENCY,
+      );
+      if code < 0 {
+        return Err(error(
+          &format!("Failed to set {} to {} Hz mono", device, sample_rate),
+          code,
+        ));
+      }
+      Ok(pcm)
+    }
+  }
+
+  /// Run `transfer` again after recovering from an overrun or underrun.
+  fn retry(
+    &mut self,
+    what: &str,
+    mut transfer: impl FnMut(*mut SndPcm) -> c_long,
+  ) -> Result<usize, String> {
+    loop {
+      let result = transfer(self.pcm);
+      if result >= 0 {
+        return Ok(result as usize);
+      }
+      // SAFETY: the handle is open for the lifetime of self.
+      let code = unsafe { snd_pcm_recover(self.pcm, result as c_int, 1) };
+      if code < 0 {
+        return Err(error(what, code));
+      }
+    }
+  }
+}
+
+impl Drop for Pcm {
+  fn drop(&mut self) {
+    // SAFETY: the handle is open and not used after this.
+    unsafe {
+      snd_pcm_close(self.pcm);
+    }
+  }
+}
+
+/// Capture from an ALSA device such as "default" or "plughw:1,0".
+pub struct AlsaSource {
+  pcm: Pcm,
+  buffer: Vec<i16>,
+}
+
+impl AlsaSource {
+  pub fn open(device: &str, sample_rate: u32) -> Result<Self, String> {
+    Ok(Self {
+      pcm: Pcm::open(device, STREAM_CAPTURE, sample_rate)?,
+      buffer: Vec::new(),
+    })
+  }
+}
+
+impl AudioSource for AlsaSource {
+  fn sample_rate(&self) -> u32 {
+    self.pcm.sample_rate
+  }
+
+  /// Blocks until some audio has been captured, never ends.
+  fn read(&mut self, buffer: &mut [f32]) -> Result<usize, String> {
+    self.buffer.resize(buffer.len(), 0);
+    let samples = &mut self.buffer;
+    // SAFETY: the buffer holds as many frames as asked for.
+    let n = self.pcm.retry("Failed to capture audio", |pcm| unsafe {
+      snd_pcm_readi(pcm, samples.as_mut_ptr().cast(), samples.len() as c_ulong)
+    })?;
+    for (sample, &raw) in buffer.iter_mut().zip(&self.buffer[..n]) {
+      *sample = raw as f32 / 32768.0;
+    }
+    Ok(n)
+  }
+}
+
+/// Play to an ALSA device such as "default" or "plughw:1,0".
+pub struct AlsaSink {
+  pcm: Pcm,
+}
+
+impl AlsaSink {
+  pub fn open(device: &str, sample_rate: u32) -> Result<Self, String> {
+    Ok(Self {
+      pcm: Pcm::open(device, STREAM_PLAYBACK, sample_rate)?,
+    })
+  }
+}
+
+impl AudioSink for AlsaSink {
+  fn sample_rate(&self) -> u32 {
+    self.pcm.sample_rate
+  }
+
+  fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+    let raw: Vec<i16> = samples
+      .iter()
+      .map(|s| (s.clamp(-1.0, 1.0) * 32767.0) as i16)
+      .collect();
+    let mut written = 0;
+    while written < raw.len() {
+      let rest = &raw[written..];
+      // SAFETY: rest holds as many frames as given.
+      written += self.pcm.retry("Failed to play audio", |pcm| unsafe {
+        snd_pcm_writei(pcm, rest.as_ptr().cast(), rest.len() as c_ulong)
+      })?;
+    }
+    Ok(())
+  }
+
+  /// Wait for the queued audio to play, then get ready for more.
+  fn flush(&mut self) -> Result<(), String> {
+    // SAFETY: the handle is open for the lifetime of self.
+    unsafe {
+      let code = snd_pcm_drain(self.pcm.pcm);
+      if code < 0 {
+        return Err(error("Failed to finish playing", code));
+      }
+      let code = snd_pcm_prepare(self.pcm.pcm);
+      if code < 0 {
+        return Err(error("Failed to restart playing", code));
+      }
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,162 @@
+//! APRS on the module.
+use crate::{
+  ax25::Address,
+  channel::{Channel, FmBandwidth, FreqConf, Region},
+  filter_config::FilterConfig,
+};
+use std::str::FromStr;
+
+/// The usual APRS frequency of a region in MHz. Region 3 has none in
+/// common, each country picks its own.
+pub fn frequency(region: Region) -> Option<f32> {
+  match region {
+    Region::One => Some(144.800),
+    Region::Two => Some(144.390),
+    Region::Three => None,
+  }
+}
+
+/// Simplex wide FM channel on `frequency` without tones, as APRS is run.
+pub fn channel(frequency: f32) -> Result<Channel, String> {
+  Ok(
+    Channel::default()
+      .bandwidth(FmBandwidth::Wide)
+      .rx(FreqConf::new(frequency)?)
+      .tx(FreqConf::new(frequency)?),
+  )
+}
+
+/// APRS channel of a region.
+pub fn region_channel(region: Region) -> Result<Channel, String> {
+  let frequency =
+    frequency(region).ok_or("Region 3 has no common APRS frequency, choose one instead")?;
+  channel(frequency)
+}
+
+/// Filters for the modem: emphasis and the voice band filters bypassed so
+/// mark and space arrive at the same level.
+pub fn filter_config() -> FilterConfig {
+  FilterConfig::flat()
+}
+
+/// Map symbol, a table character, '/' or '\\', and a symbol code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol {
+  pub table: char,
+  pub code: char,
+}
+
+impl Default for Symbol {
+  /// A car.
+  fn default() -> Self {
+    Self {
+      table: '/',
+      code: '>',
+    }
+  }
+}
+
+/// Parses the table and code together, like "/>" for a car.
+impl FromStr for Symbol {
+  type Err = String;
+
+  fn from_str(symbol: &str) -> Result<Self, Self::Err> {
+    let mut chars = symbol.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+      (Some(table @ ('/' | '\\' | '0'..='9' | 'A'..='Z')), Some(code), None)
+        if code.is_ascii_graphic() =>
+      {
+        Ok(Self { table, code })
+      }
+      _ => Err(format!("Invalid symbol {}", symbol)),
+    }
+  }
+}
+
+/// Where the station is and where it is heading.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Position {
+  /// Degrees, north positive.
+  pub latitude: f64,
+  /// Degrees, east positive.
+  pub longitude: f64,
+  /// Metres above sea level.
+  pub altitude: Option<f64>,
+  /// Degrees from true north.
+  pub course: Option<f64>,
+  /// Knots.
+  pub speed: Option<f64>,
+}
+
+impl Position {
+  pub fn new(latitude: f64, longitude: f64) -> Result<Self, String> {
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+      return Err(format!("Invalid position {}, {}", latitude, longitude));
+    }
+    Ok(Self {
+      latitude,
+      longitude,
+      ..Self::default()
+    })
+  }
+
+  /// Uncompressed position report without messaging, "!4903.50N/07201.75W>"
+  /// followed by course and speed, altitude and `comment`.
+  pub fn report(&self, symbol: Symbol, comment: &str) -> String {
+    let mut report = format!(
+      "!{}{}{}{}",
+      degrees(self.latitude, 2, ['N', 'S']),
+      symbol.table,
+      degrees(self.longitude, 3, ['E', 'W']),
+      symbol.code
+    );
+    if let (Some(course), Some(speed)) = (self.course, self.speed) {
+      let course = match course.rem_euclid(360.0).round() as u16 {
+        0 | 360 => 360,
+        course => course,
+      };
+      report += &format!(
+        "{:03}/{:03}",
+        course,
+        speed.round().clamp(0.0, 999.0) as u16
+      );
+    }
+    if let Some(altitude) = self.altitude {
+      report += &format!("/A={:06}", (altitude / 0.3048).round() as i64);
+    }
+    report + comment
+  }
+}
+
+/// Degrees and minutes to hundredths, "4903.50N" or "07201.75W".
+fn degrees(value: f64, width: usize, hemispheres: [char; 2]) -> String {
+  let hundredths = (value.abs() * 6000.0).round() as u64;
+  format!(
+    "{:0width$}{:02}.{:02}{}",
+    hundredths / 6000,
+    hundredths % 6000 / 100,
+    hundredths % 100,
+    hemispheres[(value < 0.0) as usize],
+  )
+}
+
+/// Message to a station, ":N0CALL-9 :text". Telemetry definitions are
+/// messages to the station sending the telemetry.
+pub fn message(addressee: &Address, text: &str) -> String {
+  format!(":{:<9}:{}", addressee.to_string(), text)
+}
+
+/// Telemetry report, "T#005,199,000,000,000,000,00000000", of up to five
+/// analog values and eight digital bits, the first bit from bit 0.
+pub fn telemetry(sequence: u16, analog: &[u8], digital: u8) -> Result<String, String> {
+  if analog.len() > 5 {
+    return Err(String::from("At most 5 analog telemetry channels"));
+  }
+  let mut report = format!("T#{:03}", sequence % 1000);
+  for i in 0..5 {
+    report += &format!(",{:03}", analog.get(i).copied().unwrap_or(0));
+  }
+  report.push(',');
+  report.extend((0..8).map(|bit| if digital >> bit & 1 == 1 { '1' } else { '0' }));
+  Ok(report)
+}
@@ -0,0 +1,332 @@
+//! Audio to and from the module's AF pins.
+use std::io::Read;
+
+/// A stream of mono audio, samples are in -1.0..=1.0.
+pub trait AudioSource {
+  fn sample_rate(&self) -> u32;
+
+  /// Fill `buffer`, returning how many samples were read, 0 at the end.
+  fn read(&mut self, buffer: &mut [f32]) -> Result<usize, String>;
+
+  /// Read until `buffer` is full or the stream ends.
+  fn read_exact(&mut self, buffer: &mut [f32]) -> Result<usize, String> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+      match self.read(&mut buffer[filled..])? {
+        0 => break,
+        n => filled += n,
+      }
+    }
+    Ok(filled)
+  }
+
+  /// Read the rest of the stream.
+  fn read_to_end(&mut self) -> Result<Vec<f32>, String> {
+    let mut samples = Vec::new();
+    let mut buffer = vec![0.0; 4096];
+    loop {
+      match self.read(&mut buffer)? {
+        0 => return Ok(samples),
+        n => samples.extend_from_slice(&buffer[..n]),
+      }
+    }
+  }
+}
+
+/// A stream of mono audio going to the module's AF input.
+pub trait AudioSink {
+  fn sample_rate(&self) -> u32;
+
+  /// Queue samples for playing, clipping them to -1.0..=1.0.
+  fn write(&mut self, samples: &[f32]) -> Result<(), String>;
+
+  /// Wait until everything written has been played.
+  fn flush(&mut self) -> Result<(), String> {
+    Ok(())
+  }
+}
+
+impl<S: AudioSource + ?Sized> AudioSource for Box<S> {
+  fn sample_rate(&self) -> u32 {
+    (**self).sample_rate()
+  }
+
+  fn read(&mut self, buffer: &mut [f32]) -> Result<usize, String> {
+    (**self).read(buffer)
+  }
+}
+
+impl<S: AudioSink + ?Sized> AudioSink for Box<S> {
+  fn sample_rate(&self) -> u32 {
+    (**self).sample_rate()
+  }
+
+  fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+    (**self).write(samples)
+  }
+
+  fn flush(&mut self) -> Result<(), String> {
+    (**self).flush()
+  }
+}
+
+/// Audio from memory, for tests and audio generated up front.
+#[derive(Debug, Clone)]
+pub struct MemorySource {
+  sample_rate: u32,
+  samples: Vec<f32>,
+  position: usize,
+}
+
+impl MemorySource {
+  pub fn new(sample_rate: u32, samples: Vec<f32>) -> Self {
+    Self {
+      sample_rate,
+      samples,
+      position: 0,
+    }
+  }
+}
+
+impl AudioSource for MemorySource {
+  fn sample_rate(&self) -> u32 {
+    self.sample_rate
+  }
+
+  fn read(&mut self, buffer: &mut [f32]) -> Result<usize, String> {
+    let n = buffer.len().min(self.samples.len() - self.position);
+    buffer[..n].copy_from_slice(&self.samples[self.position..self.position + n]);
+    self.position += n;
+    Ok(n)
+  }
+}
+
+/// Raw signed 16 bit little endian mono from a reader, as arecord writes
+/// it.
+pub struct RawSource<R> {
+  reader: R,
+  sample_rate: u32,
+  bytes: Vec<u8>,
+  /// First byte of a sample split across reads.
+  carry: Option<u8>,
+}
+
+impl<R: Read> RawSource<R> {
+  pub fn new(reader: R, sample_rate: u32) -> Self {
+    Self {
+      reader,
+      sample_rate,
+      bytes: Vec::new(),
+      carry: None,
+    }
+  }
+}
+
+impl<R: Read> AudioSource for RawSource<R> {
+  fn sample_rate(&self) -> u32 {
+    self.sample_rate
+  }
+
+  fn read(&mut self, buffer: &mut [f32]) -> Result<usize, String> {
+    if buffer.is_empty() {
+      return Ok(0);
+    }
+    self.bytes.resize(buffer.len() * 2, 0);
+    let mut filled = 0;
+    if let Some(byte) = self.carry.take() {
+      self.bytes[0] = byte;
+      filled = 1;
+    }
+    // A single byte is not a sample yet, only a read of 0 is the end.
+    while filled < 2 {
+      let n = self
+        .reader
+        .read(&mut self.bytes[filled..])
+        .map_err(|e| format!("Failed to read audio: {}", e))?;
+      if n == 0 {
+        return Ok(0);
+      }
+      filled += n;
+    }
+    let samples = filled / 2;
+    for (sample, bytes) in buffer.iter_mut().zip(self.bytes[..samples * 2].chunks(2)) {
+      *sample = i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32768.0;
+    }
+    if filled % 2 == 1 {
+      self.carry = Some(self.bytes[filled - 1]);
+    }
+    Ok(samples)
+  }
+}
+
+/// Keeps what is written, clipped like a sound card would.
+#[derive(Debug, Clone)]
+pub struct MemorySink {
+  sample_rate: u32,
+  samples: Vec<f32>,
+  flushes: usize,
+}
+
+impl MemorySink {
+  pub fn new(sample_rate: u32) -> Self {
+    Self {
+      sample_rate,
+      samples: Vec::new(),
+      flushes: 0,
+    }
+  }
+
+  pub fn samples(&self) -> &[f32] {
+    &self.samples
+  }
+
+  /// Times the writer waited for playing to finish, once per transmission
+  /// for a [`Transmitter`](crate::ptt::Transmitter).
+  pub fn flushes(&self) -> usize {
+    self.flushes
+  }
+
+  pub fn into_samples(self) -> Vec<f32> {
+    self.samples
+  }
+}
+
+impl AudioSink for MemorySink {
+  fn sample_rate(&self) -> u32 {
+    self.sample_rate
+  }
+
+  fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+    self
+      .samples
+      .extend(samples.iter().map(|s| s.clamp(-1.0, 1.0)));
+    Ok(())
+  }
+
+  fn flush(&mut self) -> Result<(), String> {
+    self.flushes += 1;
+    Ok(())
+  }
+}
+
+/// Level relative to full scale in dB, minus infinity for silence.
+pub fn dbfs(level: f32) -> f32 {
+  20.0 * level.log10()
+}
+
+/// RMS and peak level of the audio seen since the last reset.
+#[derive(Debug, Clone, Default)]
+pub struct LevelMeter {
+  sum_squares: f64,
+  count: u64,
+  peak: f32,
+  clipped: u64,
+}
+
+impl LevelMeter {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn push(&mut self, samples: &[f32]) {
+    for &sample in samples {
+      let level = sample.abs();
+      self.sum_squares += (sample as f64).powi(2);
+      self.peak = self.peak.max(level);
+      if level >= 1.0 {
+        self.clipped += 1;
+      }
+    }
+    self.count += samples.len() as u64;
+  }
+
+  pub fn rms(&self) -> f32 {
+    if self.count == 0 {
+      return 0.0;
+    }
+    (self.sum_squares / self.count as f64).sqrt() as f32
+  }
+
+  pub fn peak(&self) -> f32 {
+    self.peak
+  }
+
+  pub fn rms_dbfs(&self) -> f32 {
+    dbfs(self.rms())
+  }
+
+  pub fn peak_dbfs(&self) -> f32 {
+    dbfs(self.peak)
+  }
+
+  /// Samples at or beyond full scale.
+  pub fn clipped(&self) -> u64 {
+    self.clipped
+  }
+
+  pub fn count(&self) -> u64 {
+    self.count
+  }
+
+  pub fn reset(&mut self) {
+    *self = Self::default();
+  }
+}
+
+/// Meters the audio passing through a source or sink.
+pub struct Metered<A> {
+  inner: A,
+  meter: LevelMeter,
+}
+
+impl<A> Metered<A> {
+  pub fn new(inner: A) -> Self {
+    Self {
+      inner,
+      meter: LevelMeter::new(),
+    }
+  }
+
+  pub fn meter(&self) -> &LevelMeter {
+    &self.meter
+  }
+
+  pub fn meter_mut(&mut self) -> &mut LevelMeter {
+    &mut self.meter
+  }
+
+  pub fn inner(&mut self) -> &mut A {
+    &mut self.inner
+  }
+
+  pub fn into_inner(self) -> A {
+    self.inner
+  }
+}
+
+impl<S: AudioSource> AudioSource for Metered<S> {
+  fn sample_rate(&self) -> u32 {
+    self.inner.sample_rate()
+  }
+
+  fn read(&mut self, buffer: &mut [f32]) -> Result<usize, String> {
+    let n = self.inner.read(buffer)?;
+    self.meter.push(&buffer[..n]);
+    Ok(n)
+  }
+}
+
+impl<S: AudioSink> AudioSink for Metered<S> {
+  fn sample_rate(&self) -> u32 {
+    self.inner.sample_rate()
+  }
+
+  fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+    self.meter.push(samples);
+    self.inner.write(samples)
+  }
+
+  fn flush(&mut self) -> Result<(), String> {
+    self.inner.flush()
+  }
+}
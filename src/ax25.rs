@@ -0,0 +1,228 @@
+//! AX.25 unnumbered information (UI) frames, the frames APRS is sent in.
+use std::{fmt, str::FromStr};
+
+/// UI frame control field.
+pub const UI: u8 = 0x03;
+/// Protocol id for no layer 3, used by APRS.
+pub const NO_LAYER3: u8 = 0xF0;
+
+/// Callsign with SSID, and for digipeaters whether it has repeated the frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Address {
+  callsign: String,
+  ssid: u8,
+  repeated: bool,
+}
+
+impl Address {
+  /// Up to six letters and digits, and an SSID up to 15.
+  pub fn new(callsign: &str, ssid: u8) -> Result<Self, String> {
+    let callsign = callsign.to_ascii_uppercase();
+    if callsign.is_empty()
+      || callsign.len() > 6
+      || !callsign.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+      return Err(format!("Invalid callsign {}", callsign));
+    }
+    if ssid > 15 {
+      return Err(format!("Invalid SSID {}", ssid));
+    }
+    Ok(Self {
+      callsign,
+      ssid,
+      repeated: false,
+    })
+  }
+
+  pub fn callsign(&self) -> &str {
+    &self.callsign
+  }
+
+  pub fn ssid(&self) -> u8 {
+    self.ssid
+  }
+
+  pub fn repeated(&self) -> bool {
+    self.repeated
+  }
+
+  /// Mark a digipeater as having repeated the frame, the H bit.
+  pub fn set_repeated(mut self, repeated: bool) -> Self {
+    self.repeated = repeated;
+    self
+  }
+
+  fn encode(&self, bytes: &mut Vec<u8>, high_bit: bool, last: bool) {
+    let padded = format!("{:<6}", self.callsign);
+    bytes.extend(padded.bytes().map(|b| b << 1));
+    bytes.push((high_bit as u8) << 7 | 0x60 | self.ssid << 1 | last as u8);
+  }
+
+  fn decode(bytes: &[u8]) -> Result<Self, String> {
+    let callsign: String = bytes[..6]
+      .iter()
+      .map(|&b| (b >> 1) as char)
+      .collect::<String>()
+      .trim_end()
+      .to_string();
+    let mut address = Address::new(&callsign, bytes[6] >> 1 & 0x0F)?;
+    address.repeated = bytes[6] & 0x80 != 0;
+    Ok(address)
+  }
+}
+
+/// Parses "N0CALL", "N0CALL-9" and, for digipeaters, "WIDE1-1*".
+impl FromStr for Address {
+  type Err = String;
+
+  fn from_str(address: &str) -> Result<Self, Self::Err> {
+    let (address, repeated) = match address.strip_suffix('*') {
+      Some(address) => (address, true),
+      None => (address, false),
+    };
+    let (callsign, ssid) = match address.split_once('-') {
+      Some((callsign, ssid)) => (
+        callsign,
+        ssid
+          .parse::<u8>()
+          .map_err(|_| format!("Invalid SSID {}", ssid))?,
+      ),
+      None => (address, 0),
+    };
+    Ok(Address::new(callsign, ssid)?.set_repeated(repeated))
+  }
+}
+
+impl fmt::Display for Address {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.callsign)?;
+    if self.ssid != 0 {
+      write!(f, "-{}", self.ssid)?;
+    }
+    if self.repeated {
+      write!(f, "*")?;
+    }
+    Ok(())
+  }
+}
+
+/// An AX.25 UI frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UiFrame {
+  pub destination: Address,
+  pub source: Address,
+  /// Digipeaters, up to 8.
+  pub path: Vec<Address>,
+  pub pid: u8,
+  pub info: Vec<u8>,
+}
+
+impl UiFrame {
+  pub fn new(destination: Address, source: Address, info: &[u8]) -> Self {
+    Self {
+      destination,
+      source,
+      path: Vec::new(),
+      pid: NO_LAYER3,
+      info: info.to_vec(),
+    }
+  }
+
+  pub fn path(mut self, path: Vec<Address>) -> Result<Self, String> {
+    if path.len() > 8 {
+      return Err(String::from("At most 8 digipeaters"));
+    }
+    self.path = path;
+    Ok(self)
+  }
+
+  /// Frame bytes without FCS, sent as a command.
+  pub fn encode(&self) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(16 + 7 * self.path.len() + self.info.len());
+    self.destination.encode(&mut bytes, true, false);
+    self.source.encode(&mut bytes, false, self.path.is_empty());
+    for (i, digi) in self.path.iter().enumerate() {
+      digi.encode(&mut bytes, digi.repeated, i == self.path.len() - 1);
+    }
+    bytes.push(UI);
+    bytes.push(self.pid);
+    bytes.extend_from_slice(&self.info);
+    bytes
+  }
+
+  /// Frame from bytes without FCS, anything but a UI frame is refused.
+  pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+    let mut addresses = Vec::new();
+    let mut at = 0;
+    loop {
+      let field = bytes
+        .get(at..at + 7)
+        .ok_or("Frame ends in the address field")?;
+      addresses.push(Address::decode(field)?);
+      at += 7;
+      if field[6] & 1 == 1 {
+        break;
+      }
+      if addresses.len() == 10 {
+        return Err(String::from("Too many addresses"));
+      }
+    }
+    if addresses.len() < 2 {
+      return Err(String::from("Frame has no source address"));
+    }
+    match bytes.get(at..at + 2) {
+      Some(&[UI, pid]) | Some(&[0x13, pid]) => {
+        let mut addresses = addresses.into_iter();
+        let destination = addresses.next().unwrap().set_repeated(false);
+        let source = addresses.next().unwrap().set_repeated(false);
+        Ok(Self {
+          destination,
+          source,
+          path: addresses.collect(),
+          pid,
+          info: bytes[at + 2..].to_vec(),
+        })
+      }
+      _ => Err(String::from("Not a UI frame")),
+    }
+  }
+}
+
+/// Parses the monitor format "N0CALL-9>APRS,WIDE1-1:info".
+impl FromStr for UiFrame {
+  type Err = String;
+
+  fn from_str(frame: &str) -> Result<Self, Self::Err> {
+    let (header, info) = frame
+      .split_once(':')
+      .ok_or(format!("{} has no info field", frame))?;
+    let (source, addresses) = header
+      .split_once('>')
+      .ok_or(format!("{} has no destination", frame))?;
+    let mut addresses = addresses.split(',');
+    let destination = addresses.next().unwrap_or_default().parse()?;
+    let path = addresses
+      .map(|digi| digi.parse())
+      .collect::<Result<Vec<Address>, String>>()?;
+    UiFrame::new(destination, source.parse()?, info.as_bytes()).path(path)
+  }
+}
+
+/// Monitor format, the info field with anything unprintable as '.'.
+impl fmt::Display for UiFrame {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}>{}", self.source, self.destination)?;
+    for digi in &self.path {
+      write!(f, ",{}", digi)?;
+    }
+    let info: String = self
+      .info
+      .iter()
+      .map(|&b| match b {
+        0x20..=0x7E => b as char,
+        _ => '.',
+      })
+      .collect();
+    write!(f, ":{}", info)
+  }
+}
@@ -0,0 +1,314 @@
+use crate::channel::{Channel, FmBandwidth, PowerLevel, Region};
+use std::{fmt, fs, path::Path};
+
+/// Slack on band edges so f32 frequencies don't fall just outside, in MHz.
+const EDGE: f32 = 0.0001;
+
+/// A frequency range of a band plan, in MHz. A band with the same start and
+/// stop is a single channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Band {
+  pub name: String,
+  pub start: f32,
+  pub stop: f32,
+  /// Transmitting is allowed, otherwise the band is receive only.
+  pub transmit: bool,
+  /// Only narrow FM may be transmitted.
+  pub narrow_only: bool,
+  /// Transmit and receive must be on the same frequency.
+  pub simplex_only: bool,
+  /// The module's H/L pin must select low power.
+  pub low_power_only: bool,
+}
+
+impl Band {
+  pub fn new(name: &str, start: f32, stop: f32) -> Self {
+    Self {
+      name: name.to_string(),
+      start,
+      stop,
+      transmit: true,
+      narrow_only: false,
+      simplex_only: false,
+      low_power_only: false,
+    }
+  }
+
+  pub fn receive_only(mut self) -> Self {
+    self.transmit = false;
+    self
+  }
+
+  pub fn narrow_only(mut self) -> Self {
+    self.narrow_only = true;
+    self
+  }
+
+  pub fn simplex_only(mut self) -> Self {
+    self.simplex_only = true;
+    self
+  }
+
+  pub fn low_power_only(mut self) -> Self {
+    self.low_power_only = true;
+    self
+  }
+
+  pub fn contains(&self, frequency: f32) -> bool {
+    frequency >= self.start - EDGE && frequency <= self.stop + EDGE
+  }
+}
+
+/// What a band plan allows on a channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Access {
+  Transmit,
+  /// Listening is fine, the reason says why transmitting is not.
+  ReceiveOnly {
+    reason: String,
+  },
+}
+
+/// Frequencies a station may transmit on.
+///
+/// Anything outside the plan's bands, or in a receive only band, can only be
+/// listened to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BandPlan {
+  name: String,
+  bands: Vec<Band>,
+}
+
+impl BandPlan {
+  pub fn new(name: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      bands: Vec::new(),
+    }
+  }
+
+  pub fn band(mut self, band: Band) -> Self {
+    self.bands.push(band);
+    self
+  }
+
+  /// IARU amateur allocations the SA818 can reach.
+  pub fn iaru(region: Region) -> Self {
+    match region {
+      Region::One => BandPlan::new("IARU Region 1")
+        .band(Band::new("2m", 144.0, 146.0))
+        .band(Band::new("70cm", 430.0, 440.0)),
+      Region::Two => BandPlan::new("IARU Region 2")
+        .band(Band::new("2m", 144.0, 148.0))
+        .band(Band::new("70cm", 420.0, 450.0)),
+      Region::Three => BandPlan::new("IARU Region 3")
+        .band(Band::new("2m", 144.0, 148.0))
+        .band(Band::new("70cm", 430.0, 440.0)),
+    }
+  }
+
+  /// European licence-free PMR446, the 16 channel centres with narrow FM,
+  /// simplex and low power.
+  pub fn pmr446() -> Self {
+    (1..=PMR446_CHANNELS).fold(BandPlan::new("PMR446"), |plan, channel| {
+      let frequency = pmr446_frequency(channel).unwrap();
+      plan.band(
+        Band::new(&format!("PMR446 {}", channel), frequency, frequency)
+          .narrow_only()
+          .simplex_only()
+          .low_power_only(),
+      )
+    })
+  }
+
+  /// US MURS channels, 1-3 narrow only.
+  pub fn murs() -> Self {
+    BandPlan::new("MURS")
+      .band(Band::new("MURS 1-3", 151.82, 151.94).narrow_only())
+      .band(Band::new("MURS 4-5", 154.57, 154.6))
+  }
+
+  /// US FRS channels, 8-14 narrow only.
+  pub fn frs() -> Self {
+    BandPlan::new("FRS")
+      .band(Band::new("FRS 1-7, 15-22", 462.55, 462.725))
+      .band(Band::new("FRS 8-14", 467.5625, 467.7125).narrow_only())
+  }
+
+  /// Built-in plan by name: iaru1, iaru2, iaru3, pmr446, murs or frs.
+  pub fn builtin(name: &str) -> Option<Self> {
+    match name.to_lowercase().as_str() {
+      "iaru1" => Some(BandPlan::iaru(Region::One)),
+      "iaru2" => Some(BandPlan::iaru(Region::Two)),
+      "iaru3" => Some(BandPlan::iaru(Region::Three)),
+      "pmr446" => Some(BandPlan::pmr446()),
+      "murs" => Some(BandPlan::murs()),
+      "frs" => Some(BandPlan::frs()),
+      _ => None,
+    }
+  }
+
+  /// Parse a plan file, one `start,stop,access[,name]` band per line.
+  ///
+  /// Access is `rx` or `tx`, the latter optionally followed by `-narrow`,
+  /// `-simplex` and `-low` restrictions as in `tx-narrow-low`. `#` starts a
+  /// comment.
+  pub fn parse(name: &str, plan: &str) -> Result<Self, String> {
+    let mut bands = BandPlan::new(name);
+    for (number, line) in plan.lines().enumerate() {
+      let line = line.split('#').next().unwrap().trim();
+      if line.is_empty() {
+        continue;
+      }
+      let fields: Vec<&str> = line.splitn(4, ',').map(str::trim).collect();
+      if fields.len() < 3 {
+        return Err(format!("Line {}: expected start,stop,access", number + 1));
+      }
+      let frequency = |field: &str| {
+        field
+          .parse::<f32>()
+          .map_err(|e| format!("Line {}: {}", number + 1, e))
+      };
+      let (start, stop) = (frequency(fields[0])?, frequency(fields[1])?);
+      if start > stop {
+        return Err(format!("Line {}: band starts after it stops", number + 1));
+      }
+      let mut band = Band::new(fields.get(3).copied().unwrap_or_default(), start, stop);
+      let invalid = || format!("Line {}: invalid access {}", number + 1, fields[2]);
+      let mut access = fields[2].split('-');
+      match access.next() {
+        Some("rx") => band = band.receive_only(),
+        Some("tx") => {}
+        _ => return Err(invalid()),
+      }
+      for restriction in access {
+        band = match restriction {
+          "narrow" => band.narrow_only(),
+          "simplex" => band.simplex_only(),
+          "low" => band.low_power_only(),
+          _ => return Err(invalid()),
+        };
+      }
+      bands = bands.band(band);
+    }
+    Ok(bands)
+  }
+
+  /// A built-in plan name, or a plan file named after its file stem.
+  pub fn load(plan: &str) -> Result<Self, String> {
+    if let Some(plan) = BandPlan::builtin(plan) {
+      return Ok(plan);
+    }
+    BandPlan::from_file(plan)
+  }
+
+  pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+    let path = path.as_ref();
+    let plan = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let name = path
+      .file_stem()
+      .map(|stem| stem.to_string_lossy().into_owned())
+      .unwrap_or_default();
+    BandPlan::parse(&name, &plan)
+  }
+
+  pub fn write_file<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+    fs::write(path, self.to_string()).map_err(|e| e.to_string())
+  }
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn bands(&self) -> &[Band] {
+    &self.bands
+  }
+
+  /// Band containing a frequency. Where bands overlap the narrowest wins,
+  /// and a receive only one on a tie, so sub-bands restrict the band around
+  /// them.
+  pub fn lookup(&self, frequency: f32) -> Option<&Band> {
+    self
+      .bands
+      .iter()
+      .filter(|band| band.contains(frequency))
+      .min_by(|a, b| {
+        (a.stop - a.start)
+          .total_cmp(&(b.stop - b.start))
+          .then(a.transmit.cmp(&b.transmit))
+      })
+  }
+
+  /// Whether the channel's transmit frequency and bandwidth fit the plan.
+  pub fn access(&self, channel: &Channel) -> Access {
+    let Some(frequency) = channel.tx_frequency() else {
+      return Access::ReceiveOnly {
+        reason: String::from("Tx frequency is not specified!"),
+      };
+    };
+    let reason = match self.lookup(frequency) {
+      None => format!(
+        "{:.4} MHz is outside the {} band plan",
+        frequency, self.name
+      ),
+      Some(band) if !band.transmit => format!(
+        "{:.4} MHz is receive only in the {} band plan",
+        frequency, self.name
+      ),
+      Some(band) if band.narrow_only && channel.fm_bandwidth() == FmBandwidth::Wide => format!(
+        "{} only allows narrow FM at {:.4} MHz",
+        self.name, frequency
+      ),
+      Some(band)
+        if band.simplex_only
+          && channel
+            .rx_frequency()
+            .is_none_or(|rx| (rx - frequency).abs() > EDGE) =>
+      {
+        format!("{} only allows simplex at {:.4} MHz", self.name, frequency)
+      }
+      Some(band) if band.low_power_only && channel.power_level() == PowerLevel::High => format!(
+        "{} only allows low power at {:.4} MHz",
+        self.name, frequency
+      ),
+      Some(_) => return Access::Transmit,
+    };
+    Access::ReceiveOnly { reason }
+  }
+}
+
+/// Number of PMR446 channels.
+pub const PMR446_CHANNELS: u8 = 16;
+
+/// Centre frequency of a PMR446 channel, 1 to 16, in MHz.
+pub fn pmr446_frequency(channel: u8) -> Result<f32, String> {
+  if !(1..=PMR446_CHANNELS).contains(&channel) {
+    return Err(format!("Invalid PMR446 channel {}", channel));
+  }
+  Ok((446.00625 + (channel - 1) as f64 * 0.0125) as f32)
+}
+
+impl fmt::Display for BandPlan {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "# {}", self.name)?;
+    writeln!(f, "# start,stop,access,name")?;
+    for band in &self.bands {
+      let mut access = String::from(if band.transmit { "tx" } else { "rx" });
+      for (restricted, restriction) in [
+        (band.narrow_only, "-narrow"),
+        (band.simplex_only, "-simplex"),
+        (band.low_power_only, "-low"),
+      ] {
+        if restricted {
+          access.push_str(restriction);
+        }
+      }
+      writeln!(
+        f,
+        "{:.4},{:.4},{},{}",
+        band.start, band.stop, access, band.name
+      )?;
+    }
+    Ok(())
+  }
+}
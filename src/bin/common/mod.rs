@@ -0,0 +1,295 @@
+//! Helpers shared by the binaries, not every binary uses all of them.
+#![allow(dead_code)]
+
+pub mod pipe;
+
+use clap::{Args, ValueEnum};
+use pipe::{CommandSink, CommandSource};
+#[cfg(feature = "alsa")]
+use sa818::alsa::{AlsaSink, AlsaSource};
+use sa818::{
+  audio::{AudioSink, AudioSource},
+  band_plan::BandPlan,
+  channel::Channel,
+  ptt::{ControlLine, SerialPtt},
+  rssi_log::{LogFormat, RssiLogger},
+  squelch::{SquelchPin, StatusLine},
+};
+use serialport::SerialPort;
+use std::{path::PathBuf, time::Duration};
+
+pub fn open_serial(serial_port: &str, baud: u32) -> Result<Box<dyn SerialPort>, String> {
+  serialport::new(serial_port, baud)
+    .timeout(Duration::from_millis(1000))
+    .data_bits(serialport::DataBits::Eight)
+    .parity(serialport::Parity::None)
+    .stop_bits(serialport::StopBits::One)
+    .open()
+    .map_err(|e| format!("Failed to open {}: {}", serial_port, e))
+}
+
+/// Parse durations like `200ms`, `5s`, `2m` or `1h`, bare numbers are milliseconds.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+  let value = value.trim();
+  let split = value
+    .find(|c: char| !c.is_ascii_digit() && c != '.')
+    .unwrap_or(value.len());
+  let (number, unit) = value.split_at(split);
+  let number = number
+    .parse::<f64>()
+    .map_err(|_| format!("Invalid duration {}", value))?;
+  let seconds = match unit.trim() {
+    "" | "ms" => number / 1000.0,
+    "s" => number,
+    "m" | "min" => number * 60.0,
+    "h" => number * 3600.0,
+    unit => return Err(format!("Invalid duration unit {}", unit)),
+  };
+  Duration::try_from_secs_f64(seconds).map_err(|_| format!("Invalid duration {}", value))
+}
+
+/// Parse a duration that must not be zero, for polling intervals.
+pub fn parse_interval(value: &str) -> Result<Duration, String> {
+  match parse_duration(value)? {
+    Duration::ZERO => Err(format!("Interval {} must be longer than zero", value)),
+    interval => Ok(interval),
+  }
+}
+
+/// Parse sizes like `500k`, `10M` or `1G`, bare numbers are bytes.
+pub fn parse_size(value: &str) -> Result<u64, String> {
+  let value = value.trim();
+  let (number, multiplier) = match value.char_indices().last() {
+    Some((i, 'k' | 'K')) => (&value[..i], 1 << 10),
+    Some((i, 'M')) => (&value[..i], 1 << 20),
+    Some((i, 'G')) => (&value[..i], 1 << 30),
+    _ => (value, 1),
+  };
+  number
+    .trim()
+    .parse::<u64>()
+    .map(|n| n * multiplier)
+    .map_err(|_| format!("Invalid size {}", value))
+}
+
+/// Options for recording RSSI readings to a rotated log file.
+#[derive(Args)]
+pub struct LogArgs {
+  /// Append readings to FILE, CSV or JSON lines for `.jsonl`
+  #[arg(long, value_name = "FILE")]
+  pub log: Option<PathBuf>,
+  /// Log file format, overrides the file extension (csv or jsonl)
+  #[arg(long, value_name = "FORMAT")]
+  pub log_format: Option<LogFormat>,
+  /// Rotate the log file once it reaches SIZE, e.g. 10M
+  #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+  pub rotate_size: Option<u64>,
+  /// Rotate the log file after DURATION, e.g. 1h
+  #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+  pub rotate_every: Option<Duration>,
+}
+
+impl LogArgs {
+  pub fn open(&self) -> Result<Option<RssiLogger>, String> {
+    let Some(path) = &self.log else {
+      return Ok(None);
+    };
+    let format = self.log_format.unwrap_or(LogFormat::from_path(path));
+    let mut logger = RssiLogger::create(path, format)?;
+    if let Some(size) = self.rotate_size {
+      logger = logger.rotate_size(size);
+    }
+    if let Some(every) = self.rotate_every {
+      logger = logger.rotate_every(every);
+    }
+    Ok(Some(logger))
+  }
+}
+
+/// Parse frequency offsets like `12.5k`, `-600k` or `5M` into MHz, bare numbers are kHz.
+pub fn parse_offset(value: &str) -> Result<f32, String> {
+  let value = value.trim();
+  let (number, divisor) = match value.char_indices().last() {
+    Some((i, 'k' | 'K')) => (&value[..i], 1000.0),
+    Some((i, 'M')) => (&value[..i], 1.0),
+    _ => (value, 1000.0),
+  };
+  number
+    .trim()
+    .parse::<f32>()
+    .map(|n| n / divisor)
+    .map_err(|_| format!("Invalid frequency offset {}", value))
+}
+
+/// Parse a channel step like [`parse_offset`], it must be above zero.
+pub fn parse_step(value: &str) -> Result<f32, String> {
+  match parse_offset(value)? {
+    step if step > 0.0 => Ok(step),
+    _ => Err(format!("Step {} must be above zero", value)),
+  }
+}
+
+/// Options for checking channels against a band plan before writing them.
+#[derive(Args)]
+pub struct BandPlanArgs {
+  /// Only program channels this band plan allows transmitting on:
+  /// iaru1, iaru2, iaru3, pmr446, murs, frs or a plan file.
+  /// Without it transmit frequencies are not checked
+  #[arg(long, value_name = "PLAN", global = true, verbatim_doc_comment)]
+  pub band_plan: Option<String>,
+  /// Program channels the band plan only allows receiving on
+  #[arg(long, global = true, requires = "band_plan")]
+  pub ignore_band_plan: bool,
+}
+
+impl BandPlanArgs {
+  pub fn load(&self) -> Result<Option<BandPlan>, String> {
+    self.band_plan.as_deref().map(BandPlan::load).transpose()
+  }
+
+  pub fn apply(&self, channel: Channel) -> Result<Channel, String> {
+    Ok(self.apply_all(vec![channel])?.remove(0))
+  }
+
+  /// Apply the plan to every channel, loading it once.
+  pub fn apply_all(&self, channels: Vec<Channel>) -> Result<Vec<Channel>, String> {
+    let Some(plan) = self.load()? else {
+      return Ok(channels);
+    };
+    Ok(
+      channels
+        .into_iter()
+        .map(|channel| {
+          channel
+            .band_plan(plan.clone())
+            .ignore_band_plan(self.ignore_band_plan)
+        })
+        .collect(),
+    )
+  }
+}
+
+/// Options for keying the module's PTT from a serial control line.
+#[derive(Args)]
+pub struct PttArgs {
+  /// Serial port whose control line keys the PTT, defaults to the module's
+  #[arg(long, value_name = "SERIAL")]
+  pub ptt_port: Option<String>,
+  /// Control line keying the PTT
+  #[arg(long, value_enum, default_value = "rts")]
+  pub ptt: PttLine,
+  /// Drive the PTT line low to key
+  #[arg(long)]
+  pub ptt_active_low: bool,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum PttLine {
+  Rts,
+  Dtr,
+}
+
+impl From<PttLine> for ControlLine {
+  fn from(line: PttLine) -> Self {
+    match line {
+      PttLine::Rts => ControlLine::Rts,
+      PttLine::Dtr => ControlLine::Dtr,
+    }
+  }
+}
+
+impl PttArgs {
+  /// PTT on `--ptt-port`, or on `module`, the port the module is
+  /// configured through.
+  pub fn open(&self, module: &dyn SerialPort, baud: u32) -> Result<SerialPtt, String> {
+    let port = match &self.ptt_port {
+      Some(port) => open_serial(port, baud)?,
+      None => module
+        .try_clone()
+        .map_err(|e| format!("Failed to share the serial port for PTT: {}", e))?,
+    };
+    Ok(SerialPtt::new(port, self.ptt.into()).active_low(self.ptt_active_low))
+  }
+}
+
+/// Options for reading the module's squelch from a serial status line.
+#[derive(Args)]
+pub struct SquelchArgs {
+  /// Serial status line wired to the SQ pin, without it the RSSI detects a carrier
+  #[arg(long, value_enum)]
+  pub squelch_pin: Option<SquelchLine>,
+  /// Read the squelch as open while the line is deasserted
+  #[arg(long, requires = "squelch_pin")]
+  pub squelch_active_low: bool,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum SquelchLine {
+  Cts,
+  Dsr,
+  Cd,
+}
+
+impl From<SquelchLine> for StatusLine {
+  fn from(line: SquelchLine) -> Self {
+    match line {
+      SquelchLine::Cts => StatusLine::Cts,
+      SquelchLine::Dsr => StatusLine::Dsr,
+      SquelchLine::Cd => StatusLine::Cd,
+    }
+  }
+}
+
+impl SquelchArgs {
+  /// Squelch on `module`, the port the module is configured through, if
+  /// `--squelch-pin` is given.
+  pub fn open(&self, module: &dyn SerialPort) -> Result<Option<SquelchPin>, String> {
+    let Some(line) = self.squelch_pin else {
+      return Ok(None);
+    };
+    let port = module
+      .try_clone()
+      .map_err(|e| format!("Failed to share the serial port for the squelch: {}", e))?;
+    Ok(Some(
+      SquelchPin::new(port, line.into()).active_low(self.squelch_active_low),
+    ))
+  }
+}
+
+/// Options for the module's audio through a sound card.
+#[derive(Args)]
+pub struct AudioArgs {
+  /// Audio sample rate
+  #[arg(long, default_value = "48000")]
+  pub rate: u32,
+  /// ALSA device for the audio instead of the commands, e.g. plughw:1,0
+  #[cfg(feature = "alsa")]
+  #[arg(long, value_name = "DEVICE")]
+  pub alsa: Option<String>,
+  /// Command writing received audio as raw S16_LE mono, {rate} is replaced
+  #[arg(long, default_value = "arecord -q -t raw -f S16_LE -c 1 -r {rate}")]
+  pub rx_command: String,
+  /// Command playing transmit audio as raw S16_LE mono, {rate} is replaced
+  #[arg(long, default_value = "aplay -q -t raw -f S16_LE -c 1 -r {rate}")]
+  pub tx_command: String,
+}
+
+impl AudioArgs {
+  pub fn source(&self) -> Result<Box<dyn AudioSource + Send>, String> {
+    #[cfg(feature = "alsa")]
+    if let Some(device) = &self.alsa {
+      return Ok(Box::new(AlsaSource::open(device, self.rate)?));
+    }
+    let command = self.rx_command.replace("{rate}", &self.rate.to_string());
+    Ok(Box::new(CommandSource::spawn(&command, self.rate)?))
+  }
+
+  pub fn sink(&self) -> Result<Box<dyn AudioSink + Send>, String> {
+    #[cfg(feature = "alsa")]
+    if let Some(device) = &self.alsa {
+      return Ok(Box::new(AlsaSink::open(device, self.rate)?));
+    }
+    let command = self.tx_command.replace("{rate}", &self.rate.to_string());
+    Ok(Box::new(CommandSink::new(&command, self.rate)))
+  }
+}
@@ -0,0 +1,100 @@
+//! Audio through commands like arecord and aplay, raw signed 16 bit mono.
+use sa818::audio::{AudioSink, AudioSource, RawSource};
+use std::{
+  io::Write,
+  process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+fn spawn(command: &str, stdio: (Stdio, Stdio)) -> Result<Child, String> {
+  let mut words = command.split_whitespace();
+  let program = words.next().ok_or("Empty audio command")?;
+  Command::new(program)
+    .args(words)
+    .stdin(stdio.0)
+    .stdout(stdio.1)
+    .spawn()
+    .map_err(|e| format!("Failed to run {}: {}", program, e))
+}
+
+/// Receive audio from a recording command's output.
+pub struct CommandSource {
+  _child: Child,
+  raw: RawSource<ChildStdout>,
+}
+
+impl CommandSource {
+  pub fn spawn(command: &str, sample_rate: u32) -> Result<Self, String> {
+    let mut child = spawn(command, (Stdio::null(), Stdio::piped()))?;
+    let stdout = child.stdout.take().unwrap();
+    Ok(Self {
+      _child: child,
+      raw: RawSource::new(stdout, sample_rate),
+    })
+  }
+}
+
+impl AudioSource for CommandSource {
+  fn sample_rate(&self) -> u32 {
+    self.raw.sample_rate()
+  }
+
+  fn read(&mut self, buffer: &mut [f32]) -> Result<usize, String> {
+    self.raw.read(buffer)
+  }
+}
+
+/// Play audio through a command started for each transmission, so the
+/// sound card is free in between.
+pub struct CommandSink {
+  command: String,
+  sample_rate: u32,
+  playing: Option<(Child, ChildStdin)>,
+}
+
+impl CommandSink {
+  pub fn new(command: &str, sample_rate: u32) -> Self {
+    Self {
+      command: command.to_string(),
+      sample_rate,
+      playing: None,
+    }
+  }
+}
+
+impl AudioSink for CommandSink {
+  fn sample_rate(&self) -> u32 {
+    self.sample_rate
+  }
+
+  fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+    if self.playing.is_none() {
+      let mut child = spawn(&self.command, (Stdio::piped(), Stdio::null()))?;
+      let stdin = child.stdin.take().unwrap();
+      self.playing = Some((child, stdin));
+    }
+    let (_, stdin) = self.playing.as_mut().unwrap();
+    let bytes: Vec<u8> = samples
+      .iter()
+      .flat_map(|s| ((s.clamp(-1.0, 1.0) * 32767.0) as i16).to_le_bytes())
+      .collect();
+    stdin
+      .write_all(&bytes)
+      .map_err(|e| format!("Failed to play audio: {}", e))
+  }
+
+  /// Close the command's input and wait for it to finish playing.
+  fn flush(&mut self) -> Result<(), String> {
+    let Some((mut child, stdin)) = self.playing.take() else {
+      return Ok(());
+    };
+    drop(stdin);
+    let status = child
+      .wait()
+      .map_err(|e| format!("Failed to wait for {}: {}", self.command, e))?;
+    if status.success() {
+      Ok(())
+    } else {
+      Err(format!("{} failed: {}", self.command, status))
+    }
+  }
+}
@@ -1,7 +1,28 @@
+#[path = "../common/mod.rs"]
+mod common;
+mod mem;
+mod page;
+mod record;
+mod scan;
+mod tonescan;
+mod track;
+mod watch;
+
 use clap::{Args, Parser, Subcommand, ValueEnum};
-use sa818::channel::{Channel, FmBandwidth, FreqConf};
+use sa818::{
+  channel::{Channel, FmBandwidth, FreqConf, Region},
+  device::Sa818,
+  rssi::{Calibration, Rssi, RssiMonitor},
+  rssi_log::{LogFormat, LogRecord},
+};
 use serialport::SerialPort;
-use std::{process::exit, time::Duration};
+use std::{
+  io::{self, BufRead},
+  path::PathBuf,
+  process::exit,
+  thread,
+  time::{Duration, SystemTime},
+};
 
 #[derive(Parser)]
 #[command(arg_required_else_help = true)]
@@ -10,6 +31,9 @@ struct Cli {
   /// Specify serial port
   #[arg(short, long, value_name = "SERIAL", default_value = "/dev/ttyS1")]
   serial: String,
+  /// Serial baud rate
+  #[arg(short, long, default_value = "9600")]
+  baud: u32,
   /// Turn debugging information on
   #[arg(short, long, action = clap::ArgAction::Count)]
   debug: u8,
@@ -23,22 +47,81 @@ enum Commands {
   /// get version of sa818
   Version,
   /// get RSSI value
-  Rssi,
+  Rssi {
+    /// Calibration table written by `calibrate`, defaults to the datasheet curve
+    #[arg(long, short, value_name = "FILE")]
+    calibration: Option<PathBuf>,
+    /// Keep polling until interrupted
+    #[arg(long, short)]
+    watch: bool,
+    /// Time between polls in watch mode, e.g. 200ms
+    #[arg(long, short, default_value = "1s", value_parser = common::parse_duration)]
+    interval: Duration,
+    /// Output format
+    #[arg(long, short, value_enum, default_value = "text")]
+    format: OutputFormat,
+    /// Frequency the module is tuned to in MHz, recorded with each reading
+    #[arg(long)]
+    frequency: Option<f32>,
+    /// Raw RSSI at or above which the squelch counts as open
+    #[arg(long, short = 't')]
+    squelch_threshold: Option<u8>,
+    #[command(flatten)]
+    log: common::LogArgs,
+  },
+  /// scan a frequency range for activity
+  Scan(scan::ScanArgs),
+  /// listen on a working channel while checking a priority channel
+  Watch(watch::WatchArgs),
+  /// manage named channel memories
+  Mem(mem::MemArgs),
+  /// find the CTCSS tone or DCS code in a recording of received audio
+  Tonescan(tonescan::ToneScanArgs),
+  /// send a POCSAG page
+  Page(Box<page::PageArgs>),
+  /// record traffic on a channel whenever the squelch opens
+  Record(Box<record::RecordArgs>),
+  /// beacon APRS position and telemetry
+  Track(Box<track::TrackArgs>),
+  /// measure RSSI at known input levels and write a calibration table
+  Calibrate {
+    /// Output calibration table
+    #[arg(
+      long,
+      short,
+      value_name = "FILE",
+      default_value = "rssi_calibration.csv"
+    )]
+    output: PathBuf,
+    /// Signal generator levels to measure, in dBm
+    #[arg(
+      long,
+      short,
+      value_delimiter = ',',
+      allow_negative_numbers = true,
+      default_value = "-130,-120,-110,-100,-90,-80,-70,-60,-50"
+    )]
+    levels: Vec<f32>,
+    /// RSSI readings averaged per level
+    #[arg(long, default_value = "10", value_parser = clap::value_parser!(u32).range(1..))]
+    samples: u32,
+  },
   #[command(arg_required_else_help = true)]
   /// configure tx, rx frequency and group selective(CTCSS OR DCS)
   Channel {
     #[command(subcommand)]
     mode: Option<Mode>,
-    #[arg(long, short, value_enum, default_value = "narrow")]
+    #[arg(long, short, value_enum, default_value = "narrow", global = true)]
     bandwidth: Bandwidth,
 
-    #[arg(long, short, default_value = "4")]
+    #[arg(long, short, default_value = "4", global = true)]
     squelch: u8,
     /// Use the same dcs for both tx and rx
     /// Dcs format is code<N|I>
     #[arg(
             long,
             short,
+            global = true,
             conflicts_with_all = ["rcts","rdcs","tcts","tdcs","ctcss"],
             verbatim_doc_comment
         )]
@@ -46,6 +129,7 @@ enum Commands {
     #[arg(
             long,
             short,
+            global = true,
             conflicts_with_all = ["rcts","rdcs","tcts","tdcs","dcs"],
         )]
     /// Use the same ctcss for both tx and rx
@@ -54,6 +138,8 @@ enum Commands {
     receive_group: Option<RxGroupSel>,
     #[command(flatten)]
     transmit_group: Option<TxGroupSel>,
+    #[command(flatten)]
+    band_plan: common::BandPlanArgs,
   },
 }
 
@@ -61,10 +147,10 @@ enum Commands {
 #[group(required = false, multiple = false)]
 struct RxGroupSel {
   /// Receive ctcss
-  #[arg(long)]
+  #[arg(long, global = true)]
   rcts: Option<String>,
   /// Receive dcs
-  #[arg(long)]
+  #[arg(long, global = true)]
   rdcs: Option<String>,
 }
 
@@ -72,10 +158,10 @@ struct RxGroupSel {
 #[group(required = false, multiple = false)]
 struct TxGroupSel {
   /// Receive ctcss
-  #[arg(long)]
+  #[arg(long, global = true)]
   tcts: Option<String>,
   /// Transmit dcs
-  #[arg(long)]
+  #[arg(long, global = true)]
   tdcs: Option<String>,
 }
 
@@ -90,6 +176,67 @@ enum Mode {
     #[arg(short, long, value_name = "TXFREQUENCY")]
     txfrequency: f32,
   },
+  /// Receive on a repeater output and transmit on its input
+  Repeater {
+    /// Repeater output frequency in MHz
+    frequency: f32,
+    /// Offset like -600k or 7.6M, or +/- for the band's usual offset
+    #[arg(long, default_value = "-", allow_hyphen_values = true, value_parser = parse_shift)]
+    shift: Shift,
+    /// Transmit frequency for repeaters with an odd split
+    #[arg(long, conflicts_with = "shift")]
+    split: Option<f32>,
+    /// IARU region for the usual offsets
+    #[arg(long, value_enum, default_value = "1")]
+    region: IaruRegion,
+    /// Swap rx and tx to listen on the repeater input
+    #[arg(long)]
+    reverse: bool,
+  },
+  /// PMR446 channel 1-16, narrow FM simplex at low power
+  Pmr446 { channel: u8 },
+}
+
+#[derive(Clone, Copy)]
+enum Shift {
+  Up,
+  Down,
+  Offset(f32),
+}
+
+fn parse_shift(value: &str) -> Result<Shift, String> {
+  match value.trim() {
+    "+" => Ok(Shift::Up),
+    "-" => Ok(Shift::Down),
+    offset => common::parse_offset(offset).map(Shift::Offset),
+  }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+enum IaruRegion {
+  #[value(name = "1")]
+  One,
+  #[value(name = "2")]
+  Two,
+  #[value(name = "3")]
+  Three,
+}
+
+impl From<IaruRegion> for Region {
+  fn from(region: IaruRegion) -> Self {
+    match region {
+      IaruRegion::One => Region::One,
+      IaruRegion::Two => Region::Two,
+      IaruRegion::Three => Region::Three,
+    }
+  }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+enum OutputFormat {
+  Text,
+  Csv,
+  Jsonl,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -97,17 +244,181 @@ enum Bandwidth {
   Wide,
   Narrow,
 }
+
+impl From<Bandwidth> for FmBandwidth {
+  fn from(bandwidth: Bandwidth) -> Self {
+    match bandwidth {
+      Bandwidth::Wide => FmBandwidth::Wide,
+      Bandwidth::Narrow => FmBandwidth::Narrow,
+    }
+  }
+}
 fn main() {
   let cli = Cli::parse();
-  let mut serial_io: Box<dyn SerialPort> = open_serial(cli.serial);
-  match cli.command {
+  let command = match cli.command {
+    Some(Commands::Mem(args)) => {
+      mem::run(args, || common::open_serial(&cli.serial, cli.baud)).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1)
+      });
+      return;
+    }
+    Some(Commands::Tonescan(args)) => {
+      tonescan::run(args).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1)
+      });
+      return;
+    }
+    command => command,
+  };
+  let mut serial_io: Box<dyn SerialPort> = common::open_serial(&cli.serial, cli.baud)
+    .unwrap_or_else(|e| {
+      eprintln!("{e}");
+      exit(1)
+    });
+  match command {
     Some(Commands::Version) => {
       let result = sa818::get_version(&mut serial_io);
       println!("version: {}", result.unwrap())
     }
-    Some(Commands::Rssi) => {
-      let result = sa818::get_rssi(&mut serial_io);
-      println!("RSSI: {}", result.unwrap())
+    Some(Commands::Rssi {
+      calibration,
+      watch,
+      interval,
+      format,
+      frequency,
+      squelch_threshold,
+      log,
+    }) => {
+      let calibrated = calibration.is_some();
+      let calibration = load_calibration(calibration);
+      let mut logger = log.open().unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1)
+      });
+      let record = |value: u8, timestamp: SystemTime| LogRecord {
+        timestamp,
+        frequency,
+        rssi: value,
+        dbm: calibrated.then(|| Rssi(value).dbm(&calibration)),
+        squelch_open: squelch_threshold.map(|threshold| value >= threshold),
+      };
+      let mut emit = |record: LogRecord| {
+        match format {
+          OutputFormat::Text => {
+            let rssi = Rssi(record.rssi);
+            println!(
+              "RSSI: {} ({:.1} dBm, {})",
+              rssi,
+              rssi.dbm(&calibration),
+              rssi.s_meter(&calibration)
+            )
+          }
+          OutputFormat::Csv => println!("{}", LogFormat::Csv.format(&record)),
+          OutputFormat::Jsonl => println!("{}", LogFormat::JsonLines.format(&record)),
+        }
+        if let Some(logger) = &mut logger {
+          logger.write(&record).unwrap_or_else(|e| eprintln!("{e}"));
+        }
+      };
+      if format == OutputFormat::Csv {
+        println!("{}", LogFormat::Csv.header().unwrap());
+      }
+
+      if !watch {
+        let value = sa818::get_rssi(&mut serial_io).unwrap();
+        emit(record(value, SystemTime::now()));
+        return;
+      }
+      let monitor = RssiMonitor::new(Sa818::new(serial_io)).interval(interval);
+      for reading in monitor {
+        match reading {
+          Ok(reading) => emit(record(reading.sample.value, reading.sample.timestamp)),
+          Err(e) => eprintln!("{e}"),
+        }
+      }
+    }
+    Some(Commands::Scan(args)) => {
+      scan::run(Sa818::new(serial_io), args).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1)
+      });
+    }
+    Some(Commands::Watch(args)) => {
+      watch::run(Sa818::new(serial_io), args).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1)
+      });
+    }
+    Some(Commands::Page(args)) => {
+      page::run(serial_io, cli.baud, *args).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1)
+      });
+    }
+    Some(Commands::Record(args)) => {
+      record::run(serial_io, *args).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1)
+      });
+    }
+    Some(Commands::Track(args)) => {
+      track::run(serial_io, cli.baud, *args).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1)
+      });
+    }
+    Some(Commands::Mem(_)) | Some(Commands::Tonescan(_)) => unreachable!(),
+    Some(Commands::Calibrate {
+      output,
+      levels,
+      samples,
+    }) => {
+      if let Some(level) = levels.iter().find(|level| !level.is_finite()) {
+        eprintln!("Invalid level {level} dBm");
+        exit(1)
+      }
+      let mut points: Vec<(f32, f32)> = Vec::new();
+      let stdin = io::stdin();
+      for level in levels {
+        loop {
+          println!("Set the generator to {level} dBm and press Enter");
+          stdin
+            .lock()
+            .read_line(&mut String::new())
+            .unwrap_or_else(|e| {
+              eprintln!("{e}");
+              exit(1)
+            });
+          let mut sum = 0u32;
+          for _ in 0..samples {
+            sum += sa818::get_rssi(&mut serial_io).unwrap_or_else(|e| {
+              eprintln!("{e}");
+              exit(1)
+            }) as u32;
+            thread::sleep(Duration::from_millis(100));
+          }
+          let raw = sum as f32 / samples as f32;
+          println!("{level} dBm -> RSSI {raw:.1}");
+          // Calibration points need distinct raw values, measure this level again.
+          if let Some((_, other)) = points.iter().find(|point| point.0 == raw) {
+            eprintln!("RSSI {raw:.1} was already read at {other} dBm, check the generator");
+            continue;
+          }
+          points.push((raw, level));
+          break;
+        }
+      }
+      let calibration = Calibration::new(points).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1)
+      });
+      calibration.write_file(&output).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1)
+      });
+      println!("Calibration written to {}", output.display());
     }
     Some(Commands::Channel {
       bandwidth,
@@ -117,11 +428,9 @@ fn main() {
       receive_group,
       transmit_group,
       squelch,
+      band_plan,
     }) => {
-      let bandwidth = match bandwidth {
-        Bandwidth::Wide => FmBandwidth::Wide,
-        Bandwidth::Narrow => FmBandwidth::Narrow,
-      };
+      let bandwidth = bandwidth.into();
       match mode {
         Some(Mode::Simplex { frequency }) => {
           let mut chan = Channel::default()
@@ -162,8 +471,7 @@ fn main() {
             setup_tx_group(transmit_group, &mut chan, frequency);
           }
 
-          dbg!(&chan);
-          chan.write_config(&mut serial_io).unwrap();
+          write_channel(chan, &band_plan, &mut serial_io);
         }
         Some(Mode::Halfduplex {
           rxfrequency,
@@ -204,16 +512,111 @@ fn main() {
           if let Some(transmit_group) = transmit_group {
             setup_tx_group(transmit_group, &mut chan, txfrequency)
           }
-          dbg!(&chan);
-          chan.write_config(&mut serial_io).unwrap();
+          write_channel(chan, &band_plan, &mut serial_io);
+        }
+        Some(Mode::Repeater {
+          frequency,
+          shift,
+          split,
+          region,
+          reverse,
+        }) => {
+          let mut chan = Channel::default()
+            .bandwidth(bandwidth)
+            .squelch(squelch)
+            .unwrap_or_else(|e| {
+              eprintln!("{e}");
+              exit(1);
+            });
+          chan.set_rx(FreqConf::new(frequency).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            exit(1);
+          }));
+          chan.set_tx(FreqConf::new(frequency).unwrap());
+          if let Some(tone) = ctcss.map(|ctcss| sa818::group_call::parse_ctcss(&ctcss)) {
+            let tone = tone.unwrap_or_else(|e| {
+              eprintln!("{e}");
+              exit(1);
+            });
+            chan.set_rx(FreqConf::with_group_sel(frequency, tone).unwrap());
+            chan.set_tx(FreqConf::with_group_sel(frequency, tone).unwrap());
+          }
+          if let Some(tone) = dcs.map(sa818::group_call::parse_dcs) {
+            let tone = tone.unwrap_or_else(|e| {
+              eprintln!("{e}");
+              exit(1);
+            });
+            chan.set_rx(FreqConf::with_group_sel(frequency, tone).unwrap());
+            chan.set_tx(FreqConf::with_group_sel(frequency, tone).unwrap());
+          }
+          if let Some(receive_group) = receive_group {
+            setup_rx_group(receive_group, &mut chan, frequency)
+          }
+          if let Some(transmit_group) = transmit_group {
+            setup_tx_group(transmit_group, &mut chan, frequency)
+          }
+          let chan = match (split, shift) {
+            (Some(tx_frequency), _) => chan.split(tx_frequency),
+            (None, Shift::Up) => chan.shift_up(region.into()),
+            (None, Shift::Down) => chan.shift_down(region.into()),
+            (None, Shift::Offset(offset)) => chan.offset(offset),
+          }
+          .map(|chan| if reverse { chan.reverse() } else { chan })
+          .unwrap_or_else(|e| {
+            eprintln!("{e}");
+            exit(1);
+          });
+          println!(
+            "RX {:.4} MHz, TX {:.4} MHz",
+            chan.rx_frequency().unwrap(),
+            chan.tx_frequency().unwrap()
+          );
+          write_channel(chan, &band_plan, &mut serial_io);
+        }
+        Some(Mode::Pmr446 { channel }) => {
+          let chan = match (ctcss, dcs) {
+            (Some(ctcss), _) => Some(sa818::group_call::parse_ctcss(&ctcss)),
+            (_, Some(dcs)) => Some(sa818::group_call::parse_dcs(dcs)),
+            _ => None,
+          }
+          .transpose()
+          .and_then(|tone| Channel::pmr446(channel, tone))
+          .and_then(|chan| chan.bandwidth(bandwidth).squelch(squelch))
+          .unwrap_or_else(|e| {
+            eprintln!("{e}");
+            exit(1);
+          });
+          println!(
+            "PMR446 channel {} at {:.5} MHz, drive the H/L pin low",
+            channel,
+            chan.rx_frequency().unwrap()
+          );
+          write_channel(chan, &band_plan, &mut serial_io);
+        }
+        None => {
+          eprintln!("Give a channel mode, see sa818cli channel --help");
+          exit(1)
         }
-        None => todo!(),
       }
     }
     None => {}
   }
 }
 
+fn write_channel(
+  chan: Channel,
+  band_plan: &common::BandPlanArgs,
+  serial_io: &mut Box<dyn SerialPort>,
+) {
+  band_plan
+    .apply(chan)
+    .and_then(|chan| chan.write_config(serial_io))
+    .unwrap_or_else(|e| {
+      eprintln!("{e}");
+      exit(1)
+    });
+}
+
 fn setup_tx_group(transmit_group: TxGroupSel, chan: &mut Channel, frequency: f32) {
   if let Some(cts) = &transmit_group.tcts {
     let ctcss = sa818::group_call::parse_ctcss(cts).unwrap_or_else(|e| {
@@ -250,13 +653,12 @@ fn setup_rx_group(receive_group: RxGroupSel, chan: &mut Channel, frequency: f32)
   }
 }
 
-fn open_serial(serial_port: String) -> Box<dyn SerialPort> {
-  let port = serialport::new(serial_port, 9600)
-    .timeout(Duration::from_millis(1000))
-    .data_bits(serialport::DataBits::Eight)
-    .parity(serialport::Parity::None)
-    .stop_bits(serialport::StopBits::One)
-    .open()
-    .expect("Failed to open port");
-  return port;
+fn load_calibration(calibration: Option<PathBuf>) -> Calibration {
+  match calibration {
+    Some(path) => Calibration::from_file(path).unwrap_or_else(|e| {
+      eprintln!("{e}");
+      exit(1)
+    }),
+    None => Calibration::default(),
+  }
 }
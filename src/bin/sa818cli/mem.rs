@@ -0,0 +1,183 @@
+use crate::{common, Bandwidth};
+use clap::{Args, Subcommand};
+use sa818::{
+  channel::{Channel, FmBandwidth, FreqConf},
+  channel_bank::ChannelBank,
+  chirp,
+  device::Sa818,
+  group_call::parse_tone,
+};
+use serialport::SerialPort;
+use std::{env, path::PathBuf};
+
+#[derive(Args)]
+pub struct MemArgs {
+  /// Channel memory file, defaults to ~/.config/sa818/channels.csv
+  #[arg(long, value_name = "FILE")]
+  bank: Option<PathBuf>,
+  #[command(subcommand)]
+  command: MemCommand,
+}
+
+#[derive(Subcommand)]
+enum MemCommand {
+  /// store a channel under a name
+  Add {
+    name: String,
+    /// Receive frequency in MHz
+    rx: f32,
+    /// Transmit frequency in MHz, defaults to the receive frequency
+    #[arg(long)]
+    tx: Option<f32>,
+    /// Tone for both rx and tx, a ctcss frequency like 88.5 or a dcs code like 023N
+    #[arg(long, short, conflicts_with_all = ["rx_tone", "tx_tone"])]
+    tone: Option<String>,
+    /// Receive tone
+    #[arg(long)]
+    rx_tone: Option<String>,
+    /// Transmit tone
+    #[arg(long)]
+    tx_tone: Option<String>,
+    #[arg(long, short, value_enum, default_value = "narrow")]
+    bandwidth: Bandwidth,
+    #[arg(long, short, default_value = "4")]
+    squelch: u8,
+    #[arg(long, short, default_value = "")]
+    notes: String,
+  },
+  /// list stored channels
+  List,
+  /// remove a channel by name or index
+  Rm { channel: String },
+  /// tune the module to a stored channel by name or index
+  Recall {
+    channel: String,
+    #[command(flatten)]
+    band_plan: common::BandPlanArgs,
+  },
+  /// add the channels of a CHIRP CSV export
+  Import { file: PathBuf },
+  /// write the stored channels as a CHIRP CSV
+  Export { file: PathBuf },
+}
+
+/// Run a `mem` command, `open` is only called when the radio is needed.
+pub fn run<F>(args: MemArgs, open: F) -> Result<(), String>
+where
+  F: FnOnce() -> Result<Box<dyn SerialPort>, String>,
+{
+  let path = match args.bank {
+    Some(path) => path,
+    None => default_bank()?,
+  };
+  let mut bank = ChannelBank::from_file(&path)?;
+  match args.command {
+    MemCommand::Add {
+      name,
+      rx,
+      tx,
+      tone,
+      rx_tone,
+      tx_tone,
+      bandwidth,
+      squelch,
+      notes,
+    } => {
+      let rx_tone = rx_tone.or(tone.clone());
+      let tx_tone = tx_tone.or(tone);
+      let channel = Channel::default()
+        .bandwidth(bandwidth.into())
+        .squelch(squelch)?
+        .rx(freq_conf(rx, rx_tone.as_deref())?)
+        .tx(freq_conf(tx.unwrap_or(rx), tx_tone.as_deref())?);
+      let index = bank.add(&name, channel, &notes)?;
+      bank.write_file(&path)?;
+      println!("Stored {} as channel {}", name, index);
+    }
+    MemCommand::List => {
+      println!(
+        "{:>3}  {:<12} {:>9} {:>9} {:>6} {:>6} {:<6} {:>3}  NOTES",
+        "#", "NAME", "RX", "TX", "RXTONE", "TXTONE", "BW", "SQ"
+      );
+      for (index, memory) in bank.iter().enumerate() {
+        let channel = &memory.channel;
+        let tone = |conf: Option<&FreqConf>| {
+          conf
+            .and_then(|conf| conf.group_sel)
+            .map(|g| g.tone_string())
+            .unwrap_or_else(|| String::from("-"))
+        };
+        let bandwidth = match channel.fm_bandwidth() {
+          FmBandwidth::Wide => "wide",
+          FmBandwidth::Narrow => "narrow",
+        };
+        println!(
+          "{:>3}  {:<12} {:>9.4} {:>9.4} {:>6} {:>6} {:<6} {:>3}  {}",
+          index,
+          memory.name,
+          channel.rx_frequency().unwrap_or_default(),
+          channel.tx_frequency().unwrap_or_default(),
+          tone(channel.rx_conf()),
+          tone(channel.tx_conf()),
+          bandwidth,
+          channel.squelch_level(),
+          memory.notes
+        );
+      }
+    }
+    MemCommand::Rm { channel } => {
+      let memory = bank.remove(&channel)?;
+      bank.write_file(&path)?;
+      println!("Removed {}", memory.name);
+    }
+    MemCommand::Recall { channel, band_plan } => {
+      let (_, memory) = bank
+        .lookup(&channel)
+        .ok_or(format!("No channel {} in memory", channel))?;
+      let channel = band_plan.apply(memory.channel.clone())?;
+      let device = Sa818::new(open()?);
+      device.write_config(&channel)?;
+      println!(
+        "Tuned to {} ({:.4} MHz)",
+        memory.name,
+        memory.channel.rx_frequency().unwrap_or_default()
+      );
+    }
+    MemCommand::Import { file } => {
+      let import = chirp::import_file(&file)?;
+      let mut added = 0;
+      for memory in import.channels {
+        match bank.add(&memory.name, memory.channel, &memory.notes) {
+          Ok(_) => added += 1,
+          Err(e) => eprintln!("Skipped {}: {}", memory.name, e),
+        }
+      }
+      for rejected in &import.rejected {
+        eprintln!("Skipped {}", rejected);
+      }
+      bank.write_file(&path)?;
+      println!(
+        "Imported {} channels, {} rows can't be used on a SA818",
+        added,
+        import.rejected.len()
+      );
+    }
+    MemCommand::Export { file } => {
+      chirp::export_file(bank.iter(), &file)?;
+      println!("Exported {} channels to {}", bank.len(), file.display());
+    }
+  }
+  Ok(())
+}
+
+fn freq_conf(frequency: f32, tone: Option<&str>) -> Result<FreqConf, String> {
+  match tone {
+    Some(tone) => FreqConf::with_group_sel(frequency, parse_tone(tone)?),
+    None => FreqConf::new(frequency),
+  }
+}
+
+pub fn default_bank() -> Result<PathBuf, String> {
+  let home = env::var_os("HOME").ok_or("HOME is not set, use --bank")?;
+  Ok(PathBuf::from(home).join(".config/sa818/channels.csv"))
+}
@@ -0,0 +1,88 @@
+use crate::{common, Bandwidth};
+use clap::Args;
+use sa818::{
+  channel::{Channel, FreqConf},
+  device::Sa818,
+  filter_config::FilterConfig,
+  pocsag::{Page, PocsagEncoder},
+  ptt::Transmitter,
+};
+use serialport::SerialPort;
+
+#[derive(Args)]
+pub struct PageArgs {
+  /// Pager address
+  #[arg(long, short)]
+  ric: u32,
+  /// Message text, left out for a tone only page
+  #[arg(required_unless_present = "tone")]
+  message: Option<String>,
+  /// Send the message as a numeric page
+  #[arg(long, short)]
+  numeric: bool,
+  /// Send only an alert
+  #[arg(long, conflicts_with_all = ["message", "numeric"])]
+  tone: bool,
+  /// Function bits 0-3, defaults to 0 for numeric and tone, 3 for text
+  #[arg(long, short)]
+  function: Option<u8>,
+  /// Data rate in baud: 512, 1200 or 2400
+  #[arg(long, default_value = "1200")]
+  bitrate: u32,
+  /// Swap the FSK shift for pagers expecting the other polarity
+  #[arg(long)]
+  invert: bool,
+  /// Audio level relative to full scale, sets the deviation
+  #[arg(long, default_value = "0.5")]
+  level: f32,
+  /// Tune the module to FREQUENCY in MHz before paging
+  #[arg(long)]
+  frequency: Option<f32>,
+  #[arg(long, value_enum, default_value = "wide")]
+  bandwidth: Bandwidth,
+  #[command(flatten)]
+  band_plan: common::BandPlanArgs,
+  #[command(flatten)]
+  ptt: common::PttArgs,
+  #[command(flatten)]
+  audio: common::AudioArgs,
+}
+
+pub fn run(serial_io: Box<dyn SerialPort>, baud: u32, args: PageArgs) -> Result<(), String> {
+  let text = args.message.as_deref().unwrap_or_default();
+  let mut page = if args.tone {
+    Page::tone(args.ric, 0)?
+  } else if args.numeric {
+    Page::numeric(args.ric, text)?
+  } else {
+    Page::alphanumeric(args.ric, text)?
+  };
+  if let Some(function) = args.function {
+    page = page.function(function)?;
+  }
+  let audio = PocsagEncoder::new(args.audio.rate)
+    .baud(args.bitrate)?
+    .level(args.level)
+    .inverted(args.invert)
+    .encode(&[page]);
+
+  let ptt = args.ptt.open(serial_io.as_ref(), baud)?;
+  let device = Sa818::new(serial_io);
+  if let Some(frequency) = args.frequency {
+    let channel = Channel::default()
+      .bandwidth(args.bandwidth.into())
+      .rx(FreqConf::new(frequency)?)
+      .tx(FreqConf::new(frequency)?);
+    device.write_config(&args.band_plan.apply(channel)?)?;
+    // Emphasis and the voice filters would round off the data.
+    device.write_filter(&FilterConfig::flat())?;
+  }
+  let mut transmitter = Transmitter::new(ptt, args.audio.sink()?);
+  println!(
+    "Paging {} at {} baud, {:.1} s",
+    args.ric,
+    args.bitrate,
+    transmitter.duration(audio.len()).as_secs_f32()
+  );
+  transmitter.transmit(&audio)
+}
@@ -0,0 +1,164 @@
+use crate::{common, mem, Bandwidth};
+use clap::{Args, ValueEnum};
+use sa818::{
+  audio::AudioSource,
+  channel::{Channel, FreqConf},
+  channel_bank::ChannelBank,
+  device::Sa818,
+  group_call::parse_tone,
+  recorder::{ChannelInfo, RecordFormat, Recorder, RecorderEvent},
+  timestamp,
+  tnc::ChannelBusy,
+};
+use serialport::SerialPort;
+use std::{
+  path::PathBuf,
+  time::{Duration, SystemTime},
+};
+
+#[derive(Args)]
+pub struct RecordArgs {
+  /// Directory for the recordings
+  #[arg(long, short, default_value = "recordings")]
+  dir: PathBuf,
+  /// File format
+  #[arg(long, short, value_enum, default_value = "wav")]
+  format: Format,
+  /// Tune to a stored channel by name or index, recordings are named after it
+  #[arg(long, short, conflicts_with = "frequency")]
+  channel: Option<String>,
+  /// Channel memory file, defaults to ~/.config/sa818/channels.csv
+  #[arg(long, value_name = "FILE", requires = "channel")]
+  bank: Option<PathBuf>,
+  /// Tune to FREQUENCY in MHz
+  #[arg(long)]
+  frequency: Option<f32>,
+  /// Receive tone, a ctcss frequency like 88.5 or a dcs code like 023N
+  #[arg(long, requires = "frequency")]
+  tone: Option<String>,
+  #[arg(long, short, value_enum, default_value = "narrow")]
+  bandwidth: Bandwidth,
+  /// Name in the file names, defaults to the channel name or frequency
+  #[arg(long)]
+  name: Option<String>,
+  #[command(flatten)]
+  squelch: common::SquelchArgs,
+  /// Raw RSSI at or above which a carrier is present
+  #[arg(long, short = 't', default_value = "60")]
+  threshold: u8,
+  /// Time the squelch stays closed before a recording ends
+  #[arg(long, default_value = "2s", value_parser = common::parse_duration)]
+  hang_time: Duration,
+  /// Delete recordings shorter than this
+  #[arg(long, default_value = "0s", value_parser = common::parse_duration)]
+  min_duration: Duration,
+  /// Time between squelch checks
+  #[arg(long, default_value = "100ms", value_parser = common::parse_interval)]
+  poll: Duration,
+  #[command(flatten)]
+  band_plan: common::BandPlanArgs,
+  #[command(flatten)]
+  audio: common::AudioArgs,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Format {
+  Wav,
+  Flac,
+}
+
+impl From<Format> for RecordFormat {
+  fn from(format: Format) -> Self {
+    match format {
+      Format::Wav => RecordFormat::Wav,
+      Format::Flac => RecordFormat::Flac,
+    }
+  }
+}
+
+pub fn run(serial_io: Box<dyn SerialPort>, args: RecordArgs) -> Result<(), String> {
+  let mut squelch = args.squelch.open(serial_io.as_ref())?;
+  let device = Sa818::new(serial_io);
+
+  let (name, channel) = match (&args.channel, args.frequency) {
+    (Some(key), _) => {
+      let path = match &args.bank {
+        Some(path) => path.clone(),
+        None => mem::default_bank()?,
+      };
+      let bank = ChannelBank::from_file(&path)?;
+      let (_, memory) = bank
+        .lookup(key)
+        .ok_or(format!("No channel {} in memory", key))?;
+      (Some(memory.name.clone()), Some(memory.channel.clone()))
+    }
+    (None, Some(frequency)) => {
+      let rx = match &args.tone {
+        Some(tone) => FreqConf::with_group_sel(frequency, parse_tone(tone)?)?,
+        None => FreqConf::new(frequency)?,
+      };
+      let channel = Channel::default()
+        .bandwidth(args.bandwidth.into())
+        .rx(rx)
+        .tx(FreqConf::new(frequency)?);
+      (None, Some(channel))
+    }
+    (None, None) => (None, None),
+  };
+  let mut info = match &channel {
+    Some(channel) => {
+      device.write_config(&args.band_plan.apply(channel.clone())?)?;
+      ChannelInfo::new(name.as_deref(), channel)
+    }
+    None => ChannelInfo::default(),
+  };
+  if args.name.is_some() {
+    info.name = args.name;
+  }
+
+  let mut source = args.audio.source()?;
+  let mut recorder = Recorder::new(&args.dir, source.sample_rate())?
+    .format(args.format.into())
+    .channel(info)
+    .hang_time(args.hang_time)
+    .min_duration(args.min_duration);
+  let mut block = vec![0.0; (source.sample_rate() as f64 * args.poll.as_secs_f64()) as usize];
+  println!("Recording to {}", args.dir.display());
+  loop {
+    let at = SystemTime::now();
+    let n = source.read_exact(&mut block)?;
+    if n == 0 {
+      break;
+    }
+    let (open, rssi) = match &mut squelch {
+      Some(pin) => (pin.busy()?, device.get_rssi().ok()),
+      None => {
+        let rssi = device.get_rssi()?;
+        (rssi >= args.threshold, Some(rssi))
+      }
+    };
+    for event in recorder.push(&block[..n], open, rssi, at)? {
+      report(event);
+    }
+  }
+  if let Some(event) = recorder.finish()? {
+    report(event);
+  }
+  Ok(())
+}
+
+fn report(event: RecorderEvent) {
+  let now = timestamp::rfc3339(SystemTime::now());
+  match event {
+    RecorderEvent::Started(path) => println!("{now} recording {}", path.display()),
+    RecorderEvent::Finished(info) => println!(
+      "{now} saved {} ({:.1} s, peak RSSI {})",
+      info.path.display(),
+      info.duration.as_secs_f32(),
+      info
+        .peak_rssi
+        .map_or_else(|| String::from("-"), |rssi| rssi.to_string())
+    ),
+    RecorderEvent::Discarded(path) => println!("{now} discarded {}", path.display()),
+  }
+}
@@ -0,0 +1,120 @@
+use crate::{common, Bandwidth};
+use clap::{Args, ValueEnum};
+use sa818::{
+  device::Sa818,
+  scanner::{self, CarrierMode, Scanner},
+  timestamp,
+};
+use serialport::SerialPort;
+use std::{
+  io::{self, BufRead},
+  time::Duration,
+};
+
+#[derive(Args)]
+pub struct ScanArgs {
+  /// Start frequency in MHz
+  start: f32,
+  /// Stop frequency in MHz
+  stop: f32,
+  /// Channel step, e.g. 12.5k or 25k
+  #[arg(long, default_value = "12.5k", value_parser = common::parse_step)]
+  step: f32,
+  #[arg(long, short, value_enum, default_value = "narrow")]
+  bandwidth: Bandwidth,
+  /// Listening time per channel
+  #[arg(long, short, default_value = "100ms", value_parser = common::parse_duration)]
+  dwell: Duration,
+  /// Raw RSSI at or above which a channel is active
+  #[arg(long, short = 't', default_value = "60")]
+  threshold: u8,
+  /// What to do on an active channel
+  #[arg(long, value_enum, default_value = "sweep")]
+  on_carrier: OnCarrier,
+  /// Hang time after the carrier drops, or stay time for `timed`
+  #[arg(long, default_value = "2s", value_parser = common::parse_duration)]
+  hang: Duration,
+  /// Frequencies to skip in MHz, comma separated
+  #[arg(long, short, value_delimiter = ',')]
+  lockout: Vec<f32>,
+  /// Number of passes over the range, 0 scans until interrupted
+  #[arg(long, short, default_value = "1")]
+  passes: u32,
+  #[command(flatten)]
+  band_plan: common::BandPlanArgs,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum OnCarrier {
+  /// Note the activity and keep scanning
+  Sweep,
+  /// Stay until the carrier drops, resume after the hang time
+  Carrier,
+  /// Stay for the hang time, then resume
+  Timed,
+  /// Stop until Enter is pressed
+  Hold,
+}
+
+pub fn run(device: Sa818<Box<dyn SerialPort>>, args: ScanArgs) -> Result<(), String> {
+  let mode = match args.on_carrier {
+    OnCarrier::Sweep => CarrierMode::Sweep,
+    OnCarrier::Carrier => CarrierMode::Carrier { hang: args.hang },
+    OnCarrier::Timed => CarrierMode::Timed(args.hang),
+    OnCarrier::Hold => CarrierMode::Hold,
+  };
+  let channels = scanner::range_channels(args.start, args.stop, args.step, args.bandwidth.into())?;
+  let mut scanner = Scanner::channels(device, args.band_plan.apply_all(channels)?)?
+    .dwell(args.dwell)
+    .threshold(args.threshold)
+    .mode(mode);
+  for frequency in args.lockout {
+    scanner.lockout(frequency);
+  }
+
+  let mut pass = 0;
+  while args.passes == 0 || pass < args.passes {
+    pass += 1;
+    for _ in 0..scanner.unlocked_count() {
+      let step = scanner.step()?;
+      if step.active {
+        println!("{:.4} MHz  RSSI {}", step.frequency, step.rssi);
+      }
+      if scanner.held().is_some() {
+        println!(
+          "Holding on {:.4} MHz, press Enter to resume",
+          step.frequency
+        );
+        io::stdin()
+          .lock()
+          .read_line(&mut String::new())
+          .map_err(|e| e.to_string())?;
+        scanner.resume();
+      }
+    }
+  }
+
+  let active: Vec<_> = scanner.results().iter().filter(|r| r.hits > 0).collect();
+  if active.is_empty() {
+    println!("No activity above RSSI {}", args.threshold);
+    return Ok(());
+  }
+  println!(
+    "{:>12}  {:>5}  {:>4}  {:>4}  last heard",
+    "frequency", "hits", "peak", "last"
+  );
+  for result in active {
+    println!(
+      "{:>12.4}  {:>5}  {:>4}  {:>4}  {}",
+      result.frequency,
+      result.hits,
+      result.peak,
+      result.last,
+      result
+        .last_active
+        .map(timestamp::rfc3339)
+        .unwrap_or_default()
+    );
+  }
+  Ok(())
+}
@@ -0,0 +1,56 @@
+use clap::Args;
+use sa818::{audio::AudioSource, dcs_decoder::DcsDecoder, tone_scan::CtcssScanner, wav::WavReader};
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct ToneScanArgs {
+  /// Recording of the received audio
+  #[arg(long, value_name = "FILE")]
+  wav: PathBuf,
+  /// Minimum confidence, 0 to 1, to report a tone
+  #[arg(long, default_value = "0.5")]
+  min_confidence: f32,
+  /// The receiver's audio is inverted, swapping the DCS polarity
+  #[arg(long)]
+  invert: bool,
+}
+
+/// Print the DCS code or CTCSS tone found as a dcs or ctcss argument, details
+/// go to stderr.
+pub fn run(args: ToneScanArgs) -> Result<(), String> {
+  // A DCS bit stream has energy at CTCSS frequencies too, so look for it first.
+  let mut source = WavReader::open(&args.wav)?;
+  let decoder = DcsDecoder::new(source.sample_rate()).inverted(args.invert);
+  if let Some(code) = decoder.scan(&mut source)? {
+    if code.confidence >= args.min_confidence {
+      println!("{}", code.dcs);
+      eprintln!(
+        "confidence {:.0}%, level {:.1} dBFS",
+        code.confidence * 100.0,
+        20.0 * code.level.log10()
+      );
+      return Ok(());
+    }
+  }
+
+  let mut source = WavReader::open(&args.wav)?;
+  let scanner = CtcssScanner::new(source.sample_rate());
+  match scanner.scan(&mut source)? {
+    Some(tone) if tone.confidence >= args.min_confidence => {
+      println!("{:.1}", tone.ctcss.hz());
+      eprintln!(
+        "{}, confidence {:.0}%, level {:.1} dBFS",
+        tone.ctcss,
+        tone.confidence * 100.0,
+        20.0 * tone.level.log10()
+      );
+      Ok(())
+    }
+    Some(tone) => Err(format!(
+      "No clear tone, best guess {} with {:.0}% confidence",
+      tone.ctcss,
+      tone.confidence * 100.0
+    )),
+    None => Err(String::from("No CTCSS tone or DCS code found")),
+  }
+}
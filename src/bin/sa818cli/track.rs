@@ -0,0 +1,194 @@
+use crate::common;
+use clap::Args;
+use sa818::{
+  aprs::{self, Position, Symbol},
+  ax25::Address,
+  device::Sa818,
+  nmea::NmeaReader,
+  ptt::Transmitter,
+  timestamp,
+  tracker::{Beaconing, SmartBeacon, Tracker},
+};
+use serialport::SerialPort;
+use std::{
+  io::{BufReader, Write},
+  net::TcpStream,
+  process::Command,
+  sync::{Arc, Mutex},
+  thread,
+  time::{Duration, Instant, SystemTime},
+};
+
+#[derive(Args)]
+pub struct TrackArgs {
+  /// Callsign with SSID to send as, e.g. N0CALL-9
+  #[arg(long, short)]
+  callsign: Address,
+  /// Fixed latitude in degrees, north positive
+  #[arg(long, allow_negative_numbers = true, requires = "lon")]
+  lat: Option<f64>,
+  /// Fixed longitude in degrees, east positive
+  #[arg(long, allow_negative_numbers = true, requires = "lat")]
+  lon: Option<f64>,
+  /// Fixed altitude in metres
+  #[arg(long, allow_negative_numbers = true, requires = "lat")]
+  altitude: Option<f64>,
+  /// Serial port of a GPS sending NMEA
+  #[arg(long, value_name = "SERIAL", conflicts_with_all = ["lat", "gpsd"])]
+  gps: Option<String>,
+  /// GPS serial baud rate
+  #[arg(long, default_value = "4800")]
+  gps_baud: u32,
+  /// gpsd to read NMEA from, e.g. localhost:2947
+  #[arg(long, value_name = "ADDR", conflicts_with = "lat")]
+  gpsd: Option<String>,
+  /// Time between beacons
+  #[arg(long, short, default_value = "10m", value_parser = common::parse_duration)]
+  interval: Duration,
+  /// Beacon by speed and turns instead of at a fixed interval
+  #[arg(long, conflicts_with = "interval")]
+  smart: bool,
+  /// Smart beaconing rate at high speed
+  #[arg(long, default_value = "2m", value_parser = common::parse_duration, requires = "smart")]
+  fast_rate: Duration,
+  /// Smart beaconing rate when stopped
+  #[arg(long, default_value = "30m", value_parser = common::parse_duration, requires = "smart")]
+  slow_rate: Duration,
+  /// Map symbol, table and code, e.g. /> for a car or /- for a house
+  #[arg(long, default_value = "/>")]
+  symbol: Symbol,
+  /// Comment sent with the position
+  #[arg(long, default_value = "")]
+  comment: String,
+  /// Digipeater path
+  #[arg(long, value_delimiter = ',', default_value = "WIDE1-1,WIDE2-1")]
+  path: Vec<Address>,
+  /// Send RSSI and supply voltage telemetry at this interval
+  #[arg(long, value_name = "INTERVAL", value_parser = common::parse_duration)]
+  telemetry: Option<Duration>,
+  /// Command printing the supply voltage in volts for the telemetry
+  #[arg(long, value_name = "COMMAND", requires = "telemetry")]
+  voltage_command: Option<String>,
+  /// Tune the module to FREQUENCY in MHz with flat audio for packet
+  #[arg(long)]
+  frequency: Option<f32>,
+  /// Raw RSSI at or above which the channel counts as busy
+  #[arg(long, default_value = "60")]
+  busy_threshold: u8,
+  #[command(flatten)]
+  band_plan: common::BandPlanArgs,
+  #[command(flatten)]
+  ptt: common::PttArgs,
+  #[command(flatten)]
+  audio: common::AudioArgs,
+}
+
+pub fn run(serial_io: Box<dyn SerialPort>, baud: u32, args: TrackArgs) -> Result<(), String> {
+  let ptt = args.ptt.open(serial_io.as_ref(), baud)?;
+  let device = Sa818::new(serial_io);
+  if let Some(frequency) = args.frequency {
+    device.write_config(&args.band_plan.apply(aprs::channel(frequency)?)?)?;
+    device.write_filter(&aprs::filter_config())?;
+  }
+  let beaconing = if args.smart {
+    Beaconing::Smart(SmartBeacon {
+      fast_rate: args.fast_rate,
+      slow_rate: args.slow_rate,
+      ..SmartBeacon::default()
+    })
+  } else {
+    Beaconing::Fixed(args.interval)
+  };
+  let transmitter = Transmitter::new(ptt, args.audio.sink()?);
+  let mut tracker = Tracker::new(device, transmitter, args.callsign)
+    .path(args.path)?
+    .symbol(args.symbol)
+    .comment(&args.comment)
+    .beaconing(beaconing)
+    .busy_threshold(args.busy_threshold);
+  if let Some(interval) = args.telemetry {
+    tracker = tracker.telemetry(interval);
+  }
+  if let Some(command) = args.voltage_command {
+    tracker = tracker.voltage(move || read_voltage(&command));
+  }
+
+  let fix = match (args.lat, args.lon) {
+    (Some(lat), Some(lon)) => {
+      let mut position = Position::new(lat, lon)?;
+      position.altitude = args.altitude;
+      Arc::new(Mutex::new(Some((Instant::now(), position))))
+    }
+    _ => follow_gps(&args.gps, args.gps_baud, &args.gpsd)?,
+  };
+  loop {
+    let now = Instant::now();
+    let position = fix
+      .lock()
+      .unwrap()
+      .filter(|(at, _)| args.lat.is_some() || now.duration_since(*at) < Duration::from_secs(30))
+      .map(|(_, position)| position);
+    match tracker.poll(now, position.as_ref()) {
+      Ok(frames) => {
+        let time = timestamp::rfc3339(SystemTime::now());
+        for frame in frames {
+          println!("{time} {frame}");
+        }
+      }
+      Err(e) => eprintln!("{e}"),
+    }
+    thread::sleep(Duration::from_secs(1));
+  }
+}
+
+/// The latest position and when it came.
+type Fix = Arc<Mutex<Option<(Instant, Position)>>>;
+
+/// Keep the latest GPS fix updated from a thread.
+fn follow_gps(gps: &Option<String>, baud: u32, gpsd: &Option<String>) -> Result<Fix, String> {
+  let reader: Box<dyn std::io::Read + Send> = match (gps, gpsd) {
+    (Some(port), _) => common::open_serial(port, baud)?,
+    (None, Some(address)) => {
+      let mut stream = TcpStream::connect(address)
+        .map_err(|e| format!("Failed to connect to {}: {}", address, e))?;
+      stream
+        .write_all(b"?WATCH={\"enable\":true,\"nmea\":true}\n")
+        .map_err(|e| format!("Failed to start gpsd: {}", e))?;
+      Box::new(stream)
+    }
+    (None, None) => return Err(String::from("Give --lat and --lon, --gps or --gpsd")),
+  };
+  let fix = Arc::new(Mutex::new(None));
+  let latest = fix.clone();
+  thread::spawn(move || {
+    let mut nmea = NmeaReader::new(BufReader::new(reader));
+    loop {
+      match nmea.next_position() {
+        Ok(Some(position)) => *latest.lock().unwrap() = Some((Instant::now(), position)),
+        Ok(None) => {
+          eprintln!("GPS stream ended");
+          return;
+        }
+        Err(e) => {
+          eprintln!("{e}");
+          return;
+        }
+      }
+    }
+  });
+  Ok(fix)
+}
+
+fn read_voltage(command: &str) -> Result<f32, String> {
+  let mut words = command.split_whitespace();
+  let program = words.next().ok_or("Empty voltage command")?;
+  let output = Command::new(program)
+    .args(words)
+    .output()
+    .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+  let text = String::from_utf8_lossy(&output.stdout);
+  text
+    .trim()
+    .parse()
+    .map_err(|_| format!("{} printed {:?}, not a voltage", command, text.trim()))
+}
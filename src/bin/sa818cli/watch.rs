@@ -0,0 +1,83 @@
+use crate::{common, Bandwidth};
+use clap::Args;
+use sa818::{
+  channel::{Channel, FmBandwidth, FreqConf},
+  device::Sa818,
+  dual_watch::{DualWatch, WatchEvent},
+  timestamp,
+};
+use serialport::SerialPort;
+use std::time::{Duration, SystemTime};
+
+#[derive(Args)]
+pub struct WatchArgs {
+  /// Working channel frequency in MHz
+  #[arg(long)]
+  primary: f32,
+  /// Priority channel frequency in MHz
+  #[arg(long)]
+  priority: f32,
+  #[arg(long, short, value_enum, default_value = "narrow")]
+  bandwidth: Bandwidth,
+  /// Time on the primary channel between priority checks
+  #[arg(long, short, default_value = "2s", value_parser = common::parse_interval)]
+  interval: Duration,
+  /// Time spent sampling the priority channel
+  #[arg(long, default_value = "150ms", value_parser = common::parse_duration)]
+  sample_time: Duration,
+  /// Raw RSSI at or above which the priority channel is active
+  #[arg(long, short = 't', default_value = "60")]
+  threshold: u8,
+  #[command(flatten)]
+  band_plan: common::BandPlanArgs,
+}
+
+pub fn run(device: Sa818<Box<dyn SerialPort>>, args: WatchArgs) -> Result<(), String> {
+  let primary = args
+    .band_plan
+    .apply(simplex(args.primary, args.bandwidth.into())?)?;
+  let priority = args
+    .band_plan
+    .apply(simplex(args.priority, args.bandwidth.into())?)?;
+  let mut watch = DualWatch::new(device, primary, priority)
+    .interval(args.interval)
+    .sample_time(args.sample_time)
+    .threshold(args.threshold);
+  watch.start()?;
+  println!(
+    "Listening on {:.4} MHz, priority {:.4} MHz",
+    args.primary, args.priority
+  );
+  let mut active = false;
+  for event in watch {
+    let now = timestamp::rfc3339(SystemTime::now());
+    match event? {
+      WatchEvent::PriorityIdle { .. } => {}
+      WatchEvent::PriorityActive { rssi } if !active => {
+        active = true;
+        println!(
+          "{now} priority {:.4} MHz active, RSSI {rssi}",
+          args.priority
+        )
+      }
+      WatchEvent::PriorityActive { .. } => {}
+      WatchEvent::PriorityEnded { rssi } => {
+        active = false;
+        println!(
+          "{now} priority quiet (RSSI {rssi}), back on {:.4} MHz",
+          args.primary
+        )
+      }
+    }
+  }
+  Ok(())
+}
+
+fn simplex(frequency: f32, bandwidth: FmBandwidth) -> Result<Channel, String> {
+  Ok(
+    Channel::default()
+      .bandwidth(bandwidth)
+      .rx(FreqConf::new(frequency)?)
+      .tx(FreqConf::new(frequency)?),
+  )
+}
@@ -0,0 +1,175 @@
+use ratatui::{
+  prelude::*,
+  symbols::Marker,
+  widgets::{Axis, Block, Borders, Chart, Dataset, GraphType},
+};
+use sa818::rssi::{RssiSample, RssiStats};
+use std::{
+  collections::VecDeque,
+  time::{Duration, SystemTime},
+};
+
+/// Time spans the chart can be switched between.
+const SPANS: [Duration; 5] = [
+  Duration::from_secs(30),
+  Duration::from_secs(60),
+  Duration::from_secs(5 * 60),
+  Duration::from_secs(15 * 60),
+  Duration::from_secs(60 * 60),
+];
+
+/// RSSI samples kept for the chart, up to the longest span.
+pub struct History {
+  samples: VecDeque<RssiSample>,
+  span: usize,
+  paused_at: Option<SystemTime>,
+  peak: Option<u8>,
+  pub squelch: u8,
+}
+
+impl History {
+  pub fn new(squelch: u8) -> Self {
+    Self {
+      samples: VecDeque::new(),
+      span: 1,
+      paused_at: None,
+      peak: None,
+      squelch,
+    }
+  }
+
+  /// Samples keep being recorded while paused, only the view is frozen.
+  pub fn push(&mut self, sample: RssiSample) {
+    self.samples.push_back(sample);
+    self.peak = self.peak.max(Some(sample.value));
+    let longest = SPANS[SPANS.len() - 1];
+    while let Some(oldest) = self.samples.front() {
+      match sample.timestamp.duration_since(oldest.timestamp) {
+        Ok(age) if age > longest => {
+          self.samples.pop_front();
+        }
+        _ => break,
+      }
+    }
+  }
+
+  pub fn span(&self) -> Duration {
+    SPANS[self.span]
+  }
+
+  pub fn zoom_in(&mut self) {
+    self.span = self.span.saturating_sub(1);
+  }
+
+  pub fn zoom_out(&mut self) {
+    self.span = (self.span + 1).min(SPANS.len() - 1);
+  }
+
+  pub fn toggle_pause(&mut self) {
+    self.paused_at = match self.paused_at {
+      Some(_) => None,
+      None => Some(SystemTime::now()),
+    };
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.paused_at.is_some()
+  }
+
+  pub fn reset_peak(&mut self) {
+    self.peak = None;
+  }
+
+  pub fn peak(&self) -> Option<u8> {
+    self.peak
+  }
+
+  /// Statistics of the samples currently on screen.
+  pub fn stats(&self) -> Option<RssiStats> {
+    RssiStats::from_values(self.visible().map(|(_, value)| value as u8))
+  }
+
+  /// Visible samples as (seconds relative to the right edge, value).
+  fn visible(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+    let now = self.paused_at.unwrap_or_else(SystemTime::now);
+    let span = self.span().as_secs_f64();
+    self.samples.iter().filter_map(move |sample| {
+      let age = match now.duration_since(sample.timestamp) {
+        Ok(age) => age.as_secs_f64(),
+        Err(_) => return None,
+      };
+      (age <= span).then_some((-age, sample.value as f64))
+    })
+  }
+}
+
+pub struct HistoryChart<'a> {
+  pub history: &'a History,
+}
+
+impl Widget for HistoryChart<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let history = self.history;
+    let span = history.span().as_secs_f64();
+    let points: Vec<(f64, f64)> = history.visible().collect();
+    let level = |value: f64| vec![(-span, value), (0.0, value)];
+    let squelch = level(history.squelch as f64);
+    let stats = history.stats();
+    let average = stats.map(|s| level(s.mean as f64)).unwrap_or_default();
+    let peak = history.peak().map(|p| level(p as f64)).unwrap_or_default();
+
+    let datasets = vec![
+      Dataset::default()
+        .name("squelch")
+        .marker(Marker::Dot)
+        .graph_type(GraphType::Line)
+        .red()
+        .data(&squelch),
+      Dataset::default()
+        .name("avg")
+        .marker(Marker::Dot)
+        .graph_type(GraphType::Line)
+        .blue()
+        .data(&average),
+      Dataset::default()
+        .name("peak")
+        .marker(Marker::Dot)
+        .graph_type(GraphType::Line)
+        .magenta()
+        .data(&peak),
+      Dataset::default()
+        .name("rssi")
+        .marker(Marker::Braille)
+        .graph_type(GraphType::Line)
+        .yellow()
+        .data(&points),
+    ];
+
+    let title = match history.is_paused() {
+      true => " History (paused) ",
+      false => " History ",
+    };
+    Chart::new(datasets)
+      .block(Block::default().title(title).borders(Borders::ALL))
+      .x_axis(
+        Axis::default()
+          .bounds([-span, 0.0])
+          .labels(vec![format_span(history.span()).into(), "now".into()]),
+      )
+      .y_axis(Axis::default().bounds([0.0, 255.0]).labels(vec![
+        "0".into(),
+        "128".into(),
+        "255".into(),
+      ]))
+      .render(area, buf);
+  }
+}
+
+fn format_span(span: Duration) -> String {
+  let secs = span.as_secs();
+  if secs < 60 {
+    format!("-{}s", secs)
+  } else {
+    format!("-{}m", secs / 60)
+  }
+}
@@ -1,27 +1,153 @@
+#[path = "../common/mod.rs"]
+mod common;
+mod history;
+mod radio;
+mod spectrum;
 mod tui;
 
+use clap::Parser;
 use crossterm::event::{self, poll, Event, KeyCode, KeyEvent, KeyEventKind};
+use history::{History, HistoryChart};
+use radio::{Radio, RadioCommand, RadioEvent, Sweep};
 use ratatui::{
   prelude::*,
   style::Style,
   symbols::border,
-  widgets::{block::Title, Bar, BarChart, BarGroup, Block, Borders, Padding, Paragraph},
+  widgets::{block, block::Title, Bar, BarChart, BarGroup, Block, Borders, Paragraph},
 };
+use sa818::{
+  rssi::{Calibration, Rssi, RssiSample},
+  rssi_log::{LogRecord, RssiLogger},
+  scanner::ScanStep,
+};
+use spectrum::{Spectrum, SpectrumView};
 use std::{
   io::{self, Result},
-  time::Duration,
+  path::PathBuf,
+  process::exit,
+  sync::mpsc::TryRecvError,
+  time::{Duration, SystemTime},
 };
 
-#[derive(Debug, Default)]
+/// Watch the module's RSSI live, with a history chart, spectrum view and logging
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+struct Cli {
+  /// Specify serial port
+  #[arg(short, long, value_name = "SERIAL", default_value = "/dev/ttyS1")]
+  serial: String,
+  /// Serial baud rate
+  #[arg(short, long, default_value = "9600")]
+  baud: u32,
+  /// Time between RSSI polls, e.g. 250ms or 1s
+  #[arg(short, long, default_value = "250ms", value_parser = common::parse_interval)]
+  interval: Duration,
+  /// Calibration table written by `sa818cli calibrate`
+  #[arg(long, short, value_name = "FILE")]
+  calibration: Option<PathBuf>,
+  /// Raw RSSI level drawn as the squelch threshold line
+  #[arg(short = 't', long, default_value = "60")]
+  squelch_threshold: u8,
+  /// Simplex frequency to tune the module to in MHz, recorded in the log
+  #[arg(short, long)]
+  frequency: Option<f32>,
+  #[command(flatten)]
+  log: common::LogArgs,
+  /// Enable the spectrum view over START..STOP MHz
+  #[arg(long, num_args = 2, value_names = ["START", "STOP"])]
+  sweep: Option<Vec<f32>>,
+  /// Channel step of the spectrum sweep, e.g. 12.5k or 25k
+  #[arg(long, default_value = "12.5k", value_parser = common::parse_step)]
+  step: f32,
+  /// Listening time per channel of the spectrum sweep
+  #[arg(long, default_value = "50ms", value_parser = common::parse_duration)]
+  dwell: Duration,
+}
+
+impl Cli {
+  fn sweep(&self) -> Option<Sweep> {
+    self.sweep.as_ref().map(|range| Sweep {
+      start: range[0],
+      stop: range[1],
+      step: self.step,
+      dwell: self.dwell,
+    })
+  }
+}
+
+#[derive(PartialEq, Eq)]
+enum View {
+  Monitor,
+  Spectrum,
+}
+
+enum Connection {
+  Connecting,
+  Connected,
+  Disconnected(String),
+}
+
 pub struct App {
   rssi: u8,
+  history: History,
+  spectrum: Option<Spectrum>,
+  view: View,
+  calibration: Calibration,
+  calibrated: bool,
+  frequency: Option<f32>,
+  logger: Option<RssiLogger>,
+  serial: String,
+  connection: Connection,
+  version: Option<String>,
+  last_error: Option<String>,
+  radio: Radio,
   exit: bool,
 }
 
 impl App {
+  fn new(cli: Cli, radio: Radio) -> Self {
+    let spectrum = cli
+      .sweep()
+      .map(Spectrum::new)
+      .transpose()
+      .unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1)
+      });
+    let calibrated = cli.calibration.is_some();
+    let calibration = match cli.calibration {
+      Some(path) => Calibration::from_file(path).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1)
+      }),
+      None => Calibration::default(),
+    };
+    let logger = cli.log.open().unwrap_or_else(|e| {
+      eprintln!("{e}");
+      exit(1)
+    });
+    Self {
+      rssi: 0,
+      history: History::new(cli.squelch_threshold),
+      spectrum,
+      view: View::Monitor,
+      calibration,
+      calibrated,
+      frequency: cli.frequency,
+      logger,
+      serial: cli.serial,
+      connection: Connection::Connecting,
+      version: None,
+      last_error: None,
+      radio,
+      exit: false,
+    }
+  }
+
   /// runs the application's main loop until the user quits
   pub fn run(&mut self, terminal: &mut tui::Tui) -> io::Result<()> {
     while !self.exit {
+      self.handle_radio_events();
       terminal.draw(|frame| self.render_frame(frame))?;
       if poll(Duration::from_millis(100))? {
         self.handle_events()?;
@@ -34,6 +160,67 @@ impl App {
     frame.render_widget(self, frame.size());
   }
 
+  fn handle_radio_events(&mut self) {
+    loop {
+      match self.radio.events.try_recv() {
+        Ok(RadioEvent::Connected { version }) => {
+          self.connection = Connection::Connected;
+          self.version = Some(version);
+          self.last_error = None;
+        }
+        Ok(RadioEvent::Reading(reading)) => {
+          self.rssi = reading.sample.value;
+          self.history.push(reading.sample);
+          self.log(reading.sample, self.frequency);
+        }
+        Ok(RadioEvent::Sweep(step)) => self.handle_sweep(step),
+        Ok(RadioEvent::Tuned(frequency)) => {
+          self.frequency = Some(frequency);
+          self.view = View::Monitor;
+        }
+        Ok(RadioEvent::Error(e)) => self.last_error = Some(e),
+        Ok(RadioEvent::Disconnected(e)) => {
+          self.connection = Connection::Disconnected(e);
+          self.rssi = 0;
+        }
+        Err(TryRecvError::Empty) => break,
+        Err(TryRecvError::Disconnected) => {
+          self.connection = Connection::Disconnected(String::from("Polling thread stopped"));
+          break;
+        }
+      }
+    }
+  }
+
+  fn handle_sweep(&mut self, step: ScanStep) {
+    if let Some(spectrum) = &mut self.spectrum {
+      spectrum.push(step);
+    }
+    let sample = RssiSample {
+      timestamp: SystemTime::now(),
+      value: step.rssi,
+    };
+    self.log(sample, Some(step.frequency));
+  }
+
+  fn log(&mut self, sample: RssiSample, frequency: Option<f32>) {
+    let Some(logger) = &mut self.logger else {
+      return;
+    };
+    let record = LogRecord {
+      timestamp: sample.timestamp,
+      frequency,
+      rssi: sample.value,
+      dbm: self
+        .calibrated
+        .then(|| sample.rssi().dbm(&self.calibration)),
+      squelch_open: Some(sample.value >= self.history.squelch),
+    };
+    if let Err(e) = logger.write(&record) {
+      self.last_error = Some(e);
+    }
+  }
+
   fn handle_events(&mut self) -> io::Result<()> {
     match event::read()? {
       // it's important to check that the event is a key press event as
@@ -48,11 +235,50 @@ impl App {
 
   fn handle_key_event(&mut self, key_event: KeyEvent) {
     match key_event.code {
-      KeyCode::Char('q') => self.exit(),
+      KeyCode::Char('q') => return self.exit(),
+      KeyCode::Char('s') if self.spectrum.is_some() => {
+        return self.toggle_view();
+      }
+      _ => {}
+    }
+    if let (View::Spectrum, Some(spectrum)) = (&self.view, &mut self.spectrum) {
+      match key_event.code {
+        KeyCode::Left => spectrum.cursor_left(),
+        KeyCode::Right => spectrum.cursor_right(),
+        KeyCode::Char('m') => spectrum.cursor_to_peak(),
+        KeyCode::Char('r') => spectrum.reset_peak(),
+        KeyCode::Enter => {
+          let frequency = spectrum.cursor_frequency();
+          self.radio.send(RadioCommand::Tune(frequency));
+        }
+        _ => {}
+      }
+      return;
+    }
+    match key_event.code {
+      KeyCode::Char('p') | KeyCode::Char(' ') => self.history.toggle_pause(),
+      KeyCode::Char('+') | KeyCode::Char('=') => self.history.zoom_in(),
+      KeyCode::Char('-') => self.history.zoom_out(),
+      KeyCode::Char('r') => self.history.reset_peak(),
+      KeyCode::Up => self.history.squelch = self.history.squelch.saturating_add(1),
+      KeyCode::Down => self.history.squelch = self.history.squelch.saturating_sub(1),
       _ => {}
     }
   }
 
+  fn toggle_view(&mut self) {
+    self.view = match self.view {
+      View::Monitor => {
+        self.radio.send(RadioCommand::Spectrum);
+        View::Spectrum
+      }
+      View::Spectrum => {
+        self.radio.send(RadioCommand::Monitor);
+        View::Monitor
+      }
+    };
+  }
+
   fn exit(&mut self) {
     self.exit = true;
   }
@@ -60,36 +286,124 @@ impl App {
 
 impl Widget for &App {
   fn render(self, area: Rect, buf: &mut Buffer) {
-    let title = Title::from(" SA818RSSI ".bold());
+    let title = match &self.version {
+      Some(version) => Title::from(format!(" SA818RSSI - {} ", version).bold()),
+      None => Title::from(" SA818RSSI ".bold()),
+    };
+    let status = match &self.connection {
+      Connection::Connecting => Line::from(format!(" Connecting to {} ", self.serial).yellow()),
+      Connection::Connected => match &self.last_error {
+        Some(e) => Line::from(vec![
+          format!(" Connected to {} ", self.serial).green(),
+          format!("- {} ", e).red(),
+        ]),
+        None => Line::from(format!(" Connected to {} ", self.serial).green()),
+      },
+      Connection::Disconnected(e) => Line::from(format!(" Disconnected: {} - retrying ", e).red()),
+    };
 
     let block = Block::default()
       .title(title.alignment(Alignment::Center))
+      .title(
+        Title::from(status)
+          .alignment(Alignment::Center)
+          .position(block::Position::Bottom),
+      )
       .borders(Borders::ALL)
       .border_set(border::THICK);
-    let inner = block.clone().padding(Padding::top(1)).inner(area);
+    let inner = block.inner(area);
+    block.render(area, buf);
+    let [value_area, stats_area, help_area, body] = Layout::vertical([
+      Constraint::Length(1),
+      Constraint::Length(1),
+      Constraint::Length(1),
+      Constraint::Min(0),
+    ])
+    .areas(inner);
+    if let (View::Spectrum, Some(spectrum)) = (&self.view, &self.spectrum) {
+      let cursor_text = Line::from(vec![
+        "Cursor: ".into(),
+        format!("{:.4} MHz", spectrum.cursor_frequency()).yellow(),
+        "  rssi ".into(),
+        spectrum.current().to_string().yellow(),
+        "  peak ".into(),
+        spectrum.peak().to_string().magenta(),
+      ]);
+      Paragraph::new(cursor_text)
+        .centered()
+        .render(value_area, buf);
+      Paragraph::new(
+        "[left/right] cursor  [m] jump to peak  [enter] tune  [r] reset peak  [s] monitor  [q] quit"
+          .dark_gray(),
+      )
+      .centered()
+      .render(help_area, buf);
+      SpectrumView { spectrum }.render(stats_area.union(body), buf);
+      return;
+    }
+
+    let [bar_area, chart_area] =
+      Layout::horizontal([Constraint::Length(5), Constraint::Min(0)]).areas(body);
 
-    let rssi_text = Text::from(vec![Line::from(vec![
+    let rssi = Rssi(self.rssi);
+    let calibration = &self.calibration;
+    let mut rssi_text = Line::from(vec![
       "Value: ".into(),
       self.rssi.to_string().yellow(),
-    ])]);
+      format!(" ({:.1} dBm, ", rssi.dbm(calibration)).into(),
+      rssi.s_meter(calibration).to_string().yellow(),
+      ")".into(),
+    ]);
+    if let Some(frequency) = self.frequency {
+      rssi_text
+        .spans
+        .push(format!(" on {:.4} MHz", frequency).into());
+    }
+    Paragraph::new(rssi_text).centered().render(value_area, buf);
 
-    Paragraph::new(rssi_text)
+    let stats_text = match self.history.stats() {
+      Some(stats) => Line::from(vec![
+        "min ".into(),
+        stats.min.to_string().yellow(),
+        "  avg ".into(),
+        format!("{:.1}", stats.mean).blue(),
+        "  max ".into(),
+        stats.max.to_string().yellow(),
+        "  peak ".into(),
+        self.history.peak().unwrap_or(0).to_string().magenta(),
+        "  squelch ".into(),
+        self.history.squelch.to_string().red(),
+      ]),
+      None => Line::from("no samples yet"),
+    };
+    Paragraph::new(stats_text)
+      .centered()
+      .render(stats_area, buf);
+    let help = match self.spectrum {
+      Some(_) => "[+/-] span  [p] pause  [up/down] squelch  [r] reset peak  [s] spectrum  [q] quit",
+      None => "[+/-] span  [p] pause  [up/down] squelch  [r] reset peak  [q] quit",
+    };
+    Paragraph::new(help.dark_gray())
       .centered()
-      .block(block)
-      .render(area, buf);
+      .render(help_area, buf);
+
     BarChart::default()
       .bar_width(3)
       .bar_style(Style::new().yellow().on_red())
       .value_style(Style::new().red().bold())
       .data(BarGroup::default().bars(&[Bar::default().value(self.rssi.into())]))
       .max(255)
-      .render(centered_bar(inner, 3, inner.height), buf);
+      .render(centered_bar(bar_area, 3, bar_area.height), buf);
+    HistoryChart {
+      history: &self.history,
+    }
+    .render(chart_area, buf);
   }
 }
 
 fn centered_bar(r: Rect, bar_width: u16, bar_height: u16) -> Rect {
   Rect {
-    x: (r.width / 2 - bar_width / 2),
+    x: r.x + (r.width / 2 - bar_width / 2),
     y: r.y,
     width: bar_width,
     height: bar_height,
@@ -97,9 +411,18 @@ fn centered_bar(r: Rect, bar_width: u16, bar_height: u16) -> Rect {
 }
 
 fn main() -> Result<()> {
+  let cli = Cli::parse();
+  let radio = radio::spawn(
+    cli.serial.clone(),
+    cli.baud,
+    cli.interval,
+    cli.frequency,
+    cli.sweep(),
+  );
+  let mut app = App::new(cli, radio);
   initialize_panic_handler();
   let mut terminal = tui::init()?;
-  let app_result = App::default().run(&mut terminal);
+  let app_result = app.run(&mut terminal);
   tui::restore()?;
   app_result
 }
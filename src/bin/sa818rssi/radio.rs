@@ -0,0 +1,190 @@
+use crate::common;
+use sa818::{
+  channel::{Channel, FmBandwidth, FreqConf},
+  device::Sa818,
+  rssi::{RssiMonitor, RssiReading},
+  scanner::{ScanStep, Scanner},
+};
+use serialport::SerialPort;
+use std::{
+  sync::mpsc::{self, Receiver, SendError, Sender},
+  thread,
+  time::Duration,
+};
+
+/// Wait between reconnection attempts.
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+/// Consecutive failed polls before the port is considered gone.
+const MAX_ERRORS: u32 = 3;
+
+pub enum RadioEvent {
+  Connected { version: String },
+  Reading(RssiReading),
+  Sweep(ScanStep),
+  Tuned(f32),
+  Error(String),
+  Disconnected(String),
+}
+
+pub enum RadioCommand {
+  /// Poll the RSSI of the tuned frequency.
+  Monitor,
+  /// Sweep the configured range.
+  Spectrum,
+  /// Tune to a simplex frequency in MHz and go back to monitoring.
+  Tune(f32),
+}
+
+/// Frequency range stepped through in spectrum mode, in MHz.
+#[derive(Clone, Copy)]
+pub struct Sweep {
+  pub start: f32,
+  pub stop: f32,
+  pub step: f32,
+  pub dwell: Duration,
+}
+
+pub struct Radio {
+  pub events: Receiver<RadioEvent>,
+  commands: Sender<RadioCommand>,
+}
+
+impl Radio {
+  pub fn send(&self, command: RadioCommand) {
+    // The polling thread only stops once `events` is dropped.
+    let _ = self.commands.send(command);
+  }
+}
+
+struct Poller {
+  serial: String,
+  baud: u32,
+  interval: Duration,
+  sweep: Option<Sweep>,
+  commands: Receiver<RadioCommand>,
+  events: Sender<RadioEvent>,
+  spectrum: bool,
+  tuned: Option<f32>,
+}
+
+/// Poll the module on a background thread, reconnecting whenever the port
+/// goes away. The thread ends once the event receiver is dropped.
+pub fn spawn(
+  serial: String,
+  baud: u32,
+  interval: Duration,
+  frequency: Option<f32>,
+  sweep: Option<Sweep>,
+) -> Radio {
+  let (events, event_receiver) = mpsc::channel();
+  let (command_sender, commands) = mpsc::channel();
+  let mut poller = Poller {
+    serial,
+    baud,
+    interval,
+    sweep,
+    commands,
+    events,
+    spectrum: false,
+    tuned: frequency,
+  };
+  thread::spawn(move || {
+    while poller.session().is_ok() {
+      thread::sleep(RECONNECT_DELAY);
+    }
+  });
+  Radio {
+    events: event_receiver,
+    commands: command_sender,
+  }
+}
+
+impl Poller {
+  /// Run one connection until it fails. Errors only when the UI has gone away.
+  fn session(&mut self) -> Result<(), SendError<RadioEvent>> {
+    let device = match common::open_serial(&self.serial, self.baud) {
+      Ok(io) => Sa818::new(io),
+      Err(e) => return self.events.send(RadioEvent::Disconnected(e)),
+    };
+    let version = match device.get_version() {
+      Ok(version) => version,
+      Err(e) => return self.events.send(RadioEvent::Disconnected(e)),
+    };
+    self.events.send(RadioEvent::Connected { version })?;
+    if let Some(frequency) = self.tuned {
+      self.tune(&device, frequency)?;
+    }
+
+    let mut monitor = RssiMonitor::new(device.clone()).interval(self.interval);
+    let mut scanner = match self.sweep {
+      Some(sweep) => {
+        match Scanner::range(
+          device.clone(),
+          sweep.start,
+          sweep.stop,
+          sweep.step,
+          FmBandwidth::Narrow,
+        ) {
+          Ok(scanner) => Some(scanner.dwell(sweep.dwell)),
+          Err(e) => {
+            self.events.send(RadioEvent::Error(e))?;
+            None
+          }
+        }
+      }
+      None => None,
+    };
+
+    let mut errors = 0;
+    loop {
+      while let Ok(command) = self.commands.try_recv() {
+        match command {
+          RadioCommand::Monitor => {
+            self.spectrum = false;
+            if let Some(frequency) = self.tuned {
+              self.tune(&device, frequency)?;
+            }
+          }
+          RadioCommand::Spectrum => self.spectrum = scanner.is_some(),
+          RadioCommand::Tune(frequency) => {
+            self.spectrum = false;
+            self.tuned = Some(frequency);
+            self.tune(&device, frequency)?;
+          }
+        }
+      }
+      let result = match (&mut scanner, self.spectrum) {
+        (Some(scanner), true) => scanner.step().map(RadioEvent::Sweep),
+        _ => monitor.poll().map(RadioEvent::Reading),
+      };
+      match result {
+        Ok(event) => {
+          errors = 0;
+          self.events.send(event)?;
+        }
+        Err(e) => {
+          errors += 1;
+          if errors >= MAX_ERRORS {
+            return self.events.send(RadioEvent::Disconnected(e));
+          }
+          self.events.send(RadioEvent::Error(e))?;
+        }
+      }
+    }
+  }
+
+  fn tune(
+    &self,
+    device: &Sa818<Box<dyn SerialPort>>,
+    frequency: f32,
+  ) -> Result<(), SendError<RadioEvent>> {
+    let result = FreqConf::new(frequency).and_then(|conf| {
+      let channel = Channel::default().rx(conf.clone()).tx(conf);
+      device.write_config(&channel)
+    });
+    match result {
+      Ok(_) => self.events.send(RadioEvent::Tuned(frequency)),
+      Err(e) => self.events.send(RadioEvent::Error(e)),
+    }
+  }
+}
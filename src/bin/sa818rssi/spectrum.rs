@@ -0,0 +1,168 @@
+use crate::radio::Sweep;
+use ratatui::{
+  prelude::*,
+  widgets::{Block, Borders},
+};
+use sa818::scanner::{self, ScanStep};
+use std::collections::VecDeque;
+
+/// Completed sweeps kept for the waterfall.
+const WATERFALL_ROWS: usize = 64;
+
+/// Latest, peak-held and past RSSI values for every channel of the sweep.
+pub struct Spectrum {
+  sweep: Sweep,
+  current: Vec<u8>,
+  peak: Vec<u8>,
+  row: Vec<u8>,
+  waterfall: VecDeque<Vec<u8>>,
+  cursor: usize,
+}
+
+impl Spectrum {
+  pub fn new(sweep: Sweep) -> Result<Self, String> {
+    let channels = scanner::range_count(sweep.start, sweep.stop, sweep.step)?;
+    Ok(Self {
+      sweep,
+      current: vec![0; channels],
+      peak: vec![0; channels],
+      row: vec![0; channels],
+      waterfall: VecDeque::new(),
+      cursor: 0,
+    })
+  }
+
+  pub fn push(&mut self, step: ScanStep) {
+    let Some(current) = self.current.get_mut(step.index) else {
+      return;
+    };
+    *current = step.rssi;
+    self.peak[step.index] = self.peak[step.index].max(step.rssi);
+    self.row[step.index] = step.rssi;
+    if step.index == self.current.len() - 1 {
+      self.waterfall.push_front(self.row.clone());
+      self.waterfall.truncate(WATERFALL_ROWS);
+    }
+  }
+
+  pub fn frequency(&self, index: usize) -> f32 {
+    (self.sweep.start as f64 + index as f64 * self.sweep.step as f64) as f32
+  }
+
+  pub fn cursor_frequency(&self) -> f32 {
+    self.frequency(self.cursor)
+  }
+
+  pub fn cursor_left(&mut self) {
+    self.cursor = self.cursor.saturating_sub(1);
+  }
+
+  pub fn cursor_right(&mut self) {
+    self.cursor = (self.cursor + 1).min(self.current.len() - 1);
+  }
+
+  /// Move the cursor to the strongest peak-held channel.
+  pub fn cursor_to_peak(&mut self) {
+    if let Some((index, _)) = self
+      .peak
+      .iter()
+      .enumerate()
+      .max_by_key(|(index, &value)| (value, std::cmp::Reverse(*index)))
+    {
+      self.cursor = index;
+    }
+  }
+
+  pub fn reset_peak(&mut self) {
+    self.peak.copy_from_slice(&self.current);
+  }
+
+  pub fn current(&self) -> u8 {
+    self.current[self.cursor]
+  }
+
+  pub fn peak(&self) -> u8 {
+    self.peak[self.cursor]
+  }
+
+  /// Channels shown in column `x` of `width`, at least one per column.
+  fn channels_in_column(&self, x: u16, width: u16) -> std::ops::Range<usize> {
+    let channels = self.current.len();
+    let width = width as usize;
+    let x = x as usize;
+    if channels <= width {
+      let index = x * channels / width;
+      index..index + 1
+    } else {
+      x * channels / width..((x + 1) * channels / width).max(x * channels / width + 1)
+    }
+  }
+}
+
+pub struct SpectrumView<'a> {
+  pub spectrum: &'a Spectrum,
+}
+
+impl Widget for SpectrumView<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let spectrum = self.spectrum;
+    let [bars_area, waterfall_area] =
+      Layout::vertical([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(area);
+
+    let title = format!(
+      " {:.4} - {:.4} MHz ",
+      spectrum.sweep.start, spectrum.sweep.stop
+    );
+    let block = Block::default().title(title).borders(Borders::ALL);
+    let bars = block.inner(bars_area);
+    block.render(bars_area, buf);
+    let block = Block::default().title(" Waterfall ").borders(Borders::ALL);
+    let waterfall = block.inner(waterfall_area);
+    block.render(waterfall_area, buf);
+    if bars.width == 0 || bars.height == 0 {
+      return;
+    }
+
+    let level = |value: u8| (value as u32 * bars.height as u32 / 255) as u16;
+    for x in 0..bars.width {
+      let channels = spectrum.channels_in_column(x, bars.width);
+      let max = |values: &[u8]| values[channels.clone()].iter().copied().max().unwrap_or(0);
+      let current = level(max(&spectrum.current));
+      let peak = level(max(&spectrum.peak));
+      let is_cursor = channels.contains(&spectrum.cursor);
+      for y in 0..bars.height {
+        let height = bars.height - y;
+        let cell = buf.get_mut(bars.x + x, bars.y + y);
+        if height <= current {
+          cell.set_symbol(symbols::block::FULL).set_fg(Color::Yellow);
+        } else if height == peak {
+          cell.set_symbol("-").set_fg(Color::Magenta);
+        }
+        if is_cursor {
+          cell.set_bg(Color::DarkGray);
+        }
+      }
+      for (row, values) in spectrum.waterfall.iter().enumerate() {
+        if row as u16 >= waterfall.height {
+          break;
+        }
+        buf
+          .get_mut(waterfall.x + x, waterfall.y + row as u16)
+          .set_symbol(symbols::block::FULL)
+          .set_fg(heat(max(values)));
+      }
+    }
+  }
+}
+
+/// Waterfall colour for a raw RSSI value.
+fn heat(value: u8) -> Color {
+  match value {
+    0..=39 => Color::Black,
+    40..=79 => Color::Blue,
+    80..=119 => Color::Cyan,
+    120..=159 => Color::Green,
+    160..=199 => Color::Yellow,
+    _ => Color::Red,
+  }
+}
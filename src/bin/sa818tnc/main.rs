@@ -0,0 +1,205 @@
+#[path = "../common/mod.rs"]
+mod common;
+#[cfg(all(unix, feature = "pty"))]
+mod pty;
+
+use clap::Parser;
+#[cfg(all(unix, feature = "pty"))]
+use pty::Pty;
+use sa818::{
+  afsk::AfskDemodulator,
+  aprs,
+  audio::AudioSource,
+  device::Sa818,
+  kiss::{self, KissCommand, KissDecoder},
+  ptt::Transmitter,
+  tnc::{ChannelBusy, RssiBusy, Tnc},
+};
+#[cfg(all(unix, feature = "pty"))]
+use std::{
+  fs,
+  os::unix::fs::symlink,
+  path::{Path, PathBuf},
+};
+use std::{
+  io::{Read, Write},
+  net::TcpListener,
+  process::exit,
+  sync::{mpsc, Arc, Mutex},
+  thread,
+};
+
+/// Expose the module's AFSK modem as a KISS TNC over TCP and a pseudo-terminal
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+struct Cli {
+  /// Specify serial port
+  #[arg(short, long, value_name = "SERIAL", default_value = "/dev/ttyS1")]
+  serial: String,
+  /// Serial baud rate
+  #[arg(short, long, default_value = "9600")]
+  baud: u32,
+  #[command(flatten)]
+  ptt: common::PttArgs,
+  /// Listen for KISS clients on ADDR, e.g. 0.0.0.0:8001
+  #[arg(long, value_name = "ADDR")]
+  tcp: Option<String>,
+  /// Open a pseudo-terminal for KISS clients, optionally linked at PATH
+  #[cfg(all(unix, feature = "pty"))]
+  #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "")]
+  pty: Option<PathBuf>,
+  /// Tune the module to FREQUENCY in MHz with flat audio for packet
+  #[arg(long)]
+  frequency: Option<f32>,
+  /// Raw RSSI at or above which the channel counts as busy, without --squelch-pin
+  #[arg(long, default_value = "60")]
+  busy_threshold: u8,
+  #[command(flatten)]
+  squelch: common::SquelchArgs,
+  #[command(flatten)]
+  band_plan: common::BandPlanArgs,
+  #[command(flatten)]
+  audio: common::AudioArgs,
+}
+
+/// Writers to every connected client, the ones that fail are dropped.
+type Clients = Arc<Mutex<Vec<Box<dyn Write + Send>>>>;
+
+fn main() {
+  let cli = Cli::parse();
+  run(cli).unwrap_or_else(|e| {
+    eprintln!("{e}");
+    exit(1)
+  });
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+  #[cfg(all(unix, feature = "pty"))]
+  if cli.tcp.is_none() && cli.pty.is_none() {
+    return Err(String::from("Give --tcp, --pty or both"));
+  }
+  #[cfg(not(all(unix, feature = "pty")))]
+  if cli.tcp.is_none() {
+    return Err(String::from("Give --tcp"));
+  }
+  let serial_io = common::open_serial(&cli.serial, cli.baud)?;
+  let ptt = cli.ptt.open(serial_io.as_ref(), cli.baud)?;
+  let squelch = cli.squelch.open(serial_io.as_ref())?;
+  let device = Sa818::new(serial_io);
+  if let Some(frequency) = cli.frequency {
+    device.write_config(&cli.band_plan.apply(aprs::channel(frequency)?)?)?;
+    device.write_filter(&aprs::filter_config())?;
+  }
+
+  let source = cli.audio.source()?;
+  let transmitter = Transmitter::new(ptt, cli.audio.sink()?);
+  let busy: Box<dyn ChannelBusy> = match squelch {
+    Some(squelch) => Box::new(squelch),
+    None => Box::new(RssiBusy::new(device, cli.busy_threshold)),
+  };
+  let mut tnc = Tnc::new(transmitter, busy);
+
+  let clients: Clients = Arc::new(Mutex::new(Vec::new()));
+  let (commands, received) = mpsc::channel();
+  if let Some(address) = &cli.tcp {
+    let listener =
+      TcpListener::bind(address).map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+    println!("KISS on tcp {}", address);
+    let clients = clients.clone();
+    let commands = commands.clone();
+    thread::spawn(move || {
+      for stream in listener.incoming().flatten() {
+        match stream.try_clone() {
+          Ok(writer) => clients.lock().unwrap().push(Box::new(writer)),
+          Err(e) => {
+            eprintln!("{e}");
+            continue;
+          }
+        }
+        let commands = commands.clone();
+        thread::spawn(move || read_client(stream, commands));
+      }
+    });
+  }
+  #[cfg(all(unix, feature = "pty"))]
+  let _pty = match &cli.pty {
+    Some(link) => Some(open_pty(link, &clients, &commands)?),
+    None => None,
+  };
+  drop(commands);
+
+  thread::spawn(move || receive(source, clients));
+  for command in received {
+    if command == KissCommand::Return {
+      continue;
+    }
+    if let Err(e) = tnc.command(&command) {
+      eprintln!("{e}");
+    }
+  }
+  Ok(())
+}
+
+/// Open the pseudo-terminal as one more client, linked at `link` unless empty.
+#[cfg(all(unix, feature = "pty"))]
+fn open_pty(
+  link: &Path,
+  clients: &Clients,
+  commands: &mpsc::Sender<KissCommand>,
+) -> Result<Pty, String> {
+  let pty = Pty::open()?;
+  let writer = pty.writer().map_err(|e| e.to_string())?;
+  let reader = pty.reader().map_err(|e| e.to_string())?;
+  clients.lock().unwrap().push(Box::new(writer));
+  let commands = commands.clone();
+  thread::spawn(move || read_client(reader, commands));
+  if link.as_os_str().is_empty() {
+    println!("KISS on {}", pty.path.display());
+  } else {
+    let _ = fs::remove_file(link);
+    symlink(&pty.path, link).map_err(|e| format!("Failed to link {}: {}", link.display(), e))?;
+    println!("KISS on {} ({})", link.display(), pty.path.display());
+  }
+  Ok(pty)
+}
+
+/// Decode KISS from a client until it disconnects.
+fn read_client(mut reader: impl Read, commands: mpsc::Sender<KissCommand>) {
+  let mut decoder = KissDecoder::new();
+  let mut buffer = [0u8; 1024];
+  while let Ok(n @ 1..) = reader.read(&mut buffer) {
+    for command in decoder.push(&buffer[..n]) {
+      if commands.send(command).is_err() {
+        return;
+      }
+    }
+  }
+}
+
+/// Demodulate received audio and pass the frames to every client.
+fn receive(mut source: impl AudioSource, clients: Clients) {
+  let mut demodulator = AfskDemodulator::new(source.sample_rate());
+  let mut buffer = vec![0.0; 1024];
+  loop {
+    let n = match source.read(&mut buffer) {
+      Ok(0) => {
+        eprintln!("Receive audio ended");
+        exit(1)
+      }
+      Ok(n) => n,
+      Err(e) => {
+        eprintln!("{e}");
+        exit(1)
+      }
+    };
+    for frame in demodulator.push(&buffer[..n]) {
+      let bytes = kiss::encode(0, &frame);
+      clients.lock().unwrap().retain_mut(|client| {
+        client
+          .write_all(&bytes)
+          .and_then(|_| client.flush())
+          .is_ok()
+      });
+    }
+  }
+}
@@ -0,0 +1,107 @@
+//! A pseudo-terminal for KISS clients that expect a serial TNC.
+use std::{
+  ffi::CStr,
+  fs::File,
+  io::{self, Read, Write},
+  os::fd::{AsRawFd, FromRawFd, RawFd},
+  path::PathBuf,
+};
+
+/// A raw mode pseudo-terminal, the client opens the slave path.
+pub struct Pty {
+  master: File,
+  pub path: PathBuf,
+  /// Kept open so the master doesn't hang up between clients.
+  _slave: File,
+}
+
+fn check(result: libc::c_int) -> io::Result<libc::c_int> {
+  if result < 0 {
+    Err(io::Error::last_os_error())
+  } else {
+    Ok(result)
+  }
+}
+
+impl Pty {
+  pub fn open() -> Result<Self, String> {
+    Self::open_raw().map_err(|e| format!("Failed to open a pseudo-terminal: {}", e))
+  }
+
+  fn open_raw() -> io::Result<Self> {
+    // SAFETY: plain libc calls on a descriptor owned here, the name buffer
+    // outlives the call filling it.
+    unsafe {
+      let fd: RawFd = check(libc::posix_openpt(
+        libc::O_RDWR | libc::O_NOCTTY | libc::O_NONBLOCK,
+      ))?;
+      let master = File::from_raw_fd(fd);
+      check(libc::grantpt(fd))?;
+      check(libc::unlockpt(fd))?;
+      let mut name = [0 as libc::c_char; 128];
+      let error = libc::ptsname_r(fd, name.as_mut_ptr(), name.len());
+      if error != 0 {
+        return Err(io::Error::from_raw_os_error(error));
+      }
+      let path = PathBuf::from(CStr::from_ptr(name.as_ptr()).to_string_lossy().into_owned());
+
+      let mut termios = std::mem::zeroed::<libc::termios>();
+      check(libc::tcgetattr(fd, &mut termios))?;
+      libc::cfmakeraw(&mut termios);
+      check(libc::tcsetattr(fd, libc::TCSANOW, &termios))?;
+
+      let slave = File::options().read(true).write(true).open(&path)?;
+      Ok(Self {
+        master,
+        path,
+        _slave: slave,
+      })
+    }
+  }
+
+  pub fn reader(&self) -> io::Result<PtyReader> {
+    self.master.try_clone().map(PtyReader)
+  }
+
+  pub fn writer(&self) -> io::Result<PtyWriter> {
+    self.master.try_clone().map(PtyWriter)
+  }
+}
+
+/// Blocks until the client writes something.
+pub struct PtyReader(File);
+
+impl Read for PtyReader {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    loop {
+      match self.0.read(buf) {
+        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+          let mut fd = libc::pollfd {
+            fd: self.0.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+          };
+          // SAFETY: polls a single descriptor owned by self.
+          check(unsafe { libc::poll(&mut fd, 1, -1) })?;
+        }
+        result => return result,
+      }
+    }
+  }
+}
+
+/// Drops what doesn't fit while no client is reading, instead of blocking.
+pub struct PtyWriter(File);
+
+impl Write for PtyWriter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    match self.0.write(buf) {
+      Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(buf.len()),
+      result => result,
+    }
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
@@ -1,9 +1,13 @@
-use crate::{group_call::GroupSel, read_string};
+use crate::{
+  band_plan::{pmr446_frequency, Access, BandPlan},
+  group_call::GroupSel,
+  read_string,
+};
 use std::{
   fmt,
   io::{Read, Write},
 };
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FreqConf {
   pub frequency: f32,
   pub group_sel: Option<GroupSel>,
@@ -11,7 +15,7 @@ pub struct FreqConf {
 
 impl FreqConf {
   pub fn new(frequency: f32) -> Result<Self, String> {
-    if (frequency < 134.0 || frequency > 174.0) && (frequency < 400.0 || frequency > 480.0) {
+    if !(134.0..=174.0).contains(&frequency) && !(400.0..=480.0).contains(&frequency) {
       return Err(String::from("Invalid Frequency"));
     }
     Ok(Self {
@@ -31,7 +35,7 @@ impl FreqConf {
   }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FmBandwidth {
   Wide,
   Narrow,
@@ -46,28 +50,71 @@ impl fmt::Display for FmBandwidth {
   }
 }
 
+/// Transmit power. The module sets it from the H/L pin rather than a
+/// command, so this is the level the host should drive the pin to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerLevel {
+  Low,
+  High,
+}
+
+/// IARU region, selects the default repeater offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+  One,
+  Two,
+  Three,
+}
+
+/// Usual repeater offset for a frequency in MHz, if it is in a repeater band.
+///
+/// 600 kHz on 2 m everywhere, on 70 cm 7.6 MHz in region 1 and 5 MHz elsewhere.
+pub fn default_offset(frequency: f32, region: Region) -> Option<f32> {
+  match frequency {
+    f if (144.0..=148.0).contains(&f) => Some(0.6),
+    f if (420.0..=450.0).contains(&f) => match region {
+      Region::One => Some(7.6),
+      Region::Two | Region::Three => Some(5.0),
+    },
+    _ => None,
+  }
+}
+
 #[derive(Debug)]
 pub struct Command {
   pub command: String,
   pub expected_response: String,
 }
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Channel {
   bandwidth: FmBandwidth,
   tx_conf: Option<FreqConf>,
   rx_conf: Option<FreqConf>,
   squelch: u8,
+  power: PowerLevel,
+  band_plan: Option<BandPlan>,
+  ignore_band_plan: bool,
+  /// Rules of a licence-free service, enforced even when the band plan is
+  /// ignored.
+  service: Option<BandPlan>,
 }
 
-impl Channel {
-  pub fn default() -> Self {
+impl Default for Channel {
+  fn default() -> Self {
     Self {
       bandwidth: FmBandwidth::Narrow,
       tx_conf: None,
       rx_conf: None,
       squelch: 4,
+      power: PowerLevel::High,
+      band_plan: None,
+      ignore_band_plan: false,
+      service: None,
     }
   }
+}
+
+impl Channel {
   pub fn set_tx(&mut self, fq: FreqConf) {
     self.tx_conf = Some(fq);
   }
@@ -86,6 +133,62 @@ impl Channel {
     Ok(self)
   }
 
+  pub fn power(mut self, power: PowerLevel) -> Self {
+    self.power = power;
+    self
+  }
+
+  /// PMR446 channel 1 to 16: simplex, narrow FM and low power. The PMR446
+  /// rules stay with the channel, on top of any band plan and even when that
+  /// is ignored, so a changed copy is refused by `write_config`.
+  pub fn pmr446(channel: u8, tone: Option<GroupSel>) -> Result<Self, String> {
+    let frequency = pmr446_frequency(channel)?;
+    let mut conf = FreqConf::new(frequency)?;
+    conf.group_sel = tone;
+    Ok(
+      Channel::default()
+        .bandwidth(FmBandwidth::Narrow)
+        .power(PowerLevel::Low)
+        .rx(conf.clone())
+        .tx(conf)
+        .service(BandPlan::pmr446()),
+    )
+  }
+
+  /// Refuse to write the channel if the plan doesn't allow transmitting on it.
+  pub fn band_plan(mut self, plan: BandPlan) -> Self {
+    self.band_plan = Some(plan);
+    self
+  }
+
+  /// Write the channel even where the band plan only allows receiving.
+  pub fn ignore_band_plan(mut self, ignore: bool) -> Self {
+    self.ignore_band_plan = ignore;
+    self
+  }
+
+  fn service(mut self, plan: BandPlan) -> Self {
+    self.service = Some(plan);
+    self
+  }
+
+  /// What the service rules and the band plan allow on this channel,
+  /// transmit if there are neither.
+  pub fn access(&self) -> Access {
+    self.plans(false)
+  }
+
+  fn plans(&self, ignore_band_plan: bool) -> Access {
+    let band_plan = self.band_plan.as_ref().filter(|_| !ignore_band_plan);
+    self
+      .service
+      .iter()
+      .chain(band_plan)
+      .map(|plan| plan.access(self))
+      .find(|access| *access != Access::Transmit)
+      .unwrap_or(Access::Transmit)
+  }
+
   pub fn write_config<T: Read + Write>(&self, io: &mut T) -> Result<String, String> {
     let bw_string = match self.bandwidth {
       FmBandwidth::Wide => "0",
@@ -115,21 +218,44 @@ impl Channel {
     } else {
       return Err(String::from("Rx frequency is not specified!"));
     }
+    if let Access::ReceiveOnly { reason } = self.plans(self.ignore_band_plan) {
+      return Err(reason);
+    }
     let command = format!(
       "AT+DMOSETGROUP={},{},{},{},{},{}\r\n",
       bw_string, tx_frequency, rx_frequency, tx_group, self.squelch, rx_group
     );
     io.write_all(command.as_bytes())
       .map_err(|e| e.to_string())?;
-    let mut response = read_string(io)?;
-    io.read_to_string(&mut response)
-      .map_err(|e| e.to_string())?;
+    let response = read_string(io)?;
     if response.trim() != "+DMOSETGROUP=0" {
       return Err(format!("Invalid Response: {}", response));
     }
     Ok(response)
   }
 
+  pub fn rx_conf(&self) -> Option<&FreqConf> {
+    self.rx_conf.as_ref()
+  }
+  pub fn tx_conf(&self) -> Option<&FreqConf> {
+    self.tx_conf.as_ref()
+  }
+  pub fn fm_bandwidth(&self) -> FmBandwidth {
+    self.bandwidth
+  }
+  pub fn squelch_level(&self) -> u8 {
+    self.squelch
+  }
+  pub fn power_level(&self) -> PowerLevel {
+    self.power
+  }
+  pub fn rx_frequency(&self) -> Option<f32> {
+    self.rx_conf.as_ref().map(|conf| conf.frequency)
+  }
+  pub fn tx_frequency(&self) -> Option<f32> {
+    self.tx_conf.as_ref().map(|conf| conf.frequency)
+  }
+
   pub fn tx(mut self, tx_conf: FreqConf) -> Self {
     self.tx_conf = Some(tx_conf);
     self
@@ -138,4 +264,47 @@ impl Channel {
     self.rx_conf = Some(rx_conf);
     self
   }
+
+  /// Transmit `offset` MHz away from the receive frequency, keeping the tx tone.
+  pub fn offset(self, offset: f32) -> Result<Self, String> {
+    let rx = self
+      .rx_frequency()
+      .ok_or(String::from("Rx frequency is not specified!"))?;
+    // Round to 100 Hz so 145.6 - 0.6 gives 145.0 and not 144.99998.
+    let tx = ((rx as f64 + offset as f64) * 1e4).round() / 1e4;
+    self.split(tx as f32)
+  }
+
+  /// Transmit above the receive frequency by the band's usual offset.
+  pub fn shift_up(self, region: Region) -> Result<Self, String> {
+    let offset = self.band_offset(region)?;
+    self.offset(offset)
+  }
+
+  /// Transmit below the receive frequency by the band's usual offset.
+  pub fn shift_down(self, region: Region) -> Result<Self, String> {
+    let offset = self.band_offset(region)?;
+    self.offset(-offset)
+  }
+
+  /// Transmit on an unrelated frequency, keeping the tx tone.
+  pub fn split(mut self, tx_frequency: f32) -> Result<Self, String> {
+    let mut tx_conf = FreqConf::new(tx_frequency)?;
+    tx_conf.group_sel = self.tx_conf.and_then(|conf| conf.group_sel);
+    self.tx_conf = Some(tx_conf);
+    Ok(self)
+  }
+
+  /// Swap receive and transmit, to listen on a repeater's input.
+  pub fn reverse(mut self) -> Self {
+    std::mem::swap(&mut self.rx_conf, &mut self.tx_conf);
+    self
+  }
+
+  fn band_offset(&self, region: Region) -> Result<f32, String> {
+    let rx = self
+      .rx_frequency()
+      .ok_or(String::from("Rx frequency is not specified!"))?;
+    default_offset(rx, region).ok_or(format!("No default repeater offset for {} MHz", rx))
+  }
 }
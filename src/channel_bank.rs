@@ -0,0 +1,203 @@
+use crate::{
+  channel::{Channel, FmBandwidth, FreqConf},
+  csv,
+  group_call::{parse_tone, GroupSel},
+};
+use std::{fmt, fs, path::Path};
+
+const HEADER: &str = "name,rx_frequency,tx_frequency,rx_tone,tx_tone,bandwidth,squelch,notes";
+
+/// A stored channel with its name and free-form notes.
+#[derive(Debug, Clone)]
+pub struct MemoryChannel {
+  pub name: String,
+  pub channel: Channel,
+  pub notes: String,
+}
+
+/// Named channel memory, saved as a CSV file.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelBank {
+  channels: Vec<MemoryChannel>,
+}
+
+impl ChannelBank {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn len(&self) -> usize {
+    self.channels.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.channels.is_empty()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &MemoryChannel> {
+    self.channels.iter()
+  }
+
+  /// Store a channel under a new name, returning its index.
+  pub fn add(&mut self, name: &str, channel: Channel, notes: &str) -> Result<usize, String> {
+    let name = name.trim();
+    if name.is_empty() {
+      return Err(String::from("Channel name is empty"));
+    }
+    if self.find(name).is_some() {
+      return Err(format!("Channel {} already exists", name));
+    }
+    // The bank file is read line by line.
+    if name.contains(['\r', '\n']) || notes.contains(['\r', '\n']) {
+      return Err(String::from(
+        "Channel names and notes must be a single line",
+      ));
+    }
+    if channel.rx_conf().is_none() {
+      return Err(String::from("Rx frequency is not specified!"));
+    }
+    if channel.tx_conf().is_none() {
+      return Err(String::from("Tx frequency is not specified!"));
+    }
+    self.channels.push(MemoryChannel {
+      name: name.to_string(),
+      channel,
+      notes: notes.to_string(),
+    });
+    Ok(self.channels.len() - 1)
+  }
+
+  pub fn get(&self, index: usize) -> Option<&MemoryChannel> {
+    self.channels.get(index)
+  }
+
+  pub fn find(&self, name: &str) -> Option<(usize, &MemoryChannel)> {
+    self
+      .channels
+      .iter()
+      .enumerate()
+      .find(|(_, memory)| memory.name == name)
+  }
+
+  /// Look a channel up by name, or by index if no name matches.
+  pub fn lookup(&self, key: &str) -> Option<(usize, &MemoryChannel)> {
+    self.find(key).or_else(|| {
+      let index = key.parse::<usize>().ok()?;
+      Some((index, self.get(index)?))
+    })
+  }
+
+  pub fn remove(&mut self, key: &str) -> Result<MemoryChannel, String> {
+    let (index, _) = self
+      .lookup(key)
+      .ok_or(format!("No channel {} in memory", key))?;
+    Ok(self.channels.remove(index))
+  }
+
+  pub fn parse(bank: &str) -> Result<Self, String> {
+    let mut channels = ChannelBank::new();
+    let mut lines = bank.lines().enumerate();
+    if let Some((_, header)) = lines.next() {
+      let columns = csv::split(header.trim_start_matches('\u{feff}'))?;
+      if !columns.iter().map(|c| c.trim()).eq(HEADER.split(',')) {
+        return Err(format!("Line 1: expected the header {}", HEADER));
+      }
+    }
+    for (number, line) in lines {
+      if line.trim().is_empty() {
+        continue;
+      }
+      let memory = parse_memory(line).map_err(|e| format!("Line {}: {}", number + 1, e))?;
+      channels
+        .add(&memory.name, memory.channel, &memory.notes)
+        .map_err(|e| format!("Line {}: {}", number + 1, e))?;
+    }
+    Ok(channels)
+  }
+
+  /// Load a bank, a missing file is an empty bank.
+  pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+    match fs::read_to_string(path) {
+      Ok(bank) => ChannelBank::parse(&bank),
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ChannelBank::new()),
+      Err(e) => Err(e.to_string()),
+    }
+  }
+
+  pub fn write_file<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+    if let Some(parent) = path.as_ref().parent() {
+      fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::write(path, self.to_string()).map_err(|e| e.to_string())
+  }
+}
+
+fn parse_memory(line: &str) -> Result<MemoryChannel, String> {
+  let fields = csv::split(line)?;
+  if fields.len() != 8 {
+    return Err(format!("expected 8 fields, found {}", fields.len()));
+  }
+  let frequency = |field: &str| {
+    field
+      .trim()
+      .parse::<f32>()
+      .map_err(|e| format!("{}: {}", field, e))
+  };
+  let tone = |field: &str| match field.trim() {
+    "" => Ok(None),
+    tone => parse_tone(tone).map(Some),
+  };
+  let conf = |frequency: f32, tone: Option<GroupSel>| match tone {
+    Some(tone) => FreqConf::with_group_sel(frequency, tone),
+    None => FreqConf::new(frequency),
+  };
+  let bandwidth = match fields[5].trim() {
+    "wide" => FmBandwidth::Wide,
+    "narrow" => FmBandwidth::Narrow,
+    other => return Err(format!("Invalid bandwidth {}", other)),
+  };
+  let squelch = fields[6]
+    .trim()
+    .parse::<u8>()
+    .map_err(|e| format!("{}: {}", fields[6], e))?;
+  let channel = Channel::default()
+    .bandwidth(bandwidth)
+    .squelch(squelch)?
+    .rx(conf(frequency(&fields[1])?, tone(&fields[3])?)?)
+    .tx(conf(frequency(&fields[2])?, tone(&fields[4])?)?);
+  Ok(MemoryChannel {
+    name: fields[0].clone(),
+    channel,
+    notes: fields[7].clone(),
+  })
+}
+
+impl fmt::Display for ChannelBank {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "{}", HEADER)?;
+    for memory in &self.channels {
+      let channel = &memory.channel;
+      let (Some(rx), Some(tx)) = (channel.rx_conf(), channel.tx_conf()) else {
+        continue;
+      };
+      let tone = |conf: &FreqConf| conf.group_sel.map(|g| g.tone_string()).unwrap_or_default();
+      let bandwidth = match channel.fm_bandwidth() {
+        FmBandwidth::Wide => "wide",
+        FmBandwidth::Narrow => "narrow",
+      };
+      writeln!(
+        f,
+        "{},{:.4},{:.4},{},{},{},{},{}",
+        csv::quote(&memory.name),
+        rx.frequency,
+        tx.frequency,
+        tone(rx),
+        tone(tx),
+        bandwidth,
+        channel.squelch_level(),
+        csv::quote(&memory.notes)
+      )?;
+    }
+    Ok(())
+  }
+}
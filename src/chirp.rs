@@ -0,0 +1,360 @@
+//! Import and export of CHIRP channel lists.
+//!
+//! CHIRP describes tones with a tone mode (`Tone`, `TSQL`, `DTCS`, `Cross`)
+//! and a set of tone columns, only the combinations the SA818 can do are
+//! imported, the other rows are reported back with the reason.
+use crate::{
+  channel::{Channel, FmBandwidth, FreqConf},
+  channel_bank::MemoryChannel,
+  csv,
+  group_call::{Ctcss, Dcs, DcsSuffix, GroupSel},
+};
+use std::{collections::HashMap, fmt, fs, path::Path};
+
+const HEADER: &str = "Location,Name,Frequency,Duplex,Offset,Tone,rToneFreq,cToneFreq,DtcsCode,\
+DtcsPolarity,RxDtcsCode,CrossMode,Mode,TStep,Skip,Power,Comment,URCALL,RPT1CALL,RPT2CALL,DVCODE";
+
+const REQUIRED: [&str; 9] = [
+  "Frequency",
+  "Duplex",
+  "Offset",
+  "Tone",
+  "rToneFreq",
+  "cToneFreq",
+  "DtcsCode",
+  "DtcsPolarity",
+  "Mode",
+];
+
+/// Offsets above this are written as a split rather than +/-, in MHz.
+const MAX_OFFSET: f64 = 10.0;
+
+/// A CHIRP row that can't be programmed into a SA818.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rejected {
+  /// Line in the file, starting at 1.
+  pub line: usize,
+  pub name: String,
+  pub reason: String,
+}
+
+impl fmt::Display for Rejected {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.name.is_empty() {
+      write!(f, "line {}: {}", self.line, self.reason)
+    } else {
+      write!(f, "line {} ({}): {}", self.line, self.name, self.reason)
+    }
+  }
+}
+
+/// Channels read from a CHIRP file along with the rows that were skipped.
+#[derive(Debug, Clone, Default)]
+pub struct ChirpImport {
+  pub channels: Vec<MemoryChannel>,
+  pub rejected: Vec<Rejected>,
+}
+
+/// Read a CHIRP CSV export. Only a missing column or a malformed line is an
+/// error, channels the SA818 can't represent end up in `rejected`.
+pub fn import(list: &str) -> Result<ChirpImport, String> {
+  let mut lines = list.lines().enumerate();
+  let (_, header) = lines.next().ok_or("Empty CHIRP file")?;
+  let columns: HashMap<String, usize> = csv::split(header.trim_start_matches('\u{feff}'))?
+    .into_iter()
+    .enumerate()
+    .map(|(index, name)| (name.trim().to_string(), index))
+    .collect();
+  if let Some(missing) = REQUIRED.iter().find(|c| !columns.contains_key(**c)) {
+    return Err(format!("Missing CHIRP column {}", missing));
+  }
+
+  let mut import = ChirpImport::default();
+  for (number, line) in lines {
+    if line.trim().is_empty() {
+      continue;
+    }
+    let fields = csv::split(line).map_err(|e| format!("Line {}: {}", number + 1, e))?;
+    let row = Row {
+      columns: &columns,
+      fields: &fields,
+    };
+    let name = match row.get("Name") {
+      "" => format!("CH{}", row.get("Location")),
+      name => name.to_string(),
+    };
+    match row.channel() {
+      Ok(channel) => import.channels.push(MemoryChannel {
+        name,
+        channel,
+        notes: row.get("Comment").to_string(),
+      }),
+      Err(reason) => import.rejected.push(Rejected {
+        line: number + 1,
+        name,
+        reason,
+      }),
+    }
+  }
+  Ok(import)
+}
+
+pub fn import_file<P: AsRef<Path>>(path: P) -> Result<ChirpImport, String> {
+  import(&fs::read_to_string(path).map_err(|e| e.to_string())?)
+}
+
+/// Write channels as a CHIRP CSV, numbering locations from 0.
+pub fn export<'a, I>(channels: I) -> String
+where
+  I: IntoIterator<Item = &'a MemoryChannel>,
+{
+  let mut list = format!("{}\n", HEADER);
+  for (location, memory) in channels.into_iter().enumerate() {
+    let channel = &memory.channel;
+    let (Some(rx), Some(tx)) = (channel.rx_conf(), channel.tx_conf()) else {
+      continue;
+    };
+    let rx_frequency = round(rx.frequency);
+    let tx_frequency = round(tx.frequency);
+    let (duplex, offset) = match tx_frequency - rx_frequency {
+      offset if offset.abs() > MAX_OFFSET => ("split", tx_frequency),
+      offset if offset > 0.0 => ("+", offset),
+      offset if offset < 0.0 => ("-", -offset),
+      _ => ("", 0.0),
+    };
+    let tones = Tones::from_group_sel(rx.group_sel, tx.group_sel);
+    let mode = match channel.fm_bandwidth() {
+      FmBandwidth::Wide => "FM",
+      FmBandwidth::Narrow => "NFM",
+    };
+    list.push_str(&format!(
+      "{},{},{:.6},{},{:.6},{},{},{},{:03},{},{:03},{},{},5.00,,1.0W,{},,,,\n",
+      location,
+      csv::quote(&memory.name),
+      rx_frequency,
+      duplex,
+      offset,
+      tones.mode,
+      tones.r_tone,
+      tones.c_tone,
+      tones.dtcs,
+      tones.polarity,
+      tones.rx_dtcs,
+      tones.cross_mode,
+      mode,
+      csv::quote(&memory.notes),
+    ));
+  }
+  list
+}
+
+pub fn export_file<'a, I, P>(channels: I, path: P) -> Result<(), String>
+where
+  I: IntoIterator<Item = &'a MemoryChannel>,
+  P: AsRef<Path>,
+{
+  fs::write(path, export(channels)).map_err(|e| e.to_string())
+}
+
+/// Round to 100 Hz so f32 noise doesn't show up in offsets.
+fn round(frequency: f32) -> f64 {
+  (frequency as f64 * 1e4).round() / 1e4
+}
+
+struct Row<'a> {
+  columns: &'a HashMap<String, usize>,
+  fields: &'a [String],
+}
+
+impl Row<'_> {
+  fn get(&self, column: &str) -> &str {
+    self
+      .columns
+      .get(column)
+      .and_then(|index| self.fields.get(*index))
+      .map_or("", |field| field.trim())
+  }
+
+  fn frequency(&self, column: &str) -> Result<f64, String> {
+    self
+      .get(column)
+      .parse::<f64>()
+      .map_err(|_| format!("invalid {} {:?}", column, self.get(column)))
+  }
+
+  fn channel(&self) -> Result<Channel, String> {
+    let bandwidth = match self.get("Mode") {
+      "FM" => FmBandwidth::Wide,
+      "NFM" => FmBandwidth::Narrow,
+      mode => return Err(format!("mode {} is not supported", mode)),
+    };
+    let rx_frequency = self.frequency("Frequency")?;
+    let tx_frequency = match self.get("Duplex") {
+      "" => rx_frequency,
+      "+" => rx_frequency + self.frequency("Offset")?,
+      "-" => rx_frequency - self.frequency("Offset")?,
+      "split" => self.frequency("Offset")?,
+      "off" => return Err(String::from("receive only channels are not supported")),
+      duplex => return Err(format!("duplex {} is not supported", duplex)),
+    };
+    let (rx_tone, tx_tone) = self.tones()?;
+    let conf = |frequency: f64, tone: Option<GroupSel>| {
+      let conf = match tone {
+        Some(tone) => FreqConf::with_group_sel(frequency as f32, tone),
+        None => FreqConf::new(frequency as f32),
+      };
+      conf.map_err(|_| format!("{:.4} MHz is out of band", frequency))
+    };
+    Ok(
+      Channel::default()
+        .bandwidth(bandwidth)
+        .rx(conf(rx_frequency, rx_tone)?)
+        .tx(conf(tx_frequency, tx_tone)?),
+    )
+  }
+
+  /// Receive and transmit tones for the row's tone mode.
+  fn tones(&self) -> Result<(Option<GroupSel>, Option<GroupSel>), String> {
+    let r_tone = || ctcss(self.get("rToneFreq"));
+    let c_tone = || ctcss(self.get("cToneFreq"));
+    let polarity = self.get("DtcsPolarity");
+    let tx_polarity = || suffix(polarity.chars().next());
+    let rx_polarity = || suffix(polarity.chars().nth(1));
+    let dtcs = || dcs(self.get("DtcsCode"), tx_polarity()?);
+    let rx_dtcs = |code: &str| dcs(code, rx_polarity()?);
+    match self.get("Tone") {
+      "" => Ok((None, None)),
+      "Tone" => Ok((None, Some(r_tone()?))),
+      "TSQL" => Ok((Some(c_tone()?), Some(c_tone()?))),
+      "DTCS" => Ok((Some(rx_dtcs(self.get("DtcsCode"))?), Some(dtcs()?))),
+      "Cross" => {
+        let rx_code = match self.get("RxDtcsCode") {
+          "" => self.get("DtcsCode"),
+          code => code,
+        };
+        match self.get("CrossMode") {
+          "Tone->Tone" => Ok((Some(c_tone()?), Some(r_tone()?))),
+          "Tone->DTCS" => Ok((Some(rx_dtcs(rx_code)?), Some(r_tone()?))),
+          "DTCS->Tone" => Ok((Some(c_tone()?), Some(dtcs()?))),
+          "DTCS->DTCS" => Ok((Some(rx_dtcs(rx_code)?), Some(dtcs()?))),
+          "Tone->" => Ok((None, Some(r_tone()?))),
+          "DTCS->" => Ok((None, Some(dtcs()?))),
+          "->Tone" => Ok((Some(c_tone()?), None)),
+          "->DTCS" => Ok((Some(rx_dtcs(rx_code)?), None)),
+          mode => Err(format!("cross mode {} is not supported", mode)),
+        }
+      }
+      mode => Err(format!("tone mode {} is not supported", mode)),
+    }
+  }
+}
+
+fn ctcss(frequency: &str) -> Result<GroupSel, String> {
+  let tone = frequency
+    .parse::<f32>()
+    .map_err(|_| format!("invalid CTCSS tone {:?}", frequency))?;
+  Ctcss::from_hz(tone, Ctcss::TOLERANCE)
+    .map(GroupSel::Ctcss)
+    .map_err(|_| format!("CTCSS {:.1} Hz is not supported", tone))
+}
+
+fn dcs(code: &str, suffix: DcsSuffix) -> Result<GroupSel, String> {
+  let number = code
+    .parse::<u32>()
+    .map_err(|_| format!("invalid DCS code {:?}", code))?;
+  GroupSel::new_dcs(number, suffix).map_err(|_| format!("DCS {} is not supported", code))
+}
+
+fn suffix(polarity: Option<char>) -> Result<DcsSuffix, String> {
+  match polarity {
+    Some('N') | None => Ok(DcsSuffix::Normal),
+    Some('R') => Ok(DcsSuffix::Inverted),
+    Some(other) => Err(format!("invalid DCS polarity {}", other)),
+  }
+}
+
+/// Tone columns of an exported row.
+struct Tones {
+  mode: &'static str,
+  r_tone: String,
+  c_tone: String,
+  dtcs: u32,
+  polarity: String,
+  rx_dtcs: u32,
+  cross_mode: &'static str,
+}
+
+impl Tones {
+  fn from_group_sel(rx: Option<GroupSel>, tx: Option<GroupSel>) -> Self {
+    let mut tones = Tones {
+      mode: "",
+      r_tone: String::from("88.5"),
+      c_tone: String::from("88.5"),
+      dtcs: 23,
+      polarity: String::from("NN"),
+      rx_dtcs: 23,
+      cross_mode: "Tone->Tone",
+    };
+    let mut polarity = ['N', 'N'];
+    let mut dcs = |dcs: Dcs, index: usize| {
+      if dcs.polarity() == DcsSuffix::Inverted {
+        polarity[index] = 'R';
+      }
+      dcs.code() as u32
+    };
+    let hz = |ctcss: Ctcss| format!("{:.1}", ctcss.hz());
+    match (tx, rx) {
+      (None, None) => {}
+      (Some(GroupSel::Ctcss(ctcss)), None) => {
+        tones.mode = "Tone";
+        tones.r_tone = hz(ctcss);
+      }
+      (Some(GroupSel::Ctcss(tx)), Some(GroupSel::Ctcss(rx))) if tx == rx => {
+        tones.mode = "TSQL";
+        tones.r_tone = hz(tx);
+        tones.c_tone = hz(rx);
+      }
+      (Some(GroupSel::Dcs(tx)), Some(GroupSel::Dcs(rx))) if tx.code() == rx.code() => {
+        tones.mode = "DTCS";
+        tones.dtcs = dcs(tx, 0);
+        tones.rx_dtcs = dcs(rx, 1);
+      }
+      (tx, rx) => {
+        tones.mode = "Cross";
+        let tx_mode = match tx {
+          Some(GroupSel::Ctcss(ctcss)) => {
+            tones.r_tone = hz(ctcss);
+            "Tone"
+          }
+          Some(GroupSel::Dcs(code)) => {
+            tones.dtcs = dcs(code, 0);
+            "DTCS"
+          }
+          None => "",
+        };
+        let rx_mode = match rx {
+          Some(GroupSel::Ctcss(ctcss)) => {
+            tones.c_tone = hz(ctcss);
+            "Tone"
+          }
+          Some(GroupSel::Dcs(code)) => {
+            tones.rx_dtcs = dcs(code, 1);
+            "DTCS"
+          }
+          None => "",
+        };
+        tones.cross_mode = match (tx_mode, rx_mode) {
+          ("Tone", "Tone") => "Tone->Tone",
+          ("Tone", "DTCS") => "Tone->DTCS",
+          ("DTCS", "Tone") => "DTCS->Tone",
+          ("DTCS", "DTCS") => "DTCS->DTCS",
+          ("DTCS", _) => "DTCS->",
+          (_, "Tone") => "->Tone",
+          _ => "->DTCS",
+        };
+      }
+    }
+    tones.polarity = polarity.iter().collect();
+    tones
+  }
+}
@@ -0,0 +1,35 @@
+//! Minimal CSV field handling for the channel list formats.
+
+/// Split one CSV line into fields, honouring double quoted fields.
+pub(crate) fn split(line: &str) -> Result<Vec<String>, String> {
+  let mut fields = Vec::new();
+  let mut field = String::new();
+  let mut chars = line.chars().peekable();
+  let mut quoted = false;
+  while let Some(c) = chars.next() {
+    match (c, quoted) {
+      ('"', true) if chars.peek() == Some(&'"') => {
+        chars.next();
+        field.push('"');
+      }
+      ('"', true) => quoted = false,
+      ('"', false) if field.is_empty() => quoted = true,
+      (',', false) => fields.push(std::mem::take(&mut field)),
+      (c, _) => field.push(c),
+    }
+  }
+  if quoted {
+    return Err(String::from("Unterminated quoted field"));
+  }
+  fields.push(field);
+  Ok(fields)
+}
+
+/// Quote a field if it contains a separator, quote or line break.
+pub(crate) fn quote(field: &str) -> String {
+  if field.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", field.replace('"', "\"\""))
+  } else {
+    field.to_string()
+  }
+}
@@ -0,0 +1,298 @@
+//! Morse identification of unattended stations.
+use crate::{
+  audio::AudioSink,
+  ptt::{Ptt, Transmitter},
+};
+use std::{
+  f32::consts::PI,
+  time::{Duration, Instant},
+};
+
+const MORSE: [(char, &str); 45] = [
+  ('A', ".-"),
+  ('B', "-..."),
+  ('C', "-.-."),
+  ('D', "-.."),
+  ('E', "."),
+  ('F', "..-."),
+  ('G', "--."),
+  ('H', "...."),
+  ('I', ".."),
+  ('J', ".---"),
+  ('K', "-.-"),
+  ('L', ".-.."),
+  ('M', "--"),
+  ('N', "-."),
+  ('O', "---"),
+  ('P', ".--."),
+  ('Q', "--.-"),
+  ('R', ".-."),
+  ('S', "..."),
+  ('T', "-"),
+  ('U', "..-"),
+  ('V', "...-"),
+  ('W', ".--"),
+  ('X', "-..-"),
+  ('Y', "-.--"),
+  ('Z', "--.."),
+  ('0', "-----"),
+  ('1', ".----"),
+  ('2', "..---"),
+  ('3', "...--"),
+  ('4', "....-"),
+  ('5', "....."),
+  ('6', "-...."),
+  ('7', "--..."),
+  ('8', "---.."),
+  ('9', "----."),
+  ('/', "-..-."),
+  ('?', "..--.."),
+  ('.', ".-.-.-"),
+  (',', "--..--"),
+  ('=', "-...-"),
+  ('-', "-....-"),
+  ('+', ".-.-."),
+  ('@', ".--.-."),
+  (':', "---..."),
+];
+
+/// Dots and dashes of a character, `None` if it has no Morse code.
+pub fn morse(c: char) -> Option<&'static str> {
+  let c = c.to_ascii_uppercase();
+  MORSE.iter().find(|(k, _)| *k == c).map(|(_, code)| *code)
+}
+
+/// Renders text to keyed tone audio.
+pub struct CwEncoder {
+  sample_rate: u32,
+  wpm: f32,
+  tone: f32,
+  level: f32,
+}
+
+impl CwEncoder {
+  pub fn new(sample_rate: u32) -> Self {
+    Self {
+      sample_rate,
+      wpm: 20.0,
+      tone: 800.0,
+      level: 0.5,
+    }
+  }
+
+  /// Speed in words per minute of the word PARIS, 20 by default.
+  pub fn wpm(mut self, wpm: f32) -> Self {
+    self.wpm = wpm;
+    self
+  }
+
+  /// Tone frequency in Hz, 800 by default.
+  pub fn tone(mut self, tone: f32) -> Self {
+    self.tone = tone;
+    self
+  }
+
+  /// Tone amplitude relative to full scale.
+  pub fn level(mut self, level: f32) -> Self {
+    self.level = level;
+    self
+  }
+
+  /// Length of a dot, the unit every other element is counted in.
+  pub fn dot(&self) -> Duration {
+    Duration::from_nanos((1.2e9 / self.wpm as f64).round() as u64)
+  }
+
+  /// Elements of `text` as (key down, units) pairs, without trailing space.
+  fn elements(&self, text: &str) -> Result<Vec<(bool, usize)>, String> {
+    if self.wpm.is_nan() || self.wpm <= 0.0 {
+      return Err(format!("Invalid speed {} wpm", self.wpm));
+    }
+    let mut elements = Vec::new();
+    for (w, word) in text.split_whitespace().enumerate() {
+      if w > 0 {
+        elements.push((false, 7));
+      }
+      for (c, character) in word.chars().enumerate() {
+        let code = morse(character).ok_or(format!("'{}' has no Morse code", character))?;
+        if c > 0 {
+          elements.push((false, 3));
+        }
+        for (e, element) in code.chars().enumerate() {
+          if e > 0 {
+            elements.push((false, 1));
+          }
+          elements.push((true, if element == '-' { 3 } else { 1 }));
+        }
+      }
+    }
+    Ok(elements)
+  }
+
+  /// How long sending `text` takes.
+  pub fn duration(&self, text: &str) -> Result<Duration, String> {
+    let units: usize = self.elements(text)?.iter().map(|(_, units)| units).sum();
+    Ok(self.dot() * units as u32)
+  }
+
+  /// Audio of `text`, words split on whitespace.
+  pub fn encode(&self, text: &str) -> Result<Vec<f32>, String> {
+    let rate = self.sample_rate as f32;
+    let dot_len = rate * 1.2 / self.wpm;
+    // Raised cosine edges keep the keying clicks down.
+    let edge_len = (rate * 0.005).min(dot_len / 2.0) as usize;
+    let mut samples = Vec::new();
+    let mut units = 0;
+    for (down, length) in self.elements(text)? {
+      let start = (units as f32 * dot_len) as usize;
+      units += length;
+      let end = (units as f32 * dot_len) as usize;
+      if !down {
+        samples.resize(end, 0.0);
+        continue;
+      }
+      let len = end - start;
+      samples.extend((0..len).map(|i| {
+        let edge = i.min(len - 1 - i);
+        let shape = if edge < edge_len {
+          0.5 - 0.5 * (PI * edge as f32 / edge_len as f32).cos()
+        } else {
+          1.0
+        };
+        shape * self.level * (2.0 * PI * self.tone * (start + i) as f32 / rate).sin()
+      }));
+    }
+    Ok(samples)
+  }
+}
+
+/// When an ID is due: on the first key-up after a quiet spell, then every
+/// interval for as long as the station keeps transmitting.
+#[derive(Debug, Clone)]
+pub struct IdSchedule {
+  interval: Duration,
+  last_id: Option<Instant>,
+  last_activity: Option<Instant>,
+}
+
+impl IdSchedule {
+  pub fn new(interval: Duration) -> Self {
+    Self {
+      interval,
+      last_id: None,
+      last_activity: None,
+    }
+  }
+
+  pub fn interval(&self) -> Duration {
+    self.interval
+  }
+
+  pub fn last_id(&self) -> Option<Instant> {
+    self.last_id
+  }
+
+  fn expired(&self, at: Instant) -> bool {
+    self
+      .last_id
+      .is_none_or(|id| at.saturating_duration_since(id) >= self.interval)
+  }
+
+  /// The station keys up for traffic at `at`, returns whether to identify
+  /// first.
+  pub fn key_up(&mut self, at: Instant) -> bool {
+    self.last_activity = Some(at);
+    self.expired(at)
+  }
+
+  /// Whether an ID is due for traffic sent since the last one, including
+  /// traffic right after an ID sent on key-up.
+  pub fn due(&self, at: Instant) -> bool {
+    let active = match (self.last_activity, self.last_id) {
+      (Some(activity), Some(id)) => activity >= id,
+      (activity, None) => activity.is_some(),
+      (None, Some(_)) => false,
+    };
+    active && self.expired(at)
+  }
+
+  /// Record an ID sent at `at`.
+  pub fn identified(&mut self, at: Instant) {
+    self.last_id = Some(at);
+  }
+}
+
+/// Sends a CW ID through a [`Transmitter`] when the schedule calls for it.
+pub struct CwId<P, S> {
+  transmitter: Transmitter<P, S>,
+  schedule: IdSchedule,
+  audio: Vec<f32>,
+}
+
+impl<P: Ptt, S: AudioSink> CwId<P, S> {
+  /// ID of `text`, usually the callsign, sent every `interval` while active.
+  pub fn new(
+    transmitter: Transmitter<P, S>,
+    encoder: &CwEncoder,
+    text: &str,
+    interval: Duration,
+  ) -> Result<Self, String> {
+    let audio = encoder.encode(text)?;
+    if audio.is_empty() {
+      return Err(String::from("Nothing to send as ID"));
+    }
+    let duration = transmitter.duration(audio.len());
+    let timeout = transmitter.timeout_limit();
+    if duration > timeout {
+      return Err(format!(
+        "ID takes {:.1} s, longer than the {:.1} s time-out timer",
+        duration.as_secs_f32(),
+        timeout.as_secs_f32()
+      ));
+    }
+    Ok(Self {
+      transmitter,
+      schedule: IdSchedule::new(interval),
+      audio,
+    })
+  }
+
+  pub fn schedule(&self) -> &IdSchedule {
+    &self.schedule
+  }
+
+  pub fn transmitter(&mut self) -> &mut Transmitter<P, S> {
+    &mut self.transmitter
+  }
+
+  pub fn into_transmitter(self) -> Transmitter<P, S> {
+    self.transmitter
+  }
+
+  /// Key up, send the ID and unkey.
+  pub fn send(&mut self, at: Instant) -> Result<(), String> {
+    self.transmitter.transmit(&self.audio)?;
+    self.schedule.identified(at);
+    Ok(())
+  }
+
+  /// Call before transmitting traffic, sends the ID if this is the first
+  /// key-up in a while. Returns whether it was sent.
+  pub fn key_up(&mut self, at: Instant) -> Result<bool, String> {
+    if !self.schedule.key_up(at) {
+      return Ok(false);
+    }
+    self.send(at)?;
+    Ok(true)
+  }
+
+  /// Call periodically, sends the ID when one is due. Returns whether it
+  /// was sent.
+  pub fn poll(&mut self, at: Instant) -> Result<bool, String> {
+    if !self.schedule.due(at) {
+      return Ok(false);
+    }
+    self.send(at)?;
+    Ok(true)
+  }
+}
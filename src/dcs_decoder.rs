@@ -0,0 +1,241 @@
+//! Finding the DCS code of received audio.
+use crate::{
+  audio::AudioSource,
+  group_call::{Dcs, DcsSuffix, GroupSel},
+};
+use std::{collections::HashMap, f32::consts::PI, time::Duration};
+
+/// DCS bit rate in bits per second.
+pub const BIT_RATE: f32 = 134.4;
+
+/// Bit timing offsets tried, in fractions of a bit.
+const PHASES: usize = 8;
+
+/// A DCS code found in the audio.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DcsMatch {
+  pub dcs: Dcs,
+  /// Share of the 23 bit windows of the audio that decoded to this code, 0.0
+  /// to 1.0. Across a whole scan it is also scaled by the share of audio
+  /// windows that agreed.
+  pub confidence: f32,
+  /// Amplitude of the sub-audible bit stream relative to full scale.
+  pub level: f32,
+}
+
+impl DcsMatch {
+  pub fn group_sel(&self) -> GroupSel {
+    GroupSel::Dcs(self.dcs)
+  }
+}
+
+/// Recovers the 134.4 bps DCS bit stream and matches it against the standard
+/// codes.
+pub struct DcsDecoder {
+  sample_rate: u32,
+  window_len: usize,
+  min_level: f32,
+  inverted: bool,
+  polarity: DcsSuffix,
+  filter: [Biquad; 2],
+}
+
+impl DcsDecoder {
+  pub fn new(sample_rate: u32) -> Self {
+    Self {
+      sample_rate,
+      window_len: sample_rate as usize / 2,
+      min_level: 0.005,
+      inverted: false,
+      polarity: DcsSuffix::Normal,
+      // 4th order Butterworth low pass, voice starts above 300 Hz.
+      filter: [
+        Biquad::low_pass(250.0, 0.5412, sample_rate),
+        Biquad::low_pass(250.0, 1.3066, sample_rate),
+      ],
+    }
+  }
+
+  /// Audio analysed at once, it should hold a few codewords, each takes
+  /// 171 ms. Defaults to half a second, longer windows suffer more from a
+  /// transmitter bit rate that is a little off. It must hold one codeword.
+  pub fn window(mut self, window: Duration) -> Result<Self, String> {
+    let len = (self.sample_rate as f64 * window.as_secs_f64()) as usize;
+    if (len as f32) < 23.0 * self.sample_rate as f32 / BIT_RATE {
+      return Err(format!(
+        "A {} ms window is too short for a DCS codeword",
+        window.as_millis()
+      ));
+    }
+    self.window_len = len;
+    Ok(self)
+  }
+
+  /// Bit stream amplitude, relative to full scale, below which nothing is
+  /// found.
+  pub fn min_level(mut self, min_level: f32) -> Self {
+    self.min_level = min_level;
+    self
+  }
+
+  /// Swap the polarity, for receivers whose audio path inverts the signal.
+  pub fn inverted(mut self, inverted: bool) -> Self {
+    self.inverted = inverted;
+    self
+  }
+
+  /// Polarity to report codes with. The same bits decode to a normal and an
+  /// inverted standard code, normal by default.
+  pub fn polarity(mut self, polarity: DcsSuffix) -> Self {
+    self.polarity = polarity;
+    self
+  }
+
+  /// Samples needed by [`DcsDecoder::detect`].
+  pub fn window_len(&self) -> usize {
+    self.window_len
+  }
+
+  /// Bits of one window of audio and their amplitude, timed at the sample
+  /// offset giving the cleanest bits.
+  pub fn bits(&self, samples: &[f32]) -> (Vec<bool>, f32) {
+    let samples = &samples[..samples.len().min(self.window_len)];
+    let mut filtered = samples.to_vec();
+    for biquad in &self.filter {
+      biquad.process(&mut filtered);
+    }
+    let mean = filtered.iter().sum::<f32>() / filtered.len().max(1) as f32;
+    filtered.iter_mut().for_each(|sample| *sample -= mean);
+
+    let bit_len = self.sample_rate as f32 / BIT_RATE;
+    let (soft, level) = (0..PHASES)
+      .map(|phase| {
+        let offset = phase as f32 * bit_len / PHASES as f32;
+        let soft = integrate(&filtered, offset, bit_len);
+        let level = soft.iter().map(|bit| bit.abs()).sum::<f32>() / soft.len().max(1) as f32;
+        (soft, level)
+      })
+      .max_by(|a, b| a.1.total_cmp(&b.1))
+      .unwrap_or_default();
+    let bits = soft
+      .into_iter()
+      .map(|bit| (bit > 0.0) != self.inverted)
+      .collect();
+    (bits, level)
+  }
+
+  /// Code most 23 bit stretches of one window of audio decode to.
+  pub fn detect(&self, samples: &[f32]) -> Option<DcsMatch> {
+    let (bits, level) = self.bits(samples);
+    if level < self.min_level || bits.len() < 23 {
+      return None;
+    }
+    let windows = bits.len() - 22;
+    let mut votes: HashMap<Dcs, usize> = HashMap::new();
+    for window in bits.windows(23) {
+      let word = window
+        .iter()
+        .enumerate()
+        .fold(0u32, |word, (i, &bit)| word | (bit as u32) << i);
+      if let Some(dcs) = Dcs::from_bits(word).and_then(|dcs| dcs.with_polarity(self.polarity)) {
+        *votes.entry(dcs).or_default() += 1;
+      }
+    }
+    let (dcs, count) = votes
+      .into_iter()
+      .max_by_key(|&(dcs, count)| (count, std::cmp::Reverse(dcs.code())))?;
+    Some(DcsMatch {
+      dcs,
+      confidence: count as f32 / windows as f32,
+      level,
+    })
+  }
+
+  /// Detect window by window over a whole stream and report the code most
+  /// windows agree on. Needs at least half a window of audio.
+  pub fn scan<S: AudioSource>(&self, source: &mut S) -> Result<Option<DcsMatch>, String> {
+    if source.sample_rate() != self.sample_rate {
+      return Err(format!(
+        "Audio is {} Hz, the decoder expects {} Hz",
+        source.sample_rate(),
+        self.sample_rate
+      ));
+    }
+    let mut buffer = vec![0.0; self.window_len];
+    let mut windows = 0;
+    let mut matches: Vec<DcsMatch> = Vec::new();
+    loop {
+      let read = source.read_exact(&mut buffer)?;
+      // Skip a tail too short to hold a codeword.
+      if read < buffer.len() / 2 {
+        break;
+      }
+      windows += 1;
+      if let Some(found) = self.detect(&buffer[..read]) {
+        matches.push(found);
+      }
+      if read < buffer.len() {
+        break;
+      }
+    }
+
+    let mut best: Option<(Dcs, usize)> = None;
+    for found in &matches {
+      let votes = matches.iter().filter(|m| m.dcs == found.dcs).count();
+      if best.is_none_or(|(_, most)| votes > most) {
+        best = Some((found.dcs, votes));
+      }
+    }
+    Ok(best.map(|(dcs, votes)| {
+      let agreeing = matches.iter().filter(|m| m.dcs == dcs);
+      let (confidence, level) =
+        agreeing.fold((0.0, 0.0), |(c, l), m| (c + m.confidence, l + m.level));
+      DcsMatch {
+        dcs,
+        confidence: confidence / windows as f32,
+        level: level / votes as f32,
+      }
+    }))
+  }
+}
+
+/// Mean of each whole bit period starting at `offset`.
+fn integrate(samples: &[f32], offset: f32, bit_len: f32) -> Vec<f32> {
+  let mut bits = Vec::new();
+  let mut start = offset;
+  while ((start + bit_len) as usize) <= samples.len() {
+    let bit = &samples[start as usize..(start + bit_len) as usize];
+    bits.push(bit.iter().sum::<f32>() / bit.len().max(1) as f32);
+    start += bit_len;
+  }
+  bits
+}
+
+/// Second order section, coefficients from the RBJ audio EQ cookbook.
+struct Biquad {
+  b: [f32; 3],
+  a: [f32; 2],
+}
+
+impl Biquad {
+  fn low_pass(cutoff: f32, q: f32, sample_rate: u32) -> Self {
+    let w0 = 2.0 * PI * cutoff / sample_rate as f32;
+    let alpha = w0.sin() / (2.0 * q);
+    let a0 = 1.0 + alpha;
+    let b1 = (1.0 - w0.cos()) / a0;
+    Self {
+      b: [b1 / 2.0, b1, b1 / 2.0],
+      a: [-2.0 * w0.cos() / a0, (1.0 - alpha) / a0],
+    }
+  }
+
+  fn process(&self, samples: &mut [f32]) {
+    let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+    for sample in samples {
+      let x0 = *sample;
+      let y0 = self.b[0] * x0 + self.b[1] * x1 + self.b[2] * x2 - self.a[0] * y1 - self.a[1] * y2;
+      (x2, x1, y2, y1) = (x1, x0, y1, y0);
+      *sample = y0;
+    }
+  }
+}
@@ -0,0 +1,84 @@
+use crate::{channel::Channel, filter_config::FilterConfig, volume_config::VolumeConfig};
+use std::{
+  io::{Read, Write},
+  sync::{Arc, Mutex, MutexGuard, TryLockError},
+  thread,
+  time::{Duration, Instant},
+};
+
+/// Shared handle to a SA818 module.
+///
+/// The serial port lives behind a mutex so the handle can be cloned and used
+/// from several threads. Every command holds the port for its whole
+/// request/response exchange.
+pub struct Sa818<T> {
+  io: Arc<Mutex<T>>,
+}
+
+impl<T> Clone for Sa818<T> {
+  fn clone(&self) -> Self {
+    Self {
+      io: Arc::clone(&self.io),
+    }
+  }
+}
+
+impl<T: Read + Write> Sa818<T> {
+  pub fn new(io: T) -> Self {
+    Self {
+      io: Arc::new(Mutex::new(io)),
+    }
+  }
+
+  /// Lock the port, waiting for any command in flight to complete.
+  pub fn lock(&self) -> Result<MutexGuard<'_, T>, String> {
+    self
+      .io
+      .lock()
+      .map_err(|_| String::from("Device lock poisoned"))
+  }
+
+  /// Lock the port only if no other command is in flight.
+  pub fn try_lock(&self) -> Result<Option<MutexGuard<'_, T>>, String> {
+    match self.io.try_lock() {
+      Ok(guard) => Ok(Some(guard)),
+      Err(TryLockError::WouldBlock) => Ok(None),
+      Err(TryLockError::Poisoned(_)) => Err(String::from("Device lock poisoned")),
+    }
+  }
+
+  pub fn handshake(&self) -> Result<String, String> {
+    crate::handshake(&mut *self.lock()?)
+  }
+
+  pub fn get_version(&self) -> Result<String, String> {
+    crate::get_version(&mut *self.lock()?)
+  }
+
+  pub fn get_rssi(&self) -> Result<u8, String> {
+    crate::get_rssi(&mut *self.lock()?)
+  }
+
+  pub fn write_config(&self, channel: &Channel) -> Result<String, String> {
+    channel.write_config(&mut *self.lock()?)
+  }
+
+  pub fn write_volume(&self, volume: &VolumeConfig) -> Result<String, String> {
+    volume.write_config(&mut *self.lock()?)
+  }
+
+  pub fn write_filter(&self, filter: &FilterConfig) -> Result<String, String> {
+    filter.write_config(&mut *self.lock()?)
+  }
+
+  /// Highest RSSI sampled every `interval` over `duration`, at least one sample.
+  pub fn peak_rssi(&self, duration: Duration, interval: Duration) -> Result<u8, String> {
+    let start = Instant::now();
+    let mut peak = self.get_rssi()?;
+    while start.elapsed() + interval < duration {
+      thread::sleep(interval);
+      peak = peak.max(self.get_rssi()?);
+    }
+    Ok(peak)
+  }
+}
@@ -0,0 +1,368 @@
+//! DTMF tones, and remote control through PIN protected DTMF commands.
+use crate::{channel::Channel, device::Sa818, goertzel::Goertzel, volume_config::VolumeConfig};
+use std::{
+  collections::HashMap,
+  f32::consts::PI,
+  io::{Read, Write},
+  time::{Duration, Instant},
+};
+
+const LOW: [f32; 4] = [697.0, 770.0, 852.0, 941.0];
+const HIGH: [f32; 4] = [1209.0, 1336.0, 1477.0, 1633.0];
+/// Keys by row (low tone) and column (high tone).
+const KEYS: [[char; 4]; 4] = [
+  ['1', '2', '3', 'A'],
+  ['4', '5', '6', 'B'],
+  ['7', '8', '9', 'C'],
+  ['*', '0', '#', 'D'],
+];
+
+/// Low and high tone of a key, `None` if it isn't a DTMF key.
+pub fn tones(key: char) -> Option<(f32, f32)> {
+  let key = key.to_ascii_uppercase();
+  KEYS.iter().enumerate().find_map(|(row, keys)| {
+    let column = keys.iter().position(|&k| k == key)?;
+    Some((LOW[row], HIGH[column]))
+  })
+}
+
+fn is_key(key: char) -> bool {
+  tones(key).is_some()
+}
+
+/// Renders keys to audio.
+pub struct DtmfEncoder {
+  sample_rate: u32,
+  tone: Duration,
+  gap: Duration,
+  level: f32,
+  twist: f32,
+}
+
+impl DtmfEncoder {
+  pub fn new(sample_rate: u32) -> Self {
+    Self {
+      sample_rate,
+      tone: Duration::from_millis(100),
+      gap: Duration::from_millis(100),
+      level: 0.35,
+      twist: 0.0,
+    }
+  }
+
+  /// How long each key sounds, 100 ms by default.
+  pub fn tone(mut self, tone: Duration) -> Self {
+    self.tone = tone;
+    self
+  }
+
+  /// Silence between keys, 100 ms by default.
+  pub fn gap(mut self, gap: Duration) -> Self {
+    self.gap = gap;
+    self
+  }
+
+  /// Amplitude of each of the two tones relative to full scale.
+  pub fn level(mut self, level: f32) -> Self {
+    self.level = level;
+    self
+  }
+
+  /// High tone level above the low one in dB, making up for the receiver's
+  /// de-emphasis.
+  pub fn twist(mut self, twist: f32) -> Self {
+    self.twist = twist;
+    self
+  }
+
+  /// Audio of `keys`, spaces are skipped.
+  pub fn encode(&self, keys: &str) -> Result<Vec<f32>, String> {
+    let keys: Vec<char> = keys.chars().filter(|c| !c.is_whitespace()).collect();
+    let tones = keys
+      .iter()
+      .map(|&key| tones(key).ok_or(format!("'{}' is not a DTMF key", key)))
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let rate = self.sample_rate as f32;
+    let tone_len = (rate * self.tone.as_secs_f32()) as usize;
+    let gap_len = (rate * self.gap.as_secs_f32()) as usize;
+    // Short fades keep the keying from splattering.
+    let fade_len = (rate * 0.002) as usize;
+    let high_level = self.level * 10f32.powf(self.twist / 20.0);
+    let mut samples = Vec::with_capacity(tones.len() * (tone_len + gap_len));
+    for (n, (low, high)) in tones.into_iter().enumerate() {
+      if n > 0 {
+        samples.extend(std::iter::repeat_n(0.0, gap_len));
+      }
+      samples.extend((0..tone_len).map(|i| {
+        let t = i as f32 / rate;
+        let edge = i.min(tone_len - 1 - i);
+        let fade = if edge < fade_len {
+          0.5 - 0.5 * (PI * edge as f32 / fade_len as f32).cos()
+        } else {
+          1.0
+        };
+        fade * (self.level * (2.0 * PI * low * t).sin() + high_level * (2.0 * PI * high * t).sin())
+      }));
+    }
+    Ok(samples)
+  }
+}
+
+/// Finds keys in received audio, fed in blocks of any size.
+pub struct DtmfDecoder {
+  sample_rate: u32,
+  block_len: usize,
+  hop: usize,
+  low: [Goertzel; 4],
+  high: [Goertzel; 4],
+  min_level: f32,
+  normal_twist: f32,
+  reverse_twist: f32,
+  min_blocks: usize,
+  buffer: Vec<f32>,
+  current: Option<char>,
+  count: usize,
+}
+
+impl DtmfDecoder {
+  pub fn new(sample_rate: u32) -> Self {
+    // 205 samples at 8 kHz, the classic block length.
+    let block_len = (sample_rate as f32 * 0.0256) as usize;
+    let hop = block_len / 2;
+    Self {
+      sample_rate,
+      block_len,
+      hop,
+      low: LOW.map(|hz| Goertzel::new(hz, sample_rate)),
+      high: HIGH.map(|hz| Goertzel::new(hz, sample_rate)),
+      min_level: 0.01,
+      normal_twist: 4.0,
+      reverse_twist: 8.0,
+      min_blocks: 2,
+      buffer: Vec::new(),
+      current: None,
+      count: 0,
+    }
+  }
+
+  /// Tone amplitude, relative to full scale, below which nothing is found.
+  pub fn min_level(mut self, min_level: f32) -> Self {
+    self.min_level = min_level;
+    self
+  }
+
+  /// Largest level difference between the tones, in dB, with the high tone
+  /// louder (normal) and the low tone louder (reverse). Defaults to 4 and 8.
+  pub fn twist(mut self, normal: f32, reverse: f32) -> Self {
+    self.normal_twist = normal;
+    self.reverse_twist = reverse;
+    self
+  }
+
+  /// Shortest key press reported, 40 ms by default. Blocks overlap by half
+  /// so anything short of a block and a half is taken as one block.
+  pub fn min_duration(mut self, min_duration: Duration) -> Self {
+    let samples = min_duration.as_secs_f32() * self.sample_rate as f32;
+    let hops = (samples - self.block_len as f32) / self.hop as f32;
+    self.min_blocks = 1 + hops.round().max(0.0) as usize;
+    self
+  }
+
+  /// Samples [`DtmfDecoder::detect`] looks at.
+  pub fn block_len(&self) -> usize {
+    self.block_len
+  }
+
+  /// Key sounding through one block, checking both tones stand out from
+  /// their group and from the rest of the audio, and the twist.
+  pub fn detect(&self, block: &[f32]) -> Option<char> {
+    let strongest = |filters: &[Goertzel; 4]| {
+      let mut powers: Vec<(usize, f32)> = filters
+        .iter()
+        .map(|filter| filter.power(block))
+        .enumerate()
+        .collect();
+      powers.sort_by(|a, b| b.1.total_cmp(&a.1));
+      (powers[0], powers[1].1)
+    };
+    let ((row, low), low_next) = strongest(&self.low);
+    let ((column, high), high_next) = strongest(&self.high);
+
+    let min_power = self.min_level * self.min_level;
+    if low < min_power || high < min_power {
+      return None;
+    }
+    // The other tones of each group at least 6 dB down.
+    if low_next * 4.0 > low || high_next * 4.0 > high {
+      return None;
+    }
+    let twist = 10.0 * (high / low).log10();
+    if twist > self.normal_twist || -twist > self.reverse_twist {
+      return None;
+    }
+    // Both tones carry most of the energy, voice spreads it around.
+    let energy = block.iter().map(|s| s * s).sum::<f32>() / block.len().max(1) as f32;
+    if (low + high) / 2.0 < 0.6 * energy {
+      return None;
+    }
+    Some(KEYS[row][column])
+  }
+
+  /// Feed audio, returning keys whose press reached the minimum duration.
+  /// A key must be released for a block before it counts again.
+  pub fn push(&mut self, samples: &[f32]) -> Vec<char> {
+    self.buffer.extend_from_slice(samples);
+    let mut keys = Vec::new();
+    let mut start = 0;
+    while self.buffer.len() - start >= self.block_len {
+      let key = self.detect(&self.buffer[start..start + self.block_len]);
+      start += self.hop;
+      if key != self.current {
+        self.current = key;
+        self.count = 0;
+      }
+      if let Some(key) = key {
+        self.count += 1;
+        if self.count == self.min_blocks {
+          keys.push(key);
+        }
+      }
+    }
+    self.buffer.drain(..start);
+    keys
+  }
+
+  /// Keys in a whole recording.
+  pub fn decode(&mut self, samples: &[f32]) -> String {
+    self.push(samples).into_iter().collect()
+  }
+}
+
+/// What a DTMF command does to the module.
+pub enum DtmfAction {
+  Channel(Channel),
+  Volume(VolumeConfig),
+}
+
+impl DtmfAction {
+  pub fn apply<T: Read + Write>(&self, device: &Sa818<T>) -> Result<String, String> {
+    match self {
+      DtmfAction::Channel(channel) => device.write_config(channel),
+      DtmfAction::Volume(volume) => device.write_volume(volume),
+    }
+  }
+}
+
+/// Maps key sequences of the form `*<pin><code>#` to actions. A `*` starts
+/// over, entries time out between keys and too many wrong PINs lock
+/// everything out for a while.
+pub struct DtmfCommands {
+  pin: String,
+  commands: HashMap<String, DtmfAction>,
+  timeout: Duration,
+  max_attempts: u32,
+  lockout: Duration,
+  entry: Option<String>,
+  last_key: Option<Instant>,
+  failures: u32,
+  locked_until: Option<Instant>,
+}
+
+impl DtmfCommands {
+  pub fn new(pin: &str) -> Result<Self, String> {
+    validate_keys("PIN", pin)?;
+    Ok(Self {
+      pin: pin.to_ascii_uppercase(),
+      commands: HashMap::new(),
+      timeout: Duration::from_secs(5),
+      max_attempts: 3,
+      lockout: Duration::from_secs(300),
+      entry: None,
+      last_key: None,
+      failures: 0,
+      locked_until: None,
+    })
+  }
+
+  pub fn command(mut self, code: &str, action: DtmfAction) -> Result<Self, String> {
+    validate_keys("Command", code)?;
+    self.commands.insert(code.to_ascii_uppercase(), action);
+    Ok(self)
+  }
+
+  /// Longest pause between keys of one entry, 5 s by default.
+  pub fn timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  /// Wrong PINs in a row that lock out commands, and for how long. Defaults
+  /// to 3 and 5 minutes.
+  pub fn lockout(mut self, max_attempts: u32, lockout: Duration) -> Self {
+    self.max_attempts = max_attempts.max(1);
+    self.lockout = lockout;
+    self
+  }
+
+  pub fn is_locked(&self, at: Instant) -> bool {
+    self.locked_until.is_some_and(|until| at < until)
+  }
+
+  /// Feed one key heard at `at`. A `#` ending an entry gives the action to
+  /// run, or why the entry was refused.
+  pub fn key(&mut self, key: char, at: Instant) -> Option<Result<&DtmfAction, String>> {
+    let key = key.to_ascii_uppercase();
+    if self
+      .last_key
+      .is_some_and(|last| at.saturating_duration_since(last) > self.timeout)
+    {
+      self.entry = None;
+    }
+    self.last_key = Some(at);
+
+    match key {
+      '*' => {
+        self.entry = Some(String::new());
+        None
+      }
+      '#' => {
+        let entry = self.entry.take()?;
+        if self.is_locked(at) {
+          return Some(Err(String::from("Commands are locked out")));
+        }
+        let Some(code) = entry.strip_prefix(&self.pin) else {
+          self.failures += 1;
+          if self.failures >= self.max_attempts {
+            self.failures = 0;
+            self.locked_until = Some(at + self.lockout);
+          }
+          return Some(Err(String::from("Wrong PIN")));
+        };
+        self.failures = 0;
+        Some(
+          self
+            .commands
+            .get(code)
+            .ok_or(format!("Unknown command {}", code)),
+        )
+      }
+      _ => {
+        if let Some(entry) = self.entry.as_mut().filter(|entry| entry.len() < 32) {
+          entry.push(key);
+        }
+        None
+      }
+    }
+  }
+}
+
+fn validate_keys(what: &str, keys: &str) -> Result<(), String> {
+  if keys.is_empty() || !keys.chars().all(|c| is_key(c) && c != '*' && c != '#') {
+    return Err(format!(
+      "{} {:?} must be DTMF keys other than * and #",
+      what, keys
+    ));
+  }
+  Ok(())
+}
@@ -0,0 +1,124 @@
+use crate::{channel::Channel, device::Sa818};
+use std::{
+  io::{Read, Write},
+  thread,
+  time::{Duration, Instant},
+};
+
+/// Outcome of one look at the priority channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchEvent {
+  /// Nothing on the priority channel, back on the primary.
+  PriorityIdle { rssi: u8 },
+  /// Traffic on the priority channel, staying there.
+  PriorityActive { rssi: u8 },
+  /// Traffic on the priority channel ended, back on the primary.
+  PriorityEnded { rssi: u8 },
+}
+
+/// Listens on a primary channel, periodically checking a priority channel.
+///
+/// The priority channel is checked every `interval` by retuning and sampling
+/// the RSSI for `sample_time`. While it is active the module stays there,
+/// once it goes quiet the primary channel is restored.
+pub struct DualWatch<T> {
+  device: Sa818<T>,
+  primary: Channel,
+  priority: Channel,
+  interval: Duration,
+  sample_time: Duration,
+  sample_interval: Duration,
+  threshold: u8,
+  on_priority: bool,
+  next_check: Option<Instant>,
+}
+
+impl<T: Read + Write> DualWatch<T> {
+  pub fn new(device: Sa818<T>, primary: Channel, priority: Channel) -> Self {
+    Self {
+      device,
+      primary,
+      priority,
+      interval: Duration::from_secs(2),
+      sample_time: Duration::from_millis(150),
+      sample_interval: Duration::from_millis(20),
+      threshold: 60,
+      on_priority: false,
+      next_check: None,
+    }
+  }
+
+  /// Time spent on the primary channel between priority checks.
+  pub fn interval(mut self, interval: Duration) -> Self {
+    self.interval = interval;
+    self
+  }
+
+  /// Time spent sampling the priority channel on each check.
+  pub fn sample_time(mut self, sample_time: Duration) -> Self {
+    self.sample_time = sample_time;
+    self
+  }
+
+  /// Raw RSSI at or above which the priority channel counts as active.
+  pub fn threshold(mut self, threshold: u8) -> Self {
+    self.threshold = threshold;
+    self
+  }
+
+  pub fn on_priority(&self) -> bool {
+    self.on_priority
+  }
+
+  /// Tune the primary channel and start the check timer.
+  pub fn start(&mut self) -> Result<(), String> {
+    self.device.write_config(&self.primary)?;
+    self.on_priority = false;
+    self.next_check = Some(Instant::now() + self.interval);
+    Ok(())
+  }
+
+  /// Wait for the next check and look at the priority channel, starting on
+  /// the primary channel if [`DualWatch::start`] was not called.
+  ///
+  /// While staying on an active priority channel checks follow each other
+  /// without waiting.
+  pub fn check(&mut self) -> Result<WatchEvent, String> {
+    if !self.on_priority {
+      if self.next_check.is_none() {
+        self.start()?;
+      }
+      if let Some(next_check) = self.next_check {
+        let now = Instant::now();
+        if next_check > now {
+          thread::sleep(next_check - now);
+        }
+      }
+      self.device.write_config(&self.priority)?;
+    }
+
+    let rssi = self
+      .device
+      .peak_rssi(self.sample_time, self.sample_interval)?;
+    let active = rssi >= self.threshold;
+    let event = match (self.on_priority, active) {
+      (_, true) => WatchEvent::PriorityActive { rssi },
+      (false, false) => WatchEvent::PriorityIdle { rssi },
+      (true, false) => WatchEvent::PriorityEnded { rssi },
+    };
+    if !active {
+      self.device.write_config(&self.primary)?;
+      self.next_check = Some(Instant::now() + self.interval);
+    }
+    self.on_priority = active;
+    Ok(event)
+  }
+}
+
+impl<T: Read + Write> Iterator for DualWatch<T> {
+  type Item = Result<WatchEvent, String>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    Some(self.check())
+  }
+}
@@ -1,4 +1,5 @@
-use crate::channel::Command;
+use crate::{channel::Command, read_string};
+use std::io::{Read, Write};
 
 #[derive(Debug)]
 pub enum FilterState {
@@ -22,14 +23,26 @@ pub struct FilterConfig {
   low_pass: FilterState,
 }
 
-impl FilterConfig {
-  pub fn default() -> Self {
+impl Default for FilterConfig {
+  fn default() -> Self {
     FilterConfig {
       preemphasis: FilterState::Normal,
       high_pass: FilterState::Normal,
       low_pass: FilterState::Normal,
     }
   }
+}
+
+impl FilterConfig {
+  /// Every filter bypassed, flat audio for data modems.
+  pub fn flat() -> Self {
+    FilterConfig {
+      preemphasis: FilterState::Bypass,
+      high_pass: FilterState::Bypass,
+      low_pass: FilterState::Bypass,
+    }
+  }
+
   pub fn preemphasis(mut self, state: FilterState) -> Self {
     self.preemphasis = state;
     self
@@ -56,4 +69,16 @@ impl FilterConfig {
       expected_response: "+DMOSETFILTER: 0".to_string(),
     })
   }
+
+  pub fn write_config<T: Read + Write>(&self, io: &mut T) -> Result<String, String> {
+    let command = self.generate_command()?;
+    io.write_all(format!("{}\r\n", command.command).as_bytes())
+      .map_err(|e| e.to_string())?;
+    let response = read_string(io)?;
+    // Modules differ on the space after the colon.
+    if response.trim().replace(' ', "") != command.expected_response.replace(' ', "") {
+      return Err(format!("Invalid Response: {}", response));
+    }
+    Ok(response)
+  }
 }
@@ -0,0 +1,344 @@
+//! Minimal FLAC writing: 16 bit mono, fixed predictors and Rice coded
+//! residuals.
+use crate::audio::AudioSink;
+use std::{
+  fs::File,
+  io::{BufWriter, Seek, SeekFrom, Write},
+  path::Path,
+};
+
+const BLOCK: usize = 4096;
+/// Offset of the STREAMINFO fields filled in by `finish`.
+const STREAMINFO: u64 = 8;
+
+/// Collects bits most significant first.
+#[derive(Default)]
+struct BitWriter {
+  bytes: Vec<u8>,
+  bits: u64,
+  count: u32,
+}
+
+impl BitWriter {
+  fn put(&mut self, value: u64, bits: u32) {
+    for bit in (0..bits).rev() {
+      self.bits = self.bits << 1 | (value >> bit & 1);
+      self.count += 1;
+      if self.count == 8 {
+        self.bytes.push(self.bits as u8);
+        self.bits = 0;
+        self.count = 0;
+      }
+    }
+  }
+
+  fn unary(&mut self, zeros: u32) {
+    for _ in 0..zeros {
+      self.put(0, 1);
+    }
+    self.put(1, 1);
+  }
+
+  /// Pad with zeros to a byte boundary and hand the bytes over.
+  fn finish(mut self) -> Vec<u8> {
+    if self.count > 0 {
+      self.put(0, 8 - self.count);
+    }
+    self.bytes
+  }
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+  bytes.iter().fold(0u8, |mut crc, &byte| {
+    crc ^= byte;
+    for _ in 0..8 {
+      crc = if crc & 0x80 != 0 {
+        crc << 1 ^ 0x07
+      } else {
+        crc << 1
+      };
+    }
+    crc
+  })
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+  bytes.iter().fold(0u16, |mut crc, &byte| {
+    crc ^= (byte as u16) << 8;
+    for _ in 0..8 {
+      crc = if crc & 0x8000 != 0 {
+        crc << 1 ^ 0x8005
+      } else {
+        crc << 1
+      };
+    }
+    crc
+  })
+}
+
+/// Frame number in the UTF-8 like coding of frame headers.
+fn utf8(value: u32) -> Vec<u8> {
+  if value < 0x80 {
+    return vec![value as u8];
+  }
+  let continuation = match value {
+    0..0x800 => 1,
+    0x800..0x1_0000 => 2,
+    0x1_0000..0x20_0000 => 3,
+    0x20_0000..0x400_0000 => 4,
+    _ => 5,
+  };
+  let mut bytes =
+    vec![(0xFF00u16 >> (continuation + 1)) as u8 | (value >> (6 * continuation)) as u8];
+  for i in (0..continuation).rev() {
+    bytes.push(0x80 | (value >> (6 * i) & 0x3F) as u8);
+  }
+  bytes
+}
+
+/// Residual of the fixed predictor of `order` for each sample after the
+/// warm-up ones.
+fn residual(samples: &[i32], order: usize) -> Vec<i32> {
+  (order..samples.len())
+    .map(|i| {
+      let s = |back: usize| samples[i - back];
+      s(0)
+        - match order {
+          0 => 0,
+          1 => s(1),
+          2 => 2 * s(1) - s(2),
+          3 => 3 * s(1) - 3 * s(2) + s(3),
+          _ => 4 * s(1) - 6 * s(2) + 4 * s(3) - s(4),
+        }
+    })
+    .collect()
+}
+
+fn zigzag(value: i32) -> u32 {
+  (value << 1 ^ value >> 31) as u32
+}
+
+/// Rice parameter with the fewest bits for `residual`, and the bits.
+fn rice_parameter(residual: &[i32]) -> (u32, u64) {
+  (0..15)
+    .map(|k| {
+      let bits = residual
+        .iter()
+        .map(|&r| (zigzag(r) >> k) as u64 + 1 + k as u64)
+        .sum();
+      (k, bits)
+    })
+    .min_by_key(|&(_, bits)| bits)
+    .unwrap()
+}
+
+fn sample_rate_code(sample_rate: u32) -> u64 {
+  match sample_rate {
+    8000 => 0b0100,
+    16000 => 0b0101,
+    22050 => 0b0110,
+    24000 => 0b0111,
+    32000 => 0b1000,
+    44100 => 0b1001,
+    48000 => 0b1010,
+    96000 => 0b1011,
+    // From STREAMINFO.
+    _ => 0b0000,
+  }
+}
+
+/// Writes 16 bit mono FLAC files, about half the size of WAV for received
+/// audio.
+pub struct FlacWriter<W: Write + Seek> {
+  writer: W,
+  sample_rate: u32,
+  block: Vec<i32>,
+  frames: u32,
+  samples: u64,
+  last_block: usize,
+  min_frame: usize,
+  max_frame: usize,
+}
+
+impl FlacWriter<BufWriter<File>> {
+  pub fn create<P: AsRef<Path>>(path: P, sample_rate: u32) -> Result<Self, String> {
+    let path = path.as_ref();
+    let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    FlacWriter::new(BufWriter::new(file), sample_rate)
+  }
+}
+
+impl<W: Write + Seek> FlacWriter<W> {
+  pub fn new(mut writer: W, sample_rate: u32) -> Result<Self, String> {
+    if sample_rate == 0 || sample_rate >= 1 << 20 {
+      return Err(format!("Invalid FLAC sample rate {}", sample_rate));
+    }
+    let mut header = b"fLaC".to_vec();
+    // Last metadata block, STREAMINFO, 34 bytes.
+    header.extend_from_slice(&[0x80, 0, 0, 34]);
+    header.extend(Self::streaminfo(sample_rate, BLOCK, BLOCK, 0, 0, 0));
+    writer.write_all(&header).map_err(|e| e.to_string())?;
+    Ok(Self {
+      writer,
+      sample_rate,
+      block: Vec::with_capacity(BLOCK),
+      frames: 0,
+      samples: 0,
+      last_block: BLOCK,
+      min_frame: 0,
+      max_frame: 0,
+    })
+  }
+
+  fn streaminfo(
+    sample_rate: u32,
+    min_block: usize,
+    max_block: usize,
+    min_frame: usize,
+    max_frame: usize,
+    samples: u64,
+  ) -> Vec<u8> {
+    let mut bits = BitWriter::default();
+    bits.put(min_block as u64, 16);
+    bits.put(max_block as u64, 16);
+    bits.put(min_frame as u64, 24);
+    bits.put(max_frame as u64, 24);
+    bits.put(sample_rate as u64, 20);
+    // Mono, 16 bits.
+    bits.put(0, 3);
+    bits.put(15, 5);
+    bits.put(samples, 36);
+    // No MD5 of the audio.
+    bits.put(0, 64);
+    bits.put(0, 64);
+    bits.finish()
+  }
+
+  fn write_frame(&mut self) -> Result<(), String> {
+    let samples = std::mem::take(&mut self.block);
+    let mut bits = BitWriter::default();
+    bits.put(0b11_1111_1111_1110, 14);
+    bits.put(0, 2);
+    let size_code = if samples.len() == BLOCK {
+      0b1100
+    } else {
+      0b0111
+    };
+    bits.put(size_code, 4);
+    bits.put(sample_rate_code(self.sample_rate), 4);
+    // Mono, 16 bits.
+    bits.put(0, 4);
+    bits.put(0b100, 3);
+    bits.put(0, 1);
+    for byte in utf8(self.frames) {
+      bits.put(byte as u64, 8);
+    }
+    if size_code == 0b0111 {
+      bits.put(samples.len() as u64 - 1, 16);
+    }
+    let mut frame = bits.finish();
+    frame.push(crc8(&frame));
+
+    let mut bits = BitWriter::default();
+    let best = (0..=4.min(samples.len().saturating_sub(1)))
+      .map(|order| {
+        let residual = residual(&samples, order);
+        let (k, cost) = rice_parameter(&residual);
+        (order, residual, k, cost + 16 * order as u64)
+      })
+      .min_by_key(|(_, _, _, cost)| *cost);
+    match best {
+      Some((order, residual, k, cost)) if cost < 16 * samples.len() as u64 => {
+        bits.put(0b001000 | order as u64, 8 - 1);
+        bits.put(0, 1);
+        for &sample in &samples[..order] {
+          bits.put(sample as u16 as u64, 16);
+        }
+        // Rice with 4 bit parameters, a single partition.
+        bits.put(0, 2);
+        bits.put(0, 4);
+        bits.put(k as u64, 4);
+        for r in residual {
+          let u = zigzag(r);
+          bits.unary(u >> k);
+          bits.put((u & ((1 << k) - 1)) as u64, k);
+        }
+      }
+      _ => {
+        bits.put(0b000001, 7);
+        bits.put(0, 1);
+        for &sample in &samples {
+          bits.put(sample as u16 as u64, 16);
+        }
+      }
+    }
+    frame.extend(bits.finish());
+    let crc = crc16(&frame);
+    frame.extend_from_slice(&crc.to_be_bytes());
+
+    self.writer.write_all(&frame).map_err(|e| e.to_string())?;
+    self.frames += 1;
+    self.last_block = samples.len();
+    self.min_frame = match self.min_frame {
+      0 => frame.len(),
+      min => min.min(frame.len()),
+    };
+    self.max_frame = self.max_frame.max(frame.len());
+    self.block = samples;
+    self.block.clear();
+    Ok(())
+  }
+
+  /// Append samples, clipping them to -1.0..=1.0.
+  pub fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+    for &sample in samples {
+      self
+        .block
+        .push((sample.clamp(-1.0, 1.0) * 32767.0).round() as i32);
+      if self.block.len() == BLOCK {
+        self.write_frame()?;
+      }
+    }
+    self.samples += samples.len() as u64;
+    Ok(())
+  }
+
+  /// Write the last frame, fill in STREAMINFO and hand back the writer.
+  pub fn finish(mut self) -> Result<W, String> {
+    if !self.block.is_empty() {
+      self.write_frame()?;
+    }
+    // The block size range leaves out the last block, unless it is the only one.
+    let block = if self.frames > 1 {
+      BLOCK
+    } else {
+      self.last_block
+    };
+    let streaminfo = Self::streaminfo(
+      self.sample_rate,
+      block,
+      block,
+      self.min_frame,
+      self.max_frame,
+      self.samples,
+    );
+    self
+      .writer
+      .seek(SeekFrom::Start(STREAMINFO))
+      .and_then(|_| self.writer.write_all(&streaminfo))
+      .and_then(|_| self.writer.seek(SeekFrom::End(0)))
+      .and_then(|_| self.writer.flush())
+      .map_err(|e| e.to_string())?;
+    Ok(self.writer)
+  }
+}
+
+impl<W: Write + Seek> AudioSink for FlacWriter<W> {
+  fn sample_rate(&self) -> u32 {
+    self.sample_rate
+  }
+
+  fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+    FlacWriter::write(self, samples)
+  }
+}
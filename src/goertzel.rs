@@ -0,0 +1,61 @@
+//! Single frequency power measurement.
+use std::f32::consts::PI;
+
+/// Goertzel filter for one frequency.
+#[derive(Debug, Clone, Copy)]
+pub struct Goertzel {
+  frequency: f32,
+  coefficient: f32,
+}
+
+impl Goertzel {
+  pub fn new(frequency: f32, sample_rate: u32) -> Self {
+    Self {
+      frequency,
+      coefficient: 2.0 * (2.0 * PI * frequency / sample_rate as f32).cos(),
+    }
+  }
+
+  pub fn frequency(&self) -> f32 {
+    self.frequency
+  }
+
+  /// Squared amplitude of the frequency in `samples`, a full scale sine gives
+  /// about 1.0 when the block holds a whole number of its periods.
+  pub fn power(&self, samples: &[f32]) -> f32 {
+    let (mut s1, mut s2) = (0.0f32, 0.0f32);
+    for &sample in samples {
+      let s0 = sample + self.coefficient * s1 - s2;
+      s2 = s1;
+      s1 = s0;
+    }
+    let power = s1 * s1 + s2 * s2 - self.coefficient * s1 * s2;
+    let n = samples.len().max(1) as f32;
+    4.0 * power / (n * n)
+  }
+
+  /// Like [`Goertzel::power`] with the block weighted by `window`.
+  pub fn windowed_power(&self, samples: &[f32], window: &[f32]) -> f32 {
+    let (mut s1, mut s2) = (0.0f32, 0.0f32);
+    for (&sample, &weight) in samples.iter().zip(window) {
+      let s0 = sample * weight + self.coefficient * s1 - s2;
+      s2 = s1;
+      s1 = s0;
+    }
+    let power = s1 * s1 + s2 * s2 - self.coefficient * s1 * s2;
+    let gain: f32 = window
+      .iter()
+      .take(samples.len())
+      .sum::<f32>()
+      .max(f32::EPSILON);
+    4.0 * power / (gain * gain)
+  }
+}
+
+/// Hann window of `len` points.
+pub fn hann(len: usize) -> Vec<f32> {
+  let n = len.max(2) as f32 - 1.0;
+  (0..len)
+    .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / n).cos())
+    .collect()
+}
@@ -1,82 +1,362 @@
 use core::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum DcsSuffix {
   Inverted,
   Normal,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GroupSel {
-  Ctcss(u8),
-  Dcs(u32, DcsSuffix),
+  Ctcss(Ctcss),
+  Dcs(Dcs),
 }
 
-const CTCSS_FREQ: [&'static str; 39] = [
-  "0", "67.0", "71.9", "74.4", "77.0", "79.7", "82.5", "85.4", "88.5", "91.5", "94.8", "97.4",
-  "100.0", "103.5", "107.2", "110.9", "114.8", "118.8", "123.0", "127.3", "131.8", "136.5",
-  "141.3", "146.2", "151.4", "156.7", "162.2", "167.9", "173.8", "179.9", "186.2", "192.8",
-  "203.5", "210.7", "218.1", "225.7", "233.6", "241.8", "250.3",
+/// CTCSS tones in Hz, the module numbers them from 1.
+const CTCSS_FREQ: [f32; 38] = [
+  67.0, 71.9, 74.4, 77.0, 79.7, 82.5, 85.4, 88.5, 91.5, 94.8, 97.4, 100.0, 103.5, 107.2, 110.9,
+  114.8, 118.8, 123.0, 127.3, 131.8, 136.5, 141.3, 146.2, 151.4, 156.7, 162.2, 167.9, 173.8, 179.9,
+  186.2, 192.8, 203.5, 210.7, 218.1, 225.7, 233.6, 241.8, 250.3,
 ];
 
-impl GroupSel {
-  pub fn new_dcs(code: u32, suffix: DcsSuffix) -> Result<Self, String> {
-    if code < 23 || code > 754 {
-      return Err(format!("Invalid ctcss code {}", code));
+/// A CTCSS tone the module supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ctcss(u8);
+
+impl Ctcss {
+  /// Default tolerance of [`Ctcss::from_hz`] users, in Hz.
+  pub const TOLERANCE: f32 = 0.05;
+
+  /// Tone by module index, 1 (67.0 Hz) to 38 (250.3 Hz).
+  pub fn from_index(index: u8) -> Result<Self, String> {
+    if index == 0 || index as usize > CTCSS_FREQ.len() {
+      return Err(format!("Invalid ctcss code {}", index));
     }
-    Ok(GroupSel::Dcs(code, suffix))
+    Ok(Ctcss(index))
   }
-  pub fn new_ctcss(code: u8) -> Result<Self, String> {
-    if code == 0 || code > 38 {
-      return Err(format!("Invalid ctcss code {}", code));
+
+  /// Tone within `tolerance` Hz of `hz`.
+  pub fn from_hz(hz: f32, tolerance: f32) -> Result<Self, String> {
+    if !hz.is_finite() {
+      return Err(format!("{} Hz is not a valid ctcss", hz));
+    }
+    let nearest = Ctcss::nearest(hz);
+    if (nearest.hz() - hz).abs() > tolerance {
+      return Err(format!("{} Hz is not a valid ctcss", hz));
     }
-    Ok(GroupSel::Ctcss(code))
+    Ok(nearest)
+  }
+
+  /// Closest tone to `hz`.
+  pub fn nearest(hz: f32) -> Self {
+    Ctcss::all()
+      .min_by(|a, b| (a.hz() - hz).abs().total_cmp(&(b.hz() - hz).abs()))
+      .unwrap()
+  }
+
+  /// Every tone, lowest first.
+  pub fn all() -> impl Iterator<Item = Ctcss> {
+    (1..=CTCSS_FREQ.len() as u8).map(Ctcss)
+  }
+
+  pub fn index(&self) -> u8 {
+    self.0
+  }
+
+  pub fn hz(&self) -> f32 {
+    CTCSS_FREQ[self.0 as usize - 1]
+  }
+
+  /// Index as the module writes it, "0008" for 88.5 Hz.
+  pub fn module_code(&self) -> String {
+    format!("{:04}", self.0)
   }
 }
 
-pub fn parse_dcs(mut dcs_string: String) -> Result<GroupSel, String> {
-  let last = dcs_string.pop();
-  match last {
-    Some(char) => {
-      let code = dcs_string.parse::<u32>().map_err(|e| e.to_string())?;
-      match char {
-        'N' => {
-          return Ok(GroupSel::Dcs(code, DcsSuffix::Normal));
+/// Parses "88.5", "88.5Hz" or "88.5 Hz".
+impl FromStr for Ctcss {
+  type Err = String;
+
+  fn from_str(tone: &str) -> Result<Self, Self::Err> {
+    let hz = tone.trim();
+    let hz = hz
+      .strip_suffix("Hz")
+      .or_else(|| hz.strip_suffix("hz"))
+      .unwrap_or(hz)
+      .trim();
+    let hz = hz
+      .parse::<f32>()
+      .map_err(|_| format!("{} is not a valid ctcss", tone))?;
+    Ctcss::from_hz(hz, Ctcss::TOLERANCE).map_err(|_| format!("{} is not a valid ctcss", tone))
+  }
+}
+
+impl TryFrom<f32> for Ctcss {
+  type Error = String;
+
+  fn try_from(hz: f32) -> Result<Self, Self::Error> {
+    Ctcss::from_hz(hz, Ctcss::TOLERANCE)
+  }
+}
+
+impl fmt::Display for Ctcss {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:.1} Hz", self.hz())
+  }
+}
+
+/// The 104 standard DCS codes, written with their octal digits.
+const DCS_CODES: [u16; 104] = [
+  23, 25, 26, 31, 32, 36, 43, 47, 51, 53, 54, 65, 71, 72, 73, 74, 114, 115, 116, 122, 125, 131,
+  132, 134, 143, 145, 152, 155, 156, 162, 165, 172, 174, 205, 212, 223, 225, 226, 243, 244, 245,
+  246, 251, 252, 255, 261, 263, 265, 266, 271, 274, 306, 311, 315, 325, 331, 332, 343, 346, 351,
+  356, 364, 365, 371, 411, 412, 413, 423, 431, 432, 445, 446, 452, 454, 455, 462, 464, 465, 466,
+  503, 506, 516, 523, 526, 532, 546, 565, 606, 612, 624, 627, 631, 632, 654, 662, 664, 703, 712,
+  723, 731, 732, 734, 743, 754,
+];
+
+/// Golay (23,12) generator polynomial used by DCS.
+const GOLAY_POLY: u32 = 0xC75;
+const CODEWORD_MASK: u32 = (1 << 23) - 1;
+
+/// A standard DCS code and its polarity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Dcs {
+  code: u16,
+  polarity: DcsSuffix,
+}
+
+impl Dcs {
+  /// Code written with its octal digits, 23 for D023.
+  pub fn new(code: u16, polarity: DcsSuffix) -> Result<Self, String> {
+    if !DCS_CODES.contains(&code) {
+      return Err(format!("Invalid dcs code {:03}", code));
+    }
+    Ok(Dcs { code, polarity })
+  }
+
+  /// Every standard code with normal polarity, lowest first.
+  pub fn all() -> impl Iterator<Item = Dcs> {
+    DCS_CODES.iter().map(|&code| Dcs {
+      code,
+      polarity: DcsSuffix::Normal,
+    })
+  }
+
+  /// Code written with its octal digits, 23 for D023.
+  pub fn code(&self) -> u16 {
+    self.code
+  }
+
+  pub fn polarity(&self) -> DcsSuffix {
+    self.polarity
+  }
+
+  /// Numeric value of the code, 0o23 for D023.
+  pub fn octal(&self) -> u16 {
+    octal(self.code)
+  }
+
+  /// The 23 bit Golay codeword sent for this code, in transmission order
+  /// from bit 0, inverted for inverted polarity.
+  pub fn codeword(&self) -> u32 {
+    let word = golay(self.octal());
+    match self.polarity {
+      DcsSuffix::Normal => word,
+      DcsSuffix::Inverted => !word & CODEWORD_MASK,
+    }
+  }
+
+  /// Other codes, standard or not, a decoder can't tell apart from this one
+  /// because their codeword is a rotation of this one's, as octal digits.
+  pub fn aliases(&self) -> Vec<(u16, DcsSuffix)> {
+    let codeword = self.codeword();
+    let mut aliases = Vec::new();
+    for value in 0..512u16 {
+      for polarity in [DcsSuffix::Normal, DcsSuffix::Inverted] {
+        let code = digits(value);
+        if (code, polarity) == (self.code, self.polarity) {
+          continue;
         }
-        'I' => {
-          return Ok(GroupSel::Dcs(code, DcsSuffix::Inverted));
+        let word = match polarity {
+          DcsSuffix::Normal => golay(value),
+          DcsSuffix::Inverted => !golay(value) & CODEWORD_MASK,
+        };
+        if (0..23).any(|n| rotate(codeword, n) == word) {
+          aliases.push((code, polarity));
+        }
+      }
+    }
+    aliases
+  }
+
+  /// The standard code sending `bits`, 23 bits in received order from bit 0
+  /// starting anywhere in the codeword. Among aliases normal polarity wins,
+  /// then the lowest code.
+  pub fn from_bits(bits: u32) -> Option<Dcs> {
+    let mut found: Option<Dcs> = None;
+    for n in 0..23 {
+      let word = rotate(bits & CODEWORD_MASK, n);
+      for (polarity, word) in [
+        (DcsSuffix::Normal, word),
+        (DcsSuffix::Inverted, !word & CODEWORD_MASK),
+      ] {
+        let value = (word & 0x1FF) as u16;
+        if golay(value) != word {
+          continue;
         }
-        _ => {
-          return Err(format!("Invalid dcs suffix {}", char));
+        let Ok(dcs) = Dcs::new(digits(value), polarity) else {
+          continue;
+        };
+        let rank = |dcs: &Dcs| (dcs.polarity == DcsSuffix::Inverted, dcs.code);
+        if found.is_none_or(|best| rank(&dcs) < rank(&best)) {
+          found = Some(dcs);
         }
       }
     }
-    None => Err("Error Empty dcs string".to_string()),
+    found
+  }
+
+  /// The standard code with `polarity` putting the same bits on air, every
+  /// inverted standard code sends the bits of a normal one, 023I those of 047N.
+  pub fn with_polarity(&self, polarity: DcsSuffix) -> Option<Dcs> {
+    if self.polarity == polarity {
+      return Some(*self);
+    }
+    self
+      .aliases()
+      .into_iter()
+      .filter(|&(_, alias)| alias == polarity)
+      .find_map(|(code, polarity)| Dcs::new(code, polarity).ok())
+  }
+
+  /// Whether both codes put the same bit pattern on air.
+  pub fn is_alias(&self, other: &Dcs) -> bool {
+    let codeword = other.codeword();
+    (0..23).any(|n| rotate(self.codeword(), n) == codeword)
+  }
+
+  /// Code as the module expects it, "023N" or "754I".
+  pub fn module_code(&self) -> String {
+    self.to_string()
+  }
+}
+
+/// Octal digits written as a decimal number to their value, 23 to 0o23.
+fn octal(code: u16) -> u16 {
+  (code / 100) * 64 + (code / 10 % 10) * 8 + code % 10
+}
+
+/// Value to its octal digits written as a decimal number, 0o23 to 23.
+fn digits(value: u16) -> u16 {
+  (value / 64) * 100 + (value / 8 % 8) * 10 + value % 8
+}
+
+/// 9 code bits and the fixed 100 marker followed by 11 parity bits.
+fn golay(value: u16) -> u32 {
+  let data = value as u32 & 0x1FF | 1 << 11;
+  let mut parity = data << 11;
+  for bit in (11..23).rev() {
+    if parity >> bit & 1 == 1 {
+      parity ^= GOLAY_POLY << (bit - 11);
+    }
   }
+  data | parity << 12
 }
 
-pub fn parse_ctcss(ctcss: &String) -> Result<GroupSel, String> {
-  let code = CTCSS_FREQ.iter().position(|&f| f == ctcss);
-  match code {
-    Some(code) => Ok(GroupSel::Ctcss(code as u8)),
-    None => Err(format!("{} is not a valid ctcss", ctcss)),
+fn rotate(word: u32, n: u32) -> u32 {
+  (word << n | word >> ((23 - n) % 23)) & CODEWORD_MASK
+}
+
+/// Parses "D023N", "023I", "23N" or "023", normal polarity by default.
+impl FromStr for Dcs {
+  type Err = String;
+
+  fn from_str(dcs: &str) -> Result<Self, Self::Err> {
+    let invalid = || format!("{} is not a valid dcs", dcs);
+    let code = dcs.trim();
+    let code = code.strip_prefix(['D', 'd']).unwrap_or(code);
+    let (code, polarity) = match code.chars().last() {
+      Some('N' | 'n') => (&code[..code.len() - 1], DcsSuffix::Normal),
+      Some('I' | 'i') => (&code[..code.len() - 1], DcsSuffix::Inverted),
+      _ => (code, DcsSuffix::Normal),
+    };
+    if code.is_empty() || code.len() > 3 || !code.chars().all(|c| ('0'..='7').contains(&c)) {
+      return Err(invalid());
+    }
+    let code = code.parse::<u16>().map_err(|_| invalid())?;
+    Dcs::new(code, polarity).map_err(|_| invalid())
+  }
+}
+
+impl fmt::Display for Dcs {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let suffix = match self.polarity {
+      DcsSuffix::Normal => 'N',
+      DcsSuffix::Inverted => 'I',
+    };
+    write!(f, "{:03}{}", self.code, suffix)
   }
 }
 
+impl From<Dcs> for GroupSel {
+  fn from(dcs: Dcs) -> Self {
+    GroupSel::Dcs(dcs)
+  }
+}
+
+impl From<Ctcss> for GroupSel {
+  fn from(ctcss: Ctcss) -> Self {
+    GroupSel::Ctcss(ctcss)
+  }
+}
+
+impl GroupSel {
+  pub fn new_dcs(code: u32, suffix: DcsSuffix) -> Result<Self, String> {
+    let code = u16::try_from(code).map_err(|_| format!("Invalid dcs code {}", code))?;
+    Dcs::new(code, suffix).map(GroupSel::Dcs)
+  }
+  pub fn new_ctcss(code: u8) -> Result<Self, String> {
+    Ctcss::from_index(code).map(GroupSel::Ctcss)
+  }
+  /// Human readable tone, "88.5" for ctcss and "023N" for dcs.
+  pub fn tone_string(&self) -> String {
+    match self {
+      GroupSel::Ctcss(ctcss) => format!("{:.1}", ctcss.hz()),
+      GroupSel::Dcs(dcs) => dcs.to_string(),
+    }
+  }
+}
+
+pub fn parse_dcs(dcs_string: String) -> Result<GroupSel, String> {
+  dcs_string.parse::<Dcs>().map(GroupSel::Dcs)
+}
+
+pub fn parse_ctcss(ctcss: &str) -> Result<GroupSel, String> {
+  ctcss.parse::<Ctcss>().map(GroupSel::Ctcss)
+}
+
+/// Parse either a ctcss frequency like "88.5" or a dcs code like "023N",
+/// "D023" or "023". A bare number that is also a ctcss tone, like "100", is
+/// the tone.
+pub fn parse_tone(tone: &str) -> Result<GroupSel, String> {
+  let code = tone.trim();
+  if code.starts_with(['D', 'd']) || code.ends_with(['N', 'I', 'n', 'i']) {
+    return parse_dcs(tone.to_string());
+  }
+  parse_ctcss(tone).or_else(|e| parse_dcs(tone.to_string()).map_err(|_| e))
+}
+
 impl fmt::Display for GroupSel {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     match self {
-      GroupSel::Ctcss(code) => {
-        write!(f, "{}", code)
+      GroupSel::Ctcss(ctcss) => {
+        write!(f, "{}", ctcss.module_code())
+      }
+      GroupSel::Dcs(dcs) => {
+        write!(f, "{}", dcs.module_code())
       }
-      GroupSel::Dcs(code, suffix) => match suffix {
-        crate::group_call::DcsSuffix::Inverted => {
-          write!(f, "{}I", code)
-        }
-        crate::group_call::DcsSuffix::Normal => {
-          write!(f, "{}N", code)
-        }
-      },
     }
   }
 }
@@ -0,0 +1,128 @@
+//! HDLC framing as used by AX.25: flags, bit stuffing and the frame check
+//! sequence. Bits are in line order, least significant bit of each byte
+//! first, before NRZI coding.
+
+/// Flag delimiting frames.
+pub const FLAG: u8 = 0x7E;
+
+/// Shortest frame passed on, two addresses, control and the FCS.
+const MIN_FRAME: usize = 17;
+/// Longest frame collected before giving up on it.
+const MAX_FRAME: usize = 2048;
+
+/// CRC-16/X.25 frame check sequence of `data`.
+pub fn fcs(data: &[u8]) -> u16 {
+  let mut crc = 0xFFFFu16;
+  for &byte in data {
+    crc ^= byte as u16;
+    for _ in 0..8 {
+      crc = if crc & 1 == 1 {
+        crc >> 1 ^ 0x8408
+      } else {
+        crc >> 1
+      };
+    }
+  }
+  !crc
+}
+
+fn push_byte(bits: &mut Vec<bool>, byte: u8) {
+  bits.extend((0..8).map(|i| byte >> i & 1 == 1));
+}
+
+/// Bits of `frame` with its FCS appended and stuffed, between `preamble`
+/// and `tail` flags.
+pub fn encode(frame: &[u8], preamble: usize, tail: usize) -> Vec<bool> {
+  let mut bits = Vec::with_capacity((preamble + tail + frame.len() + 2) * 10);
+  for _ in 0..preamble.max(1) {
+    push_byte(&mut bits, FLAG);
+  }
+  let fcs = fcs(frame).to_le_bytes();
+  let mut ones = 0;
+  for &byte in frame.iter().chain(&fcs) {
+    for i in 0..8 {
+      let bit = byte >> i & 1 == 1;
+      bits.push(bit);
+      ones = if bit { ones + 1 } else { 0 };
+      if ones == 5 {
+        bits.push(false);
+        ones = 0;
+      }
+    }
+  }
+  for _ in 0..tail.max(1) {
+    push_byte(&mut bits, FLAG);
+  }
+  bits
+}
+
+/// Finds frames with a good FCS in a stream of bits.
+#[derive(Debug, Default)]
+pub struct HdlcDecoder {
+  ones: u32,
+  byte: u8,
+  bit_count: u32,
+  data: Vec<u8>,
+  in_frame: bool,
+}
+
+impl HdlcDecoder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Feed one bit, returning the frame, without FCS, a flag just closed.
+  pub fn push(&mut self, bit: bool) -> Option<Vec<u8>> {
+    if bit {
+      self.ones += 1;
+      if self.ones >= 7 {
+        // Abort, or noise between frames.
+        self.in_frame = false;
+        return None;
+      }
+      self.add(true);
+      return None;
+    }
+
+    let ones = std::mem::take(&mut self.ones);
+    match ones {
+      6 => {
+        // The flag's first seven bits are already in the partial byte.
+        let frame = (self.in_frame && self.bit_count == 7)
+          .then(|| std::mem::take(&mut self.data))
+          .filter(|data| data.len() >= MIN_FRAME)
+          .and_then(|mut data| {
+            let received = u16::from_le_bytes([data[data.len() - 2], data[data.len() - 1]]);
+            data.truncate(data.len() - 2);
+            (fcs(&data) == received).then_some(data)
+          });
+        self.in_frame = true;
+        self.data.clear();
+        self.byte = 0;
+        self.bit_count = 0;
+        frame
+      }
+      // A stuffed zero.
+      5 => None,
+      _ => {
+        self.add(false);
+        None
+      }
+    }
+  }
+
+  fn add(&mut self, bit: bool) {
+    if !self.in_frame {
+      return;
+    }
+    self.byte = self.byte >> 1 | (bit as u8) << 7;
+    self.bit_count += 1;
+    if self.bit_count == 8 {
+      self.data.push(self.byte);
+      self.bit_count = 0;
+      if self.data.len() > MAX_FRAME {
+        self.in_frame = false;
+      }
+    }
+  }
+}
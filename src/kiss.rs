@@ -0,0 +1,155 @@
+//! KISS, the framing packet software uses to talk to a TNC.
+use std::time::Duration;
+
+pub const FEND: u8 = 0xC0;
+pub const FESC: u8 = 0xDB;
+pub const TFEND: u8 = 0xDC;
+pub const TFESC: u8 = 0xDD;
+
+/// Longest frame collected before it is dropped.
+const MAX_FRAME: usize = 4096;
+
+/// A frame from the host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KissCommand {
+  /// AX.25 frame, without FCS, to send on a port.
+  Data {
+    port: u8,
+    frame: Vec<u8>,
+  },
+  /// Keyed time before data in 10 ms units.
+  TxDelay(u8),
+  /// Chance, out of 256, of sending once the channel is clear.
+  Persistence(u8),
+  /// Wait between channel checks in 10 ms units.
+  SlotTime(u8),
+  /// Keyed time after data in 10 ms units.
+  TxTail(u8),
+  FullDuplex(bool),
+  SetHardware(Vec<u8>),
+  /// Leave KISS mode.
+  Return,
+}
+
+impl KissCommand {
+  fn parse(frame: &[u8]) -> Option<Self> {
+    let (&kind, data) = frame.split_first()?;
+    if kind == 0xFF {
+      return Some(KissCommand::Return);
+    }
+    let port = kind >> 4;
+    let value = data.first().copied();
+    Some(match kind & 0x0F {
+      0 => KissCommand::Data {
+        port,
+        frame: data.to_vec(),
+      },
+      1 => KissCommand::TxDelay(value?),
+      2 => KissCommand::Persistence(value?),
+      3 => KissCommand::SlotTime(value?),
+      4 => KissCommand::TxTail(value?),
+      5 => KissCommand::FullDuplex(value? != 0),
+      6 => KissCommand::SetHardware(data.to_vec()),
+      _ => return None,
+    })
+  }
+}
+
+/// A data frame for the host, escaped and delimited.
+pub fn encode(port: u8, frame: &[u8]) -> Vec<u8> {
+  let mut bytes = Vec::with_capacity(frame.len() + 4);
+  bytes.push(FEND);
+  bytes.push(port << 4);
+  for &byte in frame {
+    match byte {
+      FEND => bytes.extend_from_slice(&[FESC, TFEND]),
+      FESC => bytes.extend_from_slice(&[FESC, TFESC]),
+      _ => bytes.push(byte),
+    }
+  }
+  bytes.push(FEND);
+  bytes
+}
+
+/// Splits a byte stream from the host into commands.
+#[derive(Debug, Default)]
+pub struct KissDecoder {
+  frame: Vec<u8>,
+  escaped: bool,
+}
+
+impl KissDecoder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Feed bytes as they arrive, returning the commands they complete.
+  /// Unknown commands and broken escapes are dropped.
+  pub fn push(&mut self, bytes: &[u8]) -> Vec<KissCommand> {
+    let mut commands = Vec::new();
+    for &byte in bytes {
+      match (byte, self.escaped) {
+        (FEND, _) => {
+          if let Some(command) = KissCommand::parse(&self.frame) {
+            commands.push(command);
+          }
+          self.frame.clear();
+          self.escaped = false;
+        }
+        (FESC, false) => self.escaped = true,
+        (_, true) => {
+          self.escaped = false;
+          match byte {
+            TFEND => self.frame.push(FEND),
+            TFESC => self.frame.push(FESC),
+            _ => self.frame.push(byte),
+          }
+        }
+        _ => self.frame.push(byte),
+      }
+      if self.frame.len() > MAX_FRAME {
+        self.frame.clear();
+      }
+    }
+    commands
+  }
+}
+
+/// Channel access parameters the host sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KissParams {
+  pub tx_delay: Duration,
+  pub persistence: u8,
+  pub slot_time: Duration,
+  pub tx_tail: Duration,
+  pub full_duplex: bool,
+}
+
+impl Default for KissParams {
+  /// The KISS defaults: 500 ms delay, p = 63 and 100 ms slots.
+  fn default() -> Self {
+    Self {
+      tx_delay: Duration::from_millis(500),
+      persistence: 63,
+      slot_time: Duration::from_millis(100),
+      tx_tail: Duration::ZERO,
+      full_duplex: false,
+    }
+  }
+}
+
+impl KissParams {
+  /// Take a parameter command, returns false for anything else.
+  pub fn apply(&mut self, command: &KissCommand) -> bool {
+    let tens = |value: &u8| Duration::from_millis(*value as u64 * 10);
+    match command {
+      KissCommand::TxDelay(value) => self.tx_delay = tens(value),
+      KissCommand::Persistence(value) => self.persistence = *value,
+      KissCommand::SlotTime(value) => self.slot_time = tens(value),
+      KissCommand::TxTail(value) => self.tx_tail = tens(value),
+      KissCommand::FullDuplex(value) => self.full_duplex = *value,
+      _ => return false,
+    }
+    true
+  }
+}
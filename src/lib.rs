@@ -1,8 +1,41 @@
+pub mod afsk;
+#[cfg(feature = "alsa")]
+pub mod alsa;
+pub mod aprs;
+pub mod audio;
+pub mod ax25;
+pub mod band_plan;
 pub mod channel;
+pub mod channel_bank;
+pub mod chirp;
+pub(crate) mod csv;
+pub mod cw_id;
+pub mod dcs_decoder;
+pub mod device;
+pub mod dtmf;
+pub mod dual_watch;
 pub mod filter_config;
+pub mod flac;
+pub mod goertzel;
 pub mod group_call;
+pub mod hdlc;
+pub mod kiss;
+pub mod nmea;
+pub mod pocsag;
+pub mod ptt;
+pub mod recorder;
+pub mod resample;
+pub mod rssi;
+pub mod rssi_log;
+pub mod scanner;
+pub mod squelch;
 pub mod tail_tone;
+pub mod timestamp;
+pub mod tnc;
+pub mod tone_scan;
+pub mod tracker;
 pub mod volume_config;
+pub mod wav;
 use std::io::{BufRead, BufReader, Read, Write};
 
 use crate::channel::Channel;
@@ -10,6 +43,7 @@ use crate::filter_config::FilterConfig;
 use crate::volume_config::VolumeConfig;
 use tail_tone::TailTone;
 
+#[allow(dead_code)]
 struct Sa818Config {
   channel_conf: Option<Channel>,
   filter_conf: Option<FilterConfig>,
@@ -17,8 +51,8 @@ struct Sa818Config {
   volume_conf: Option<VolumeConfig>,
 }
 
-impl Sa818Config {
-  pub fn default() -> Self {
+impl Default for Sa818Config {
+  fn default() -> Self {
     Self {
       channel_conf: Some(Channel::default()),
       filter_conf: Some(filter_config::FilterConfig::default()),
@@ -62,7 +96,7 @@ pub fn get_rssi<T: Read + Write>(io: &mut T) -> Result<u8, String> {
     .next()
     .unwrap()
     .parse::<u8>()
-    .map_err(|e| format!("Failed to parse rssi value: {}", e.to_string()))?;
+    .map_err(|e| format!("Failed to parse rssi value: {}", e))?;
   Ok(rssi)
 }
 
@@ -0,0 +1,97 @@
+//! Positions from a GPS receiver's NMEA 0183 sentences.
+use crate::aprs::Position;
+use std::io::{BufRead, ErrorKind};
+
+/// XOR of the bytes between '$' and '*'.
+pub fn checksum(body: &str) -> u8 {
+  body.bytes().fold(0, |sum, b| sum ^ b)
+}
+
+/// Keeps the altitude from GGA sentences for the positions of RMC ones.
+#[derive(Debug, Default)]
+pub struct NmeaParser {
+  altitude: Option<f64>,
+}
+
+impl NmeaParser {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Take a sentence from any talker, returns the position of a valid RMC
+  /// fix. Anything else, including sentences failing their checksum, gives
+  /// nothing.
+  pub fn push(&mut self, line: &str) -> Option<Position> {
+    let sentence = line.trim().strip_prefix('$')?;
+    let body = match sentence.split_once('*') {
+      Some((body, sum)) => (u8::from_str_radix(sum, 16).ok()? == checksum(body)).then_some(body)?,
+      None => sentence,
+    };
+    let fields: Vec<&str> = body.split(',').collect();
+    match fields[0].get(2..)? {
+      "GGA" if fields.len() > 9 => {
+        let fixed = fields[6].parse::<u8>().is_ok_and(|quality| quality > 0);
+        self.altitude = fixed.then(|| fields[9].parse().ok()).flatten();
+        None
+      }
+      "RMC" if fields.len() > 8 && fields[2] == "A" => {
+        let latitude = coordinate(fields[3], fields[4], 2)?;
+        let longitude = coordinate(fields[5], fields[6], 3)?;
+        let mut position = Position::new(latitude, longitude).ok()?;
+        position.speed = fields[7].parse().ok();
+        position.course = fields[8].parse().ok();
+        position.altitude = self.altitude;
+        Some(position)
+      }
+      _ => None,
+    }
+  }
+}
+
+/// "4903.50", "N" to degrees.
+fn coordinate(value: &str, hemisphere: &str, degree_digits: usize) -> Option<f64> {
+  let degrees = value.get(..degree_digits)?.parse::<f64>().ok()?;
+  let minutes = value.get(degree_digits..)?.parse::<f64>().ok()?;
+  let value = degrees + minutes / 60.0;
+  match hemisphere {
+    "N" | "E" => Some(value),
+    "S" | "W" => Some(-value),
+    _ => None,
+  }
+}
+
+/// Positions from a stream of sentences, a GPS on a serial port or a
+/// gpsd socket watching in NMEA mode.
+pub struct NmeaReader<R> {
+  reader: R,
+  parser: NmeaParser,
+  line: String,
+}
+
+impl<R: BufRead> NmeaReader<R> {
+  pub fn new(reader: R) -> Self {
+    Self {
+      reader,
+      parser: NmeaParser::new(),
+      line: String::new(),
+    }
+  }
+
+  /// Read up to the next fix, `None` at the end of the stream. Read
+  /// timeouts, as serial ports give between sentences, are waited out.
+  pub fn next_position(&mut self) -> Result<Option<Position>, String> {
+    loop {
+      match self.reader.read_line(&mut self.line) {
+        Ok(0) => return Ok(None),
+        Ok(_) => {}
+        Err(e) if e.kind() == ErrorKind::TimedOut => continue,
+        Err(e) => return Err(format!("Failed to read NMEA: {}", e)),
+      }
+      let position = self.parser.push(&self.line);
+      self.line.clear();
+      if position.is_some() {
+        return Ok(position);
+      }
+    }
+  }
+}
@@ -0,0 +1,277 @@
+//! POCSAG paging: codewords, batches and baseband audio for the transmitter.
+use std::iter::repeat_n;
+
+/// Codeword starting each batch.
+pub const SYNC: u32 = 0x7CD2_15D8;
+/// Codeword filling unused slots.
+pub const IDLE: u32 = 0x7A89_C197;
+/// Bits of alternating ones and zeros ahead of the first batch.
+pub const PREAMBLE_BITS: usize = 576;
+/// Codewords in a batch after the sync codeword, two per frame.
+const BATCH: usize = 16;
+/// BCH(31,21) generator, x^10 + x^9 + x^8 + x^6 + x^5 + x^3 + 1.
+const GENERATOR: u32 = 0x769;
+/// Largest 21 bit pager address.
+pub const MAX_RIC: u32 = (1 << 21) - 1;
+
+/// Codeword from 21 bits of data: the BCH check bits and even parity.
+pub fn codeword(data: u32) -> u32 {
+  let mut remainder = (data & 0x1F_FFFF) << 10;
+  for bit in (10..31).rev() {
+    if remainder >> bit & 1 == 1 {
+      remainder ^= GENERATOR << (bit - 10);
+    }
+  }
+  let word = (data & 0x1F_FFFF) << 11 | remainder << 1;
+  word | word.count_ones() & 1
+}
+
+/// Whether a received codeword has no bit errors.
+pub fn check(codeword: u32) -> bool {
+  self::codeword(codeword >> 11) == codeword
+}
+
+/// What a page carries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+  /// Digits, space, '-', 'U', '[' and ']'.
+  Numeric(String),
+  /// 7 bit ASCII.
+  Alphanumeric(String),
+  /// Only the address, the function bits pick the alert.
+  Tone,
+}
+
+/// A message for a pager address (RIC).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+  ric: u32,
+  function: u8,
+  message: Message,
+}
+
+fn numeric_digit(c: char) -> Option<u32> {
+  Some(match c {
+    '0'..='9' => c as u32 - '0' as u32,
+    'U' | 'u' => 0xB,
+    ' ' => 0xC,
+    '-' => 0xD,
+    ']' | ')' => 0xE,
+    '[' | '(' => 0xF,
+    _ => return None,
+  })
+}
+
+fn check_ric(ric: u32) -> Result<(), String> {
+  if ric > MAX_RIC {
+    return Err(format!("RIC {} is over {}", ric, MAX_RIC));
+  }
+  Ok(())
+}
+
+impl Page {
+  /// Numeric message, with the usual function 0.
+  pub fn numeric(ric: u32, text: &str) -> Result<Self, String> {
+    check_ric(ric)?;
+    if let Some(c) = text.chars().find(|&c| numeric_digit(c).is_none()) {
+      return Err(format!("{:?} can't be sent in a numeric page", c));
+    }
+    Ok(Self {
+      ric,
+      function: 0,
+      message: Message::Numeric(text.to_string()),
+    })
+  }
+
+  /// Alphanumeric message, with the usual function 3.
+  pub fn alphanumeric(ric: u32, text: &str) -> Result<Self, String> {
+    check_ric(ric)?;
+    if let Some(c) = text.chars().find(|c| !c.is_ascii()) {
+      return Err(format!("{:?} can't be sent in an alphanumeric page", c));
+    }
+    Ok(Self {
+      ric,
+      function: 3,
+      message: Message::Alphanumeric(text.to_string()),
+    })
+  }
+
+  /// Tone only page with function 0 to 3.
+  pub fn tone(ric: u32, function: u8) -> Result<Self, String> {
+    check_ric(ric)?;
+    Self {
+      ric,
+      function: 0,
+      message: Message::Tone,
+    }
+    .function(function)
+  }
+
+  /// Override the function bits, pagers use them to pick an alert.
+  pub fn function(mut self, function: u8) -> Result<Self, String> {
+    if function > 3 {
+      return Err(format!("Invalid function {}, use 0 to 3", function));
+    }
+    self.function = function;
+    Ok(self)
+  }
+
+  pub fn ric(&self) -> u32 {
+    self.ric
+  }
+
+  pub fn message(&self) -> &Message {
+    &self.message
+  }
+
+  fn address_codeword(&self) -> u32 {
+    codeword((self.ric >> 3) << 2 | self.function as u32)
+  }
+
+  /// Message codewords, the data flagged by the top bit.
+  fn message_codewords(&self) -> Vec<u32> {
+    // Characters go least significant bit first.
+    let mut bits: Vec<bool> = match &self.message {
+      Message::Numeric(text) => text
+        .chars()
+        .filter_map(numeric_digit)
+        .flat_map(|digit| (0..4).map(move |bit| digit >> bit & 1 == 1))
+        .collect(),
+      Message::Alphanumeric(text) => text
+        .bytes()
+        .flat_map(|byte| (0..7).map(move |bit| byte >> bit & 1 == 1))
+        .collect(),
+      Message::Tone => return Vec::new(),
+    };
+    // Numeric pages are filled with spaces, alphanumeric ones with NUL.
+    let fill = matches!(self.message, Message::Numeric(_));
+    while !bits.len().is_multiple_of(20) {
+      let space = [false, false, true, true];
+      bits.push(fill && space[bits.len() % 4]);
+    }
+    bits
+      .chunks(20)
+      .map(|chunk| {
+        let data = chunk.iter().fold(0, |word, &bit| word << 1 | bit as u32);
+        codeword(1 << 20 | data)
+      })
+      .collect()
+  }
+}
+
+/// Batches carrying `pages`, each starting with the sync codeword. Every
+/// address goes in the frame its RIC selects, unused slots are idle.
+pub fn codewords(pages: &[Page]) -> Vec<u32> {
+  let mut slots = Vec::new();
+  for page in pages {
+    let frame = (page.ric & 7) as usize;
+    while slots.len() % BATCH != frame * 2 {
+      slots.push(IDLE);
+    }
+    slots.push(page.address_codeword());
+    slots.extend(page.message_codewords());
+  }
+  if slots.is_empty() || !slots.len().is_multiple_of(BATCH) {
+    let padded = slots.len().div_ceil(BATCH).max(1) * BATCH;
+    slots.resize(padded, IDLE);
+  }
+  slots
+    .chunks(BATCH)
+    .flat_map(|batch| std::iter::once(SYNC).chain(batch.iter().copied()))
+    .collect()
+}
+
+/// The preamble and batches as bits, most significant first.
+pub fn bits(pages: &[Page]) -> Vec<bool> {
+  let preamble = (0..PREAMBLE_BITS).map(|i| i % 2 == 0);
+  let words = codewords(pages)
+    .into_iter()
+    .flat_map(|word| (0..32).rev().map(move |bit| word >> bit & 1 == 1));
+  preamble.chain(words).collect()
+}
+
+/// Turns pages into baseband audio for the module's AF input, the FM
+/// transmitter shifting the carrier with it.
+pub struct PocsagEncoder {
+  sample_rate: u32,
+  baud: u32,
+  level: f32,
+  inverted: bool,
+}
+
+impl PocsagEncoder {
+  /// 1200 baud, the most common rate.
+  pub fn new(sample_rate: u32) -> Self {
+    Self {
+      sample_rate,
+      baud: 1200,
+      level: 0.5,
+      inverted: false,
+    }
+  }
+
+  /// 512, 1200 or 2400 baud.
+  pub fn baud(mut self, baud: u32) -> Result<Self, String> {
+    if ![512, 1200, 2400].contains(&baud) {
+      return Err(format!(
+        "Invalid POCSAG rate {}, use 512, 1200 or 2400",
+        baud
+      ));
+    }
+    if self.sample_rate < baud * 4 {
+      return Err(format!(
+        "Sample rate {} is too low for {} baud",
+        self.sample_rate, baud
+      ));
+    }
+    self.baud = baud;
+    Ok(self)
+  }
+
+  /// Amplitude relative to full scale.
+  pub fn level(mut self, level: f32) -> Self {
+    self.level = level;
+    self
+  }
+
+  /// Send a one as a positive level instead of negative. Which shift a
+  /// pager expects depends on the wiring of the audio to the modulator.
+  pub fn inverted(mut self, inverted: bool) -> Self {
+    self.inverted = inverted;
+    self
+  }
+
+  /// Audio of raw bits, the edges rounded off by a moving average a
+  /// quarter of a bit long.
+  pub fn modulate(&self, bits: &[bool]) -> Vec<f32> {
+    let samples_per_bit = self.sample_rate as f64 / self.baud as f64;
+    let high = if self.inverted {
+      self.level
+    } else {
+      -self.level
+    };
+    let mut square = Vec::with_capacity((bits.len() as f64 * samples_per_bit) as usize + 1);
+    let mut clock = 0.0;
+    for &bit in bits {
+      clock += samples_per_bit;
+      let n = clock.round() as usize - square.len();
+      square.extend(repeat_n(if bit { high } else { -high }, n));
+    }
+    let taps = ((samples_per_bit / 4.0).round() as usize).max(1);
+    let mut sum = 0.0;
+    (0..square.len())
+      .map(|i| {
+        sum += square[i];
+        if i >= taps {
+          sum -= square[i - taps];
+        }
+        sum / taps.min(i + 1) as f32
+      })
+      .collect()
+  }
+
+  /// Audio of the preamble and batches for `pages`.
+  pub fn encode(&self, pages: &[Page]) -> Vec<f32> {
+    self.modulate(&bits(pages))
+  }
+}
@@ -0,0 +1,131 @@
+//! Keying the transmitter and sending audio.
+use crate::audio::AudioSink;
+use serialport::SerialPort;
+use std::time::Duration;
+
+/// Control of the module's PTT pin.
+pub trait Ptt {
+  fn set_keyed(&mut self, keyed: bool) -> Result<(), String>;
+}
+
+/// Serial port control line driving the PTT pin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlLine {
+  Rts,
+  Dtr,
+}
+
+/// PTT on the RTS or DTR line of a serial port, often the one the module
+/// is configured through.
+pub struct SerialPtt {
+  port: Box<dyn SerialPort>,
+  line: ControlLine,
+  active_low: bool,
+}
+
+impl SerialPtt {
+  pub fn new(port: Box<dyn SerialPort>, line: ControlLine) -> Self {
+    Self {
+      port,
+      line,
+      active_low: false,
+    }
+  }
+
+  /// Drive the line low to key, the SA818 PTT pin is active low when wired
+  /// straight to a line with TTL levels.
+  pub fn active_low(mut self, active_low: bool) -> Self {
+    self.active_low = active_low;
+    self
+  }
+}
+
+impl Ptt for SerialPtt {
+  fn set_keyed(&mut self, keyed: bool) -> Result<(), String> {
+    let level = keyed != self.active_low;
+    match self.line {
+      ControlLine::Rts => self.port.write_request_to_send(level),
+      ControlLine::Dtr => self.port.write_data_terminal_ready(level),
+    }
+    .map_err(|e| format!("Failed to set PTT: {}", e))
+  }
+}
+
+/// Keys the PTT around audio written to the module's AF input.
+pub struct Transmitter<P, S> {
+  ptt: P,
+  sink: S,
+  key_delay: Duration,
+  timeout: Duration,
+}
+
+impl<P: Ptt, S: AudioSink> Transmitter<P, S> {
+  pub fn new(ptt: P, sink: S) -> Self {
+    Self {
+      ptt,
+      sink,
+      key_delay: Duration::from_millis(100),
+      timeout: Duration::from_secs(180),
+    }
+  }
+
+  /// Silence sent after keying up while the module switches to transmit.
+  pub fn key_delay(mut self, key_delay: Duration) -> Self {
+    self.key_delay = key_delay;
+    self
+  }
+
+  /// Time-out timer, the longest transmission allowed including the key
+  /// delay. Longer audio is refused without keying up. Defaults to 3 minutes.
+  pub fn timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  pub fn sample_rate(&self) -> u32 {
+    self.sink.sample_rate()
+  }
+
+  pub fn timeout_limit(&self) -> Duration {
+    self.timeout
+  }
+
+  /// How long sending `samples` keys the transmitter.
+  pub fn duration(&self, samples: usize) -> Duration {
+    self.key_delay + Duration::from_secs_f64(samples as f64 / self.sink.sample_rate() as f64)
+  }
+
+  pub fn ptt(&mut self) -> &mut P {
+    &mut self.ptt
+  }
+
+  pub fn sink(&mut self) -> &mut S {
+    &mut self.sink
+  }
+
+  pub fn into_parts(self) -> (P, S) {
+    (self.ptt, self.sink)
+  }
+
+  /// Key up, play `samples` and unkey once they have been played. The PTT
+  /// is released even when playing fails.
+  pub fn transmit(&mut self, samples: &[f32]) -> Result<(), String> {
+    let duration = self.duration(samples.len());
+    if duration > self.timeout {
+      return Err(format!(
+        "Transmission of {:.1} s exceeds the {:.1} s time-out timer",
+        duration.as_secs_f32(),
+        self.timeout.as_secs_f32()
+      ));
+    }
+    let delay = vec![0.0; (self.sink.sample_rate() as f64 * self.key_delay.as_secs_f64()) as usize];
+    self.ptt.set_keyed(true)?;
+    let played = self
+      .sink
+      .write(&delay)
+      .and_then(|_| self.sink.write(samples))
+      .and_then(|_| self.sink.flush());
+    let released = self.ptt.set_keyed(false);
+    played.and(released)
+  }
+}
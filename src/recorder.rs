@@ -0,0 +1,327 @@
+//! Records traffic heard on a channel, a file per transmission with a JSON
+//! sidecar describing it.
+use crate::{channel::Channel, flac::FlacWriter, group_call::GroupSel, timestamp, wav::WavWriter};
+use std::{
+  fmt::Write as _,
+  fs::{self, File},
+  io::BufWriter,
+  path::{Path, PathBuf},
+  str::FromStr,
+  time::{Duration, SystemTime},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+  Wav,
+  Flac,
+}
+
+impl RecordFormat {
+  pub fn extension(&self) -> &'static str {
+    match self {
+      RecordFormat::Wav => "wav",
+      RecordFormat::Flac => "flac",
+    }
+  }
+}
+
+impl FromStr for RecordFormat {
+  type Err = String;
+
+  fn from_str(format: &str) -> Result<Self, Self::Err> {
+    match format.to_ascii_lowercase().as_str() {
+      "wav" => Ok(RecordFormat::Wav),
+      "flac" => Ok(RecordFormat::Flac),
+      _ => Err(format!(
+        "Unknown recording format {}, use wav or flac",
+        format
+      )),
+    }
+  }
+}
+
+/// The channel being recorded, for file names and sidecars.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelInfo {
+  pub name: Option<String>,
+  /// Receive frequency in MHz.
+  pub frequency: Option<f32>,
+  pub rx_tone: Option<GroupSel>,
+  pub tx_tone: Option<GroupSel>,
+}
+
+impl ChannelInfo {
+  pub fn new(name: Option<&str>, channel: &Channel) -> Self {
+    Self {
+      name: name.map(str::to_string),
+      frequency: channel.rx_frequency(),
+      rx_tone: channel.rx_conf().and_then(|conf| conf.group_sel),
+      tx_tone: channel.tx_conf().and_then(|conf| conf.group_sel),
+    }
+  }
+
+  /// Name, or else frequency, with only characters safe in file names.
+  fn label(&self) -> Option<String> {
+    let label = match (&self.name, self.frequency) {
+      (Some(name), _) => name.clone(),
+      (None, Some(frequency)) => format!("{:.4}", frequency),
+      (None, None) => return None,
+    };
+    Some(
+      label
+        .chars()
+        .map(|c| match c {
+          'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
+          _ => '_',
+        })
+        .collect(),
+    )
+  }
+}
+
+fn json_string(value: &str) -> String {
+  let mut json = String::from("\"");
+  for c in value.chars() {
+    match c {
+      '"' => json.push_str("\\\""),
+      '\\' => json.push_str("\\\\"),
+      c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+      c => json.push(c),
+    }
+  }
+  json.push('"');
+  json
+}
+
+/// A finished recording.
+#[derive(Debug, Clone)]
+pub struct RecordingInfo {
+  pub path: PathBuf,
+  pub channel: ChannelInfo,
+  pub started: SystemTime,
+  pub duration: Duration,
+  pub peak_rssi: Option<u8>,
+  pub sample_rate: u32,
+}
+
+impl RecordingInfo {
+  pub fn to_json(&self) -> String {
+    let or_null = |value: Option<String>| value.unwrap_or_else(|| String::from("null"));
+    let tone = |tone: Option<GroupSel>| or_null(tone.map(|t| json_string(&t.tone_string())));
+    let file = self
+      .path
+      .file_name()
+      .map(|name| name.to_string_lossy().into_owned())
+      .unwrap_or_default();
+    format!(
+      "{{\"file\":{},\"channel\":{},\"frequency\":{},\"rx_tone\":{},\"tx_tone\":{},\
+       \"start\":\"{}\",\"duration\":{:.3},\"peak_rssi\":{},\"sample_rate\":{}}}",
+      json_string(&file),
+      or_null(self.channel.name.as_deref().map(json_string)),
+      or_null(self.channel.frequency.map(|f| format!("{:.4}", f))),
+      tone(self.channel.rx_tone),
+      tone(self.channel.tx_tone),
+      timestamp::rfc3339(self.started),
+      self.duration.as_secs_f64(),
+      or_null(self.peak_rssi.map(|rssi| rssi.to_string())),
+      self.sample_rate
+    )
+  }
+
+  /// The sidecar next to the recording.
+  pub fn sidecar_path(&self) -> PathBuf {
+    self.path.with_extension("json")
+  }
+}
+
+#[derive(Debug, Clone)]
+pub enum RecorderEvent {
+  /// The squelch opened and a file was started.
+  Started(PathBuf),
+  /// The squelch stayed closed for the hang time, the file and its sidecar
+  /// are written.
+  Finished(RecordingInfo),
+  /// The recording was shorter than the minimum and was deleted.
+  Discarded(PathBuf),
+}
+
+enum Writer {
+  Wav(WavWriter<BufWriter<File>>),
+  Flac(FlacWriter<BufWriter<File>>),
+}
+
+impl Writer {
+  fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+    match self {
+      Writer::Wav(writer) => writer.write(samples),
+      Writer::Flac(writer) => writer.write(samples),
+    }
+  }
+
+  fn finish(self) -> Result<(), String> {
+    match self {
+      Writer::Wav(writer) => writer.finish().map(drop),
+      Writer::Flac(writer) => writer.finish().map(drop),
+    }
+  }
+}
+
+struct Recording {
+  writer: Writer,
+  path: PathBuf,
+  started: SystemTime,
+  samples: u64,
+  /// Samples since the squelch last closed, none while it is open.
+  closed: Option<u64>,
+  peak_rssi: Option<u8>,
+}
+
+/// Writes audio to a new file each time the squelch opens, closing it once
+/// the squelch has stayed closed for the hang time.
+pub struct Recorder {
+  dir: PathBuf,
+  format: RecordFormat,
+  channel: ChannelInfo,
+  sample_rate: u32,
+  hang_time: Duration,
+  min_duration: Duration,
+  current: Option<Recording>,
+}
+
+impl Recorder {
+  /// Recorder writing WAV files into `dir`, created if missing.
+  pub fn new<P: AsRef<Path>>(dir: P, sample_rate: u32) -> Result<Self, String> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    Ok(Self {
+      dir: dir.to_path_buf(),
+      format: RecordFormat::Wav,
+      channel: ChannelInfo::default(),
+      sample_rate,
+      hang_time: Duration::from_secs(2),
+      min_duration: Duration::ZERO,
+      current: None,
+    })
+  }
+
+  pub fn format(mut self, format: RecordFormat) -> Self {
+    self.format = format;
+    self
+  }
+
+  pub fn channel(mut self, channel: ChannelInfo) -> Self {
+    self.channel = channel;
+    self
+  }
+
+  /// Time the squelch has to stay closed to end a recording, 2 s by
+  /// default. Replies coming within it land in the same file.
+  pub fn hang_time(mut self, hang_time: Duration) -> Self {
+    self.hang_time = hang_time;
+    self
+  }
+
+  /// Delete recordings shorter than this, hang time included, such as
+  /// squelch bursts from noise.
+  pub fn min_duration(mut self, min_duration: Duration) -> Self {
+    self.min_duration = min_duration;
+    self
+  }
+
+  pub fn is_recording(&self) -> bool {
+    self.current.is_some()
+  }
+
+  fn samples(&self, duration: Duration) -> u64 {
+    (duration.as_secs_f64() * self.sample_rate as f64).round() as u64
+  }
+
+  fn start(&self, at: SystemTime) -> Result<Recording, String> {
+    let mut stem = timestamp::compact(at);
+    if let Some(label) = self.channel.label() {
+      stem = format!("{}_{}", stem, label);
+    }
+    let extension = self.format.extension();
+    let mut path = self.dir.join(format!("{}.{}", stem, extension));
+    let mut n = 1;
+    while path.exists() {
+      path = self.dir.join(format!("{}-{}.{}", stem, n, extension));
+      n += 1;
+    }
+    let writer = match self.format {
+      RecordFormat::Wav => Writer::Wav(WavWriter::create(&path, self.sample_rate)?),
+      RecordFormat::Flac => Writer::Flac(FlacWriter::create(&path, self.sample_rate)?),
+    };
+    Ok(Recording {
+      writer,
+      path,
+      started: at,
+      samples: 0,
+      closed: None,
+      peak_rssi: None,
+    })
+  }
+
+  /// Feed a block of received audio with the squelch state and RSSI read
+  /// for it, `at` being when the block started.
+  pub fn push(
+    &mut self,
+    samples: &[f32],
+    open: bool,
+    rssi: Option<u8>,
+    at: SystemTime,
+  ) -> Result<Vec<RecorderEvent>, String> {
+    let mut events = Vec::new();
+    if self.current.is_none() {
+      if !open {
+        return Ok(events);
+      }
+      let recording = self.start(at)?;
+      events.push(RecorderEvent::Started(recording.path.clone()));
+      self.current = Some(recording);
+    }
+    let hang = self.samples(self.hang_time);
+    let recording = self.current.as_mut().unwrap();
+    recording.writer.write(samples)?;
+    recording.samples += samples.len() as u64;
+    if open {
+      recording.closed = None;
+      if let Some(rssi) = rssi {
+        recording.peak_rssi = Some(recording.peak_rssi.map_or(rssi, |peak| peak.max(rssi)));
+      }
+    } else {
+      let closed = recording.closed.unwrap_or(0) + samples.len() as u64;
+      recording.closed = Some(closed);
+      if closed >= hang {
+        events.extend(self.finish()?);
+      }
+    }
+    Ok(events)
+  }
+
+  /// Close the file being recorded, if any, as at shutdown.
+  pub fn finish(&mut self) -> Result<Option<RecorderEvent>, String> {
+    let Some(recording) = self.current.take() else {
+      return Ok(None);
+    };
+    recording.writer.finish()?;
+    let duration = Duration::from_secs_f64(recording.samples as f64 / self.sample_rate as f64);
+    if duration < self.min_duration {
+      fs::remove_file(&recording.path)
+        .map_err(|e| format!("{}: {}", recording.path.display(), e))?;
+      return Ok(Some(RecorderEvent::Discarded(recording.path)));
+    }
+    let info = RecordingInfo {
+      path: recording.path,
+      channel: self.channel.clone(),
+      started: recording.started,
+      duration,
+      peak_rssi: recording.peak_rssi,
+      sample_rate: self.sample_rate,
+    };
+    let sidecar = info.sidecar_path();
+    fs::write(&sidecar, info.to_json() + "\n")
+      .map_err(|e| format!("{}: {}", sidecar.display(), e))?;
+    Ok(Some(RecorderEvent::Finished(info)))
+  }
+}
@@ -0,0 +1,185 @@
+//! Sample rate conversion, so files and sound cards at any rate fit a
+//! pipeline running at another.
+use crate::audio::{AudioSink, AudioSource};
+use std::f64::consts::PI;
+
+/// Zero crossings of the interpolation kernel on each side.
+const ZERO_CROSSINGS: f64 = 16.0;
+
+/// Windowed sinc interpolation between two rates, fed in blocks of any
+/// size. Going down in rate the kernel low-passes below the new Nyquist
+/// frequency.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+  from: u32,
+  to: u32,
+  /// Input samples per output sample.
+  step: f64,
+  cutoff: f64,
+  half: usize,
+  history: Vec<f32>,
+  /// Input position of the next output sample within `history`.
+  position: f64,
+}
+
+impl Resampler {
+  pub fn new(from: u32, to: u32) -> Result<Self, String> {
+    if from == 0 || to == 0 {
+      return Err(format!("Can't resample from {} Hz to {} Hz", from, to));
+    }
+    let cutoff = 0.95 * (to as f64 / from as f64).min(1.0);
+    let half = (ZERO_CROSSINGS / cutoff).ceil() as usize;
+    Ok(Self {
+      from,
+      to,
+      step: from as f64 / to as f64,
+      cutoff,
+      half,
+      history: vec![0.0; half],
+      position: half as f64,
+    })
+  }
+
+  pub fn from_rate(&self) -> u32 {
+    self.from
+  }
+
+  pub fn to_rate(&self) -> u32 {
+    self.to
+  }
+
+  /// Input samples an output sample waits for.
+  pub fn latency(&self) -> usize {
+    self.half
+  }
+
+  fn kernel(&self, distance: f64) -> f64 {
+    let x = distance * self.cutoff;
+    let sinc = if x.abs() < 1e-9 {
+      1.0
+    } else {
+      (PI * x).sin() / (PI * x)
+    };
+    let window = 0.5 + 0.5 * (PI * distance / self.half as f64).cos();
+    self.cutoff * sinc * window
+  }
+
+  /// Convert the next block of input.
+  pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+    if self.from == self.to {
+      return input.to_vec();
+    }
+    self.history.extend_from_slice(input);
+    let mut output = Vec::with_capacity((input.len() as f64 / self.step) as usize + 1);
+    while self.position + (self.half as f64) < self.history.len() as f64 {
+      let center = self.position.floor() as usize;
+      let mut sum = 0.0;
+      for k in center + 1 - self.half..=center + self.half {
+        sum += self.history[k] as f64 * self.kernel(self.position - k as f64);
+      }
+      output.push(sum as f32);
+      self.position += self.step;
+    }
+    // Keep only what later outputs still reach.
+    let keep_from = (self.position.floor() as usize + 1).saturating_sub(self.half);
+    self.history.drain(..keep_from);
+    self.position -= keep_from as f64;
+    output
+  }
+
+  /// Output held back for samples that never came, then start afresh.
+  pub fn flush(&mut self) -> Vec<f32> {
+    if self.from == self.to {
+      return Vec::new();
+    }
+    let tail = vec![0.0; self.half];
+    let output = self.process(&tail);
+    *self = Self::new(self.from, self.to).unwrap();
+    output
+  }
+}
+
+/// A source read at another rate.
+pub struct ResampledSource<S> {
+  source: S,
+  resampler: Resampler,
+  pending: Vec<f32>,
+  block: Vec<f32>,
+  ended: bool,
+}
+
+impl<S: AudioSource> ResampledSource<S> {
+  pub fn new(source: S, sample_rate: u32) -> Result<Self, String> {
+    Ok(Self {
+      resampler: Resampler::new(source.sample_rate(), sample_rate)?,
+      source,
+      pending: Vec::new(),
+      block: vec![0.0; 1024],
+      ended: false,
+    })
+  }
+
+  pub fn into_inner(self) -> S {
+    self.source
+  }
+}
+
+impl<S: AudioSource> AudioSource for ResampledSource<S> {
+  fn sample_rate(&self) -> u32 {
+    self.resampler.to_rate()
+  }
+
+  fn read(&mut self, buffer: &mut [f32]) -> Result<usize, String> {
+    while self.pending.is_empty() && !self.ended {
+      let n = self.source.read(&mut self.block)?;
+      if n == 0 {
+        self.pending = self.resampler.flush();
+        self.ended = true;
+      } else {
+        self.pending = self.resampler.process(&self.block[..n]);
+      }
+    }
+    let n = buffer.len().min(self.pending.len());
+    buffer[..n].copy_from_slice(&self.pending[..n]);
+    self.pending.drain(..n);
+    Ok(n)
+  }
+}
+
+/// A sink written at another rate. Each flush ends a stream, like a
+/// transmission, sending out what the resampler held back.
+pub struct ResampledSink<S> {
+  sink: S,
+  resampler: Resampler,
+}
+
+impl<S: AudioSink> ResampledSink<S> {
+  /// Sink taking audio at `sample_rate` for `sink`.
+  pub fn new(sink: S, sample_rate: u32) -> Result<Self, String> {
+    Ok(Self {
+      resampler: Resampler::new(sample_rate, sink.sample_rate())?,
+      sink,
+    })
+  }
+
+  pub fn into_inner(self) -> S {
+    self.sink
+  }
+}
+
+impl<S: AudioSink> AudioSink for ResampledSink<S> {
+  fn sample_rate(&self) -> u32 {
+    self.resampler.from_rate()
+  }
+
+  fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+    let output = self.resampler.process(samples);
+    self.sink.write(&output)
+  }
+
+  fn flush(&mut self) -> Result<(), String> {
+    let output = self.resampler.flush();
+    self.sink.write(&output)?;
+    self.sink.flush()
+  }
+}
@@ -0,0 +1,435 @@
+use crate::device::Sa818;
+use std::{
+  collections::VecDeque,
+  fmt, fs,
+  io::{Read, Write},
+  path::Path,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc, Arc,
+  },
+  thread::{self, JoinHandle},
+  time::{Duration, Instant, SystemTime},
+};
+
+/// How long to wait before retrying when another command holds the port.
+const BUSY_BACKOFF: Duration = Duration::from_millis(5);
+
+#[derive(Debug, Clone, Copy)]
+pub struct RssiSample {
+  pub timestamp: SystemTime,
+  pub value: u8,
+}
+
+impl RssiSample {
+  pub fn rssi(&self) -> Rssi {
+    Rssi(self.value)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RssiStats {
+  pub count: usize,
+  pub min: u8,
+  pub max: u8,
+  pub mean: f32,
+  pub median: u8,
+  pub p90: u8,
+}
+
+impl RssiStats {
+  pub fn from_values<I: IntoIterator<Item = u8>>(values: I) -> Option<Self> {
+    let mut sorted: Vec<u8> = values.into_iter().collect();
+    sorted.sort_unstable();
+    let count = sorted.len();
+    if count == 0 {
+      return None;
+    }
+    let sum: u32 = sorted.iter().map(|&v| v as u32).sum();
+    Some(RssiStats {
+      count,
+      min: sorted[0],
+      max: sorted[count - 1],
+      mean: sum as f32 / count as f32,
+      median: percentile(&sorted, 50.0)?,
+      p90: percentile(&sorted, 90.0)?,
+    })
+  }
+}
+
+/// Samples collected over a sliding time window.
+///
+/// Expiry is relative to the newest sample's timestamp, so the window does
+/// not shrink while polling is paused.
+#[derive(Debug, Clone)]
+pub struct RssiWindow {
+  span: Duration,
+  samples: VecDeque<RssiSample>,
+}
+
+impl RssiWindow {
+  pub fn new(span: Duration) -> Self {
+    Self {
+      span,
+      samples: VecDeque::new(),
+    }
+  }
+
+  pub fn push(&mut self, sample: RssiSample) {
+    self.samples.push_back(sample);
+    while let Some(oldest) = self.samples.front() {
+      match sample.timestamp.duration_since(oldest.timestamp) {
+        Ok(age) if age > self.span => {
+          self.samples.pop_front();
+        }
+        _ => break,
+      }
+    }
+  }
+
+  pub fn samples(&self) -> impl Iterator<Item = &RssiSample> {
+    self.samples.iter()
+  }
+
+  pub fn len(&self) -> usize {
+    self.samples.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.samples.is_empty()
+  }
+
+  /// Nearest-rank percentile, `p` in 0..=100.
+  pub fn percentile(&self, p: f32) -> Option<u8> {
+    percentile(&self.sorted_values(), p)
+  }
+
+  pub fn stats(&self) -> Option<RssiStats> {
+    RssiStats::from_values(self.samples.iter().map(|s| s.value))
+  }
+
+  fn sorted_values(&self) -> Vec<u8> {
+    let mut values: Vec<u8> = self.samples.iter().map(|s| s.value).collect();
+    values.sort_unstable();
+    values
+  }
+}
+
+fn percentile(sorted: &[u8], p: f32) -> Option<u8> {
+  if sorted.is_empty() || !(0.0..=100.0).contains(&p) {
+    return None;
+  }
+  let rank = (p / 100.0 * sorted.len() as f32).ceil() as usize;
+  Some(sorted[rank.saturating_sub(1).min(sorted.len() - 1)])
+}
+
+/// A sample together with the statistics of the window it landed in.
+#[derive(Debug, Clone, Copy)]
+pub struct RssiReading {
+  pub sample: RssiSample,
+  pub stats: RssiStats,
+}
+
+/// Polls the module RSSI at a fixed rate.
+///
+/// Use it directly as an (endless) iterator, or move it to a background
+/// thread with [`RssiMonitor::spawn`] or [`RssiMonitor::channel`]. Polling
+/// pauses while another command on the same [`Sa818`] handle holds the port.
+pub struct RssiMonitor<T> {
+  device: Sa818<T>,
+  interval: Duration,
+  window: RssiWindow,
+  next_poll: Option<Instant>,
+}
+
+impl<T: Read + Write> RssiMonitor<T> {
+  pub fn new(device: Sa818<T>) -> Self {
+    Self {
+      device,
+      interval: Duration::from_millis(250),
+      window: RssiWindow::new(Duration::from_secs(10)),
+      next_poll: None,
+    }
+  }
+
+  pub fn interval(mut self, interval: Duration) -> Self {
+    self.interval = interval;
+    self
+  }
+
+  pub fn window(mut self, span: Duration) -> Self {
+    self.window = RssiWindow::new(span);
+    self
+  }
+
+  pub fn current_window(&self) -> &RssiWindow {
+    &self.window
+  }
+
+  /// Wait for the next scheduled poll and take one sample.
+  pub fn poll(&mut self) -> Result<RssiReading, String> {
+    if let Some(next_poll) = self.next_poll {
+      let now = Instant::now();
+      if next_poll > now {
+        thread::sleep(next_poll - now);
+      }
+    }
+    let value = loop {
+      match self.device.try_lock()? {
+        Some(mut io) => break crate::get_rssi(&mut *io),
+        None => thread::sleep(BUSY_BACKOFF),
+      }
+    };
+    // Schedule at a fixed rate, dropping ticks we already missed.
+    let now = Instant::now();
+    let next_poll = self.next_poll.unwrap_or(now) + self.interval;
+    self.next_poll = Some(next_poll.max(now));
+
+    let sample = RssiSample {
+      timestamp: SystemTime::now(),
+      value: value?,
+    };
+    self.window.push(sample);
+    Ok(RssiReading {
+      sample,
+      stats: self.window.stats().unwrap(),
+    })
+  }
+}
+
+impl<T: Read + Write> Iterator for RssiMonitor<T> {
+  type Item = Result<RssiReading, String>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    Some(self.poll())
+  }
+}
+
+impl<T: Read + Write + Send + 'static> RssiMonitor<T> {
+  /// Poll on a background thread, handing every reading to `callback`.
+  pub fn spawn<F>(mut self, mut callback: F) -> MonitorHandle
+  where
+    F: FnMut(Result<RssiReading, String>) + Send + 'static,
+  {
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = Arc::clone(&stop);
+    let thread = thread::spawn(move || {
+      while !thread_stop.load(Ordering::Relaxed) {
+        callback(self.poll());
+      }
+    });
+    MonitorHandle {
+      stop,
+      thread: Some(thread),
+    }
+  }
+
+  /// Poll on a background thread, sending readings over a channel.
+  ///
+  /// Polling also stops once the receiver is dropped.
+  pub fn channel(mut self) -> (MonitorHandle, mpsc::Receiver<Result<RssiReading, String>>) {
+    let (sender, receiver) = mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = Arc::clone(&stop);
+    let thread = thread::spawn(move || {
+      while !thread_stop.load(Ordering::Relaxed) {
+        if sender.send(self.poll()).is_err() {
+          break;
+        }
+      }
+    });
+    let handle = MonitorHandle {
+      stop,
+      thread: Some(thread),
+    };
+    (handle, receiver)
+  }
+}
+
+/// Stops the background polling thread when dropped.
+pub struct MonitorHandle {
+  stop: Arc<AtomicBool>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl MonitorHandle {
+  /// Stop polling and wait for the thread to finish its current poll.
+  pub fn stop(mut self) {
+    self.shutdown();
+  }
+
+  fn shutdown(&mut self) {
+    self.stop.store(true, Ordering::Relaxed);
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
+
+impl Drop for MonitorHandle {
+  fn drop(&mut self) {
+    self.shutdown();
+  }
+}
+
+/// Raw RSSI value as reported by the module (0-255).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Rssi(pub u8);
+
+impl Rssi {
+  pub fn raw(&self) -> u8 {
+    self.0
+  }
+
+  /// Estimated input level in dBm.
+  pub fn dbm(&self, calibration: &Calibration) -> f32 {
+    calibration.dbm(self.0 as f32)
+  }
+
+  pub fn s_meter(&self, calibration: &Calibration) -> SMeter {
+    SMeter::from_dbm(self.dbm(calibration))
+  }
+}
+
+impl From<u8> for Rssi {
+  fn from(raw: u8) -> Self {
+    Rssi(raw)
+  }
+}
+
+impl fmt::Display for Rssi {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+/// Piecewise-linear mapping from raw RSSI to dBm.
+///
+/// Values outside the table are extrapolated from the nearest segment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibration {
+  points: Vec<(f32, f32)>,
+}
+
+impl Default for Calibration {
+  /// Nominal datasheet curve: about 0.5 dB per step, raw 0 at -135 dBm.
+  /// Individual modules vary by several dB, measure your own for accuracy.
+  fn default() -> Self {
+    Calibration {
+      points: vec![(0.0, -135.0), (255.0, -7.5)],
+    }
+  }
+}
+
+impl Calibration {
+  /// Build a calibration from `(raw, dBm)` points.
+  pub fn new(mut points: Vec<(f32, f32)>) -> Result<Self, String> {
+    if points.len() < 2 {
+      return Err(String::from("Calibration needs at least two points"));
+    }
+    if points
+      .iter()
+      .any(|(raw, dbm)| !raw.is_finite() || !dbm.is_finite())
+    {
+      return Err(String::from("Calibration points must be finite"));
+    }
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    if points.windows(2).any(|w| w[0].0 == w[1].0) {
+      return Err(String::from("Calibration has duplicate raw values"));
+    }
+    Ok(Calibration { points })
+  }
+
+  pub fn points(&self) -> &[(f32, f32)] {
+    &self.points
+  }
+
+  pub fn dbm(&self, raw: f32) -> f32 {
+    let segment = self
+      .points
+      .windows(2)
+      .find(|w| raw <= w[1].0)
+      .unwrap_or(&self.points[self.points.len() - 2..]);
+    let (x0, y0) = segment[0];
+    let (x1, y1) = segment[1];
+    y0 + (raw - x0) * (y1 - y0) / (x1 - x0)
+  }
+
+  /// Parse a table with one `raw,dbm` pair per line, `#` starts a comment.
+  pub fn parse(table: &str) -> Result<Self, String> {
+    let mut points = Vec::new();
+    for (number, line) in table.lines().enumerate() {
+      let line = line.split('#').next().unwrap().trim();
+      if line.is_empty() {
+        continue;
+      }
+      let (raw, dbm) = line
+        .split_once(',')
+        .ok_or(format!("Line {}: expected raw,dbm", number + 1))?;
+      let raw = raw
+        .trim()
+        .parse::<f32>()
+        .map_err(|e| format!("Line {}: {}", number + 1, e))?;
+      let dbm = dbm
+        .trim()
+        .parse::<f32>()
+        .map_err(|e| format!("Line {}: {}", number + 1, e))?;
+      points.push((raw, dbm));
+    }
+    Calibration::new(points)
+  }
+
+  pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+    let table = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    Calibration::parse(&table)
+  }
+
+  pub fn write_file<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+    fs::write(path, self.to_string()).map_err(|e| e.to_string())
+  }
+}
+
+impl fmt::Display for Calibration {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "# raw,dbm")?;
+    for (raw, dbm) in &self.points {
+      writeln!(f, "{},{}", raw, dbm)?;
+    }
+    Ok(())
+  }
+}
+
+/// S-meter reading using the VHF/UHF convention: S9 is -93 dBm, 6 dB per unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SMeter {
+  pub units: u8,
+  /// dB above S9, zero below S9.
+  pub over: u8,
+}
+
+impl SMeter {
+  pub fn from_dbm(dbm: f32) -> Self {
+    let s9 = -93.0;
+    if dbm >= s9 {
+      return SMeter {
+        units: 9,
+        over: (dbm - s9).round() as u8,
+      };
+    }
+    let units = 9.0 + ((dbm - s9) / 6.0).floor();
+    SMeter {
+      units: units.max(0.0) as u8,
+      over: 0,
+    }
+  }
+}
+
+impl fmt::Display for SMeter {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.over > 0 {
+      write!(f, "S{}+{}", self.units, self.over)
+    } else {
+      write!(f, "S{}", self.units)
+    }
+  }
+}
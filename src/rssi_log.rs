@@ -0,0 +1,233 @@
+use crate::timestamp;
+use std::{
+  fmt::Write as _,
+  fs::{self, File, OpenOptions},
+  io::{BufWriter, Write},
+  path::{Path, PathBuf},
+  str::FromStr,
+  time::{Duration, SystemTime},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+  Csv,
+  JsonLines,
+}
+
+impl LogFormat {
+  /// `.jsonl`/`.json` files are JSON lines, anything else is CSV.
+  pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+    match path.as_ref().extension().and_then(|e| e.to_str()) {
+      Some("jsonl") | Some("json") => LogFormat::JsonLines,
+      _ => LogFormat::Csv,
+    }
+  }
+
+  pub fn header(&self) -> Option<&'static str> {
+    match self {
+      LogFormat::Csv => Some("timestamp,frequency,rssi,dbm,squelch"),
+      LogFormat::JsonLines => None,
+    }
+  }
+
+  pub fn format(&self, record: &LogRecord) -> String {
+    match self {
+      LogFormat::Csv => record.to_csv(),
+      LogFormat::JsonLines => record.to_json(),
+    }
+  }
+}
+
+impl FromStr for LogFormat {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "csv" => Ok(LogFormat::Csv),
+      "jsonl" | "json" => Ok(LogFormat::JsonLines),
+      _ => Err(format!("Invalid log format {}", s)),
+    }
+  }
+}
+
+/// One RSSI measurement, fields the caller does not know are left empty.
+#[derive(Debug, Clone, Copy)]
+pub struct LogRecord {
+  pub timestamp: SystemTime,
+  /// MHz
+  pub frequency: Option<f32>,
+  pub rssi: u8,
+  pub dbm: Option<f32>,
+  pub squelch_open: Option<bool>,
+}
+
+impl LogRecord {
+  pub fn new(timestamp: SystemTime, rssi: u8) -> Self {
+    Self {
+      timestamp,
+      frequency: None,
+      rssi,
+      dbm: None,
+      squelch_open: None,
+    }
+  }
+
+  pub fn to_csv(&self) -> String {
+    let mut line = format!("{},", timestamp::rfc3339(self.timestamp));
+    if let Some(frequency) = self.frequency {
+      write!(line, "{:.4}", frequency).unwrap();
+    }
+    write!(line, ",{},", self.rssi).unwrap();
+    if let Some(dbm) = self.dbm {
+      write!(line, "{:.1}", dbm).unwrap();
+    }
+    line.push(',');
+    if let Some(open) = self.squelch_open {
+      line.push_str(squelch_name(open));
+    }
+    line
+  }
+
+  pub fn to_json(&self) -> String {
+    let frequency = match self.frequency {
+      Some(frequency) => format!("{:.4}", frequency),
+      None => String::from("null"),
+    };
+    let dbm = match self.dbm {
+      Some(dbm) => format!("{:.1}", dbm),
+      None => String::from("null"),
+    };
+    let squelch = match self.squelch_open {
+      Some(open) => format!("\"{}\"", squelch_name(open)),
+      None => String::from("null"),
+    };
+    format!(
+      "{{\"timestamp\":\"{}\",\"frequency\":{},\"rssi\":{},\"dbm\":{},\"squelch\":{}}}",
+      timestamp::rfc3339(self.timestamp),
+      frequency,
+      self.rssi,
+      dbm,
+      squelch
+    )
+  }
+}
+
+fn squelch_name(open: bool) -> &'static str {
+  match open {
+    true => "open",
+    false => "closed",
+  }
+}
+
+/// Appends records to a file, rotating it by size and/or age.
+///
+/// A rotated file is renamed to `<stem>-<opened at>.<ext>` and a fresh file
+/// is started at the original path.
+pub struct RssiLogger {
+  path: PathBuf,
+  format: LogFormat,
+  max_bytes: Option<u64>,
+  max_age: Option<Duration>,
+  file: BufWriter<File>,
+  bytes: u64,
+  opened_at: SystemTime,
+}
+
+impl RssiLogger {
+  pub fn create<P: AsRef<Path>>(path: P, format: LogFormat) -> Result<Self, String> {
+    let path = path.as_ref().to_path_buf();
+    let (file, bytes) = open_log(&path, format)?;
+    Ok(Self {
+      path,
+      format,
+      max_bytes: None,
+      max_age: None,
+      file,
+      bytes,
+      opened_at: SystemTime::now(),
+    })
+  }
+
+  pub fn rotate_size(mut self, max_bytes: u64) -> Self {
+    self.max_bytes = Some(max_bytes);
+    self
+  }
+
+  pub fn rotate_every(mut self, max_age: Duration) -> Self {
+    self.max_age = Some(max_age);
+    self
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  pub fn write(&mut self, record: &LogRecord) -> Result<(), String> {
+    if self.should_rotate(record.timestamp) {
+      self.rotate(record.timestamp)?;
+    }
+    let line = self.format.format(record);
+    writeln!(self.file, "{}", line).map_err(|e| e.to_string())?;
+    self.file.flush().map_err(|e| e.to_string())?;
+    self.bytes += line.len() as u64 + 1;
+    Ok(())
+  }
+
+  fn should_rotate(&self, now: SystemTime) -> bool {
+    let too_big = self.max_bytes.is_some_and(|max| self.bytes >= max);
+    let too_old = self.max_age.is_some_and(|max| {
+      now
+        .duration_since(self.opened_at)
+        .is_ok_and(|age| age >= max)
+    });
+    too_big || too_old
+  }
+
+  fn rotate(&mut self, now: SystemTime) -> Result<(), String> {
+    self.file.flush().map_err(|e| e.to_string())?;
+    fs::rename(&self.path, rotated_path(&self.path, self.opened_at)).map_err(|e| e.to_string())?;
+    let (file, bytes) = open_log(&self.path, self.format)?;
+    self.file = file;
+    self.bytes = bytes;
+    self.opened_at = now;
+    Ok(())
+  }
+}
+
+/// Open for appending, writing the header if the file is new.
+fn open_log(path: &Path, format: LogFormat) -> Result<(BufWriter<File>, u64), String> {
+  let file = OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(path)
+    .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+  let mut bytes = file.metadata().map_err(|e| e.to_string())?.len();
+  let mut file = BufWriter::new(file);
+  if let (0, Some(header)) = (bytes, format.header()) {
+    writeln!(file, "{}", header).map_err(|e| e.to_string())?;
+    bytes += header.len() as u64 + 1;
+  }
+  Ok((file, bytes))
+}
+
+/// Name for a rotated file, numbered if several rotations share a second.
+fn rotated_path(path: &Path, opened_at: SystemTime) -> PathBuf {
+  let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("rssi");
+  let ext = path.extension().and_then(|e| e.to_str());
+  let stamp = timestamp::compact(opened_at);
+  (0..)
+    .map(|n| {
+      let suffix = if n == 0 {
+        String::new()
+      } else {
+        format!("-{}", n)
+      };
+      let name = match ext {
+        Some(ext) => format!("{}-{}{}.{}", stem, stamp, suffix, ext),
+        None => format!("{}-{}{}", stem, stamp, suffix),
+      };
+      path.with_file_name(name)
+    })
+    .find(|candidate| !candidate.exists())
+    .unwrap()
+}
@@ -0,0 +1,277 @@
+use crate::{
+  channel::{Channel, FmBandwidth, FreqConf},
+  device::Sa818,
+};
+use std::{
+  io::{Read, Write},
+  thread,
+  time::{Duration, Instant, SystemTime},
+};
+
+/// Frequencies closer than this (MHz) are treated as the same channel.
+const FREQUENCY_TOLERANCE: f32 = 0.0005;
+
+/// What the scanner does when it finds a carrier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CarrierMode {
+  /// Record the activity and keep stepping.
+  Sweep,
+  /// Stay while the carrier is present, resume `hang` after it drops.
+  Carrier { hang: Duration },
+  /// Stay for a fixed time, then resume.
+  Timed(Duration),
+  /// Stop on the active channel until [`Scanner::resume`] is called.
+  Hold,
+}
+
+/// Result of listening to one channel.
+#[derive(Debug, Clone, Copy)]
+pub struct ScanStep {
+  pub index: usize,
+  pub frequency: f32,
+  /// Highest RSSI seen while listening.
+  pub rssi: u8,
+  pub active: bool,
+}
+
+/// Activity accumulated for one channel over all passes.
+#[derive(Debug, Clone, Copy)]
+pub struct ScanResult {
+  pub frequency: f32,
+  /// Number of times the scanner stopped on an active channel.
+  pub hits: u32,
+  pub peak: u8,
+  pub last: u8,
+  pub last_active: Option<SystemTime>,
+}
+
+/// Number of channels from `start` to `stop` inclusive in `step`s, all in MHz.
+pub fn range_count(start: f32, stop: f32, step: f32) -> Result<usize, String> {
+  if step <= 0.0 || stop < start {
+    return Err(String::from("Invalid scan range"));
+  }
+  // Allow for f32 rounding so the stop frequency itself is included.
+  Ok(((stop - start) as f64 / step as f64 + 0.01).floor() as usize + 1)
+}
+
+/// Simplex channels from `start` to `stop` inclusive, all in MHz.
+pub fn range_channels(
+  start: f32,
+  stop: f32,
+  step: f32,
+  bandwidth: FmBandwidth,
+) -> Result<Vec<Channel>, String> {
+  (0..range_count(start, stop, step)?)
+    .map(|i| {
+      let frequency = (start as f64 + i as f64 * step as f64) as f32;
+      Ok(
+        Channel::default()
+          .bandwidth(bandwidth)
+          .rx(FreqConf::new(frequency)?)
+          .tx(FreqConf::new(frequency)?),
+      )
+    })
+    .collect()
+}
+
+/// Steps the module through a list of channels, sampling RSSI on each.
+pub struct Scanner<T> {
+  device: Sa818<T>,
+  channels: Vec<Channel>,
+  results: Vec<ScanResult>,
+  lockout: Vec<f32>,
+  dwell: Duration,
+  sample_interval: Duration,
+  threshold: u8,
+  mode: CarrierMode,
+  position: usize,
+  held: Option<usize>,
+}
+
+impl<T: Read + Write> Scanner<T> {
+  /// Scan the given channels in order.
+  pub fn channels(device: Sa818<T>, channels: Vec<Channel>) -> Result<Self, String> {
+    if channels.is_empty() {
+      return Err(String::from("No channels to scan"));
+    }
+    let results = channels
+      .iter()
+      .map(|channel| {
+        let frequency = channel
+          .rx_frequency()
+          .ok_or(String::from("Rx frequency is not specified!"))?;
+        Ok(ScanResult {
+          frequency,
+          hits: 0,
+          peak: 0,
+          last: 0,
+          last_active: None,
+        })
+      })
+      .collect::<Result<Vec<_>, String>>()?;
+    Ok(Self {
+      device,
+      channels,
+      results,
+      lockout: Vec::new(),
+      dwell: Duration::from_millis(100),
+      sample_interval: Duration::from_millis(20),
+      threshold: 60,
+      mode: CarrierMode::Sweep,
+      position: 0,
+      held: None,
+    })
+  }
+
+  /// Scan simplex channels from `start` to `stop` inclusive, all in MHz.
+  pub fn range(
+    device: Sa818<T>,
+    start: f32,
+    stop: f32,
+    step: f32,
+    bandwidth: FmBandwidth,
+  ) -> Result<Self, String> {
+    Scanner::channels(device, range_channels(start, stop, step, bandwidth)?)
+  }
+
+  /// Time spent listening to each channel after tuning.
+  pub fn dwell(mut self, dwell: Duration) -> Self {
+    self.dwell = dwell;
+    self
+  }
+
+  /// Time between RSSI samples while listening.
+  pub fn sample_interval(mut self, interval: Duration) -> Self {
+    self.sample_interval = interval;
+    self
+  }
+
+  /// Raw RSSI at or above which a channel counts as active.
+  pub fn threshold(mut self, threshold: u8) -> Self {
+    self.threshold = threshold;
+    self
+  }
+
+  pub fn mode(mut self, mode: CarrierMode) -> Self {
+    self.mode = mode;
+    self
+  }
+
+  /// Skip `frequency` (MHz) until it is unlocked again.
+  pub fn lockout(&mut self, frequency: f32) {
+    if !self.is_locked_out(frequency) {
+      self.lockout.push(frequency);
+    }
+  }
+
+  pub fn unlock(&mut self, frequency: f32) {
+    self
+      .lockout
+      .retain(|locked| (locked - frequency).abs() > FREQUENCY_TOLERANCE);
+  }
+
+  pub fn is_locked_out(&self, frequency: f32) -> bool {
+    self
+      .lockout
+      .iter()
+      .any(|locked| (locked - frequency).abs() <= FREQUENCY_TOLERANCE)
+  }
+
+  pub fn results(&self) -> &[ScanResult] {
+    &self.results
+  }
+
+  pub fn channel_count(&self) -> usize {
+    self.channels.len()
+  }
+
+  /// Channels a pass visits, those not locked out.
+  pub fn unlocked_count(&self) -> usize {
+    self
+      .results
+      .iter()
+      .filter(|result| !self.is_locked_out(result.frequency))
+      .count()
+  }
+
+  /// Channel the scanner is holding on, if any.
+  pub fn held(&self) -> Option<usize> {
+    self.held
+  }
+
+  /// Continue scanning after a [`CarrierMode::Hold`] stop.
+  pub fn resume(&mut self) {
+    self.held = None;
+  }
+
+  /// Listen to the next channel that is not locked out.
+  ///
+  /// While holding, the held channel is sampled again without retuning.
+  pub fn step(&mut self) -> Result<ScanStep, String> {
+    if let Some(index) = self.held {
+      let rssi = self.listen(self.dwell)?;
+      return Ok(self.record(index, rssi));
+    }
+    let index = (0..self.channels.len())
+      .map(|offset| (self.position + offset) % self.channels.len())
+      .find(|&index| !self.is_locked_out(self.results[index].frequency))
+      .ok_or(String::from("All channels are locked out"))?;
+    self.position = (index + 1) % self.channels.len();
+
+    self.device.write_config(&self.channels[index])?;
+    let rssi = self.listen(self.dwell)?;
+    let step = self.record(index, rssi);
+    if step.active {
+      self.results[index].hits += 1;
+      self.stay(index)?;
+    }
+    Ok(step)
+  }
+
+  /// One pass over every channel that is not locked out.
+  pub fn sweep(&mut self) -> Result<Vec<ScanStep>, String> {
+    (0..self.unlocked_count()).map(|_| self.step()).collect()
+  }
+
+  fn stay(&mut self, index: usize) -> Result<(), String> {
+    match self.mode {
+      CarrierMode::Sweep => {}
+      CarrierMode::Hold => self.held = Some(index),
+      CarrierMode::Timed(duration) => {
+        let rssi = self.listen(duration)?;
+        self.record(index, rssi);
+      }
+      CarrierMode::Carrier { hang } => {
+        let mut last_heard = Instant::now();
+        while last_heard.elapsed() < hang {
+          thread::sleep(self.sample_interval);
+          let rssi = self.device.get_rssi()?;
+          if self.record(index, rssi).active {
+            last_heard = Instant::now();
+          }
+        }
+      }
+    }
+    Ok(())
+  }
+
+  fn listen(&self, duration: Duration) -> Result<u8, String> {
+    self.device.peak_rssi(duration, self.sample_interval)
+  }
+
+  fn record(&mut self, index: usize, rssi: u8) -> ScanStep {
+    let active = rssi >= self.threshold;
+    let result = &mut self.results[index];
+    result.last = rssi;
+    result.peak = result.peak.max(rssi);
+    if active {
+      result.last_active = Some(SystemTime::now());
+    }
+    ScanStep {
+      index,
+      frequency: result.frequency,
+      rssi,
+      active,
+    }
+  }
+}
@@ -0,0 +1,46 @@
+//! Reading the module's squelch from its SQ pin.
+use crate::tnc::ChannelBusy;
+use serialport::SerialPort;
+
+/// Serial port status line wired to the module's SQ pin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusLine {
+  Cts,
+  Dsr,
+  Cd,
+}
+
+/// Busy while the module's squelch is open, read from a status line.
+pub struct SquelchPin {
+  port: Box<dyn SerialPort>,
+  line: StatusLine,
+  active_low: bool,
+}
+
+impl SquelchPin {
+  pub fn new(port: Box<dyn SerialPort>, line: StatusLine) -> Self {
+    Self {
+      port,
+      line,
+      active_low: false,
+    }
+  }
+
+  /// Read the squelch as open while the line is deasserted.
+  pub fn active_low(mut self, active_low: bool) -> Self {
+    self.active_low = active_low;
+    self
+  }
+}
+
+impl ChannelBusy for SquelchPin {
+  fn busy(&mut self) -> Result<bool, String> {
+    let asserted = match self.line {
+      StatusLine::Cts => self.port.read_clear_to_send(),
+      StatusLine::Dsr => self.port.read_data_set_ready(),
+      StatusLine::Cd => self.port.read_carrier_detect(),
+    }
+    .map_err(|e| format!("Failed to read squelch: {}", e))?;
+    Ok(asserted != self.active_low)
+  }
+}
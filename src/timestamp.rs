@@ -0,0 +1,56 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// UTC date and time broken out of a `SystemTime`.
+struct Utc {
+  year: i64,
+  month: u32,
+  day: u32,
+  hour: u64,
+  minute: u64,
+  second: u64,
+  millis: u32,
+}
+
+impl From<SystemTime> for Utc {
+  fn from(time: SystemTime) -> Self {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    // Days to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = (secs / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    Utc {
+      year,
+      month,
+      day,
+      hour: secs % 86400 / 3600,
+      minute: secs % 3600 / 60,
+      second: secs % 60,
+      millis: since_epoch.subsec_millis(),
+    }
+  }
+}
+
+/// RFC 3339 timestamp with milliseconds, e.g. `2024-03-01T12:30:05.250Z`.
+pub fn rfc3339(time: SystemTime) -> String {
+  let t = Utc::from(time);
+  format!(
+    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+    t.year, t.month, t.day, t.hour, t.minute, t.second, t.millis
+  )
+}
+
+/// Timestamp safe to use in file names, e.g. `20240301T123005Z`.
+pub fn compact(time: SystemTime) -> String {
+  let t = Utc::from(time);
+  format!(
+    "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+    t.year, t.month, t.day, t.hour, t.minute, t.second
+  )
+}
@@ -0,0 +1,186 @@
+//! A KISS TNC on the module: CSMA channel access and keyed AFSK transmit.
+use crate::{
+  afsk::{AfskModulator, BAUD},
+  audio::AudioSink,
+  device::Sa818,
+  kiss::{KissCommand, KissParams},
+  ptt::{Ptt, Transmitter},
+};
+use std::{
+  io::{Read, Write},
+  thread,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Whether someone else is transmitting.
+pub trait ChannelBusy {
+  fn busy(&mut self) -> Result<bool, String>;
+}
+
+impl<B: ChannelBusy + ?Sized> ChannelBusy for Box<B> {
+  fn busy(&mut self) -> Result<bool, String> {
+    (**self).busy()
+  }
+}
+
+/// Busy while the RSSI is at or above a threshold.
+pub struct RssiBusy<T> {
+  device: Sa818<T>,
+  threshold: u8,
+}
+
+impl<T: Read + Write> RssiBusy<T> {
+  pub fn new(device: Sa818<T>, threshold: u8) -> Self {
+    Self { device, threshold }
+  }
+}
+
+impl<T: Read + Write> ChannelBusy for RssiBusy<T> {
+  fn busy(&mut self) -> Result<bool, String> {
+    Ok(self.device.get_rssi()? >= self.threshold)
+  }
+}
+
+/// p-persistent CSMA as KISS TNCs do it.
+pub struct Csma {
+  state: u32,
+}
+
+impl Default for Csma {
+  fn default() -> Self {
+    let nanos = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.subsec_nanos())
+      .unwrap_or(1);
+    Self { state: nanos | 1 }
+  }
+}
+
+impl Csma {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Fixed random sequence, for tests.
+  pub fn seed(mut self, seed: u32) -> Self {
+    // Spread small seeds over the state, xorshift starts slowly from them.
+    self.state = seed.wrapping_mul(0x9E37_79B9) | 1;
+    self
+  }
+
+  fn random(&mut self) -> u8 {
+    self.state ^= self.state << 13;
+    self.state ^= self.state >> 17;
+    self.state ^= self.state << 5;
+    (self.state >> 24) as u8
+  }
+
+  /// Wait, one slot at a time through `sleep`, until the channel is clear
+  /// and the persistence draw allows sending. Gives up after `max_wait`.
+  pub fn wait<B: ChannelBusy + ?Sized>(
+    &mut self,
+    params: &KissParams,
+    busy: &mut B,
+    max_wait: Duration,
+    mut sleep: impl FnMut(Duration),
+  ) -> Result<(), String> {
+    if params.full_duplex {
+      return Ok(());
+    }
+    let mut waited = Duration::ZERO;
+    loop {
+      if !busy.busy()? && self.random() <= params.persistence {
+        return Ok(());
+      }
+      if waited >= max_wait {
+        return Err(format!(
+          "Channel busy for {:.1} s, frame dropped",
+          waited.as_secs_f32()
+        ));
+      }
+      sleep(params.slot_time);
+      waited += params.slot_time.max(Duration::from_millis(1));
+    }
+  }
+}
+
+/// Sends frames from the host on air.
+pub struct Tnc<P, S, B> {
+  transmitter: Transmitter<P, S>,
+  busy: B,
+  params: KissParams,
+  csma: Csma,
+  max_wait: Duration,
+}
+
+impl<P: Ptt, S: AudioSink, B: ChannelBusy> Tnc<P, S, B> {
+  /// The transmitter's key delay is dropped, TXDELAY is the only wait after
+  /// keying.
+  pub fn new(transmitter: Transmitter<P, S>, busy: B) -> Self {
+    Self {
+      transmitter: transmitter.key_delay(Duration::ZERO),
+      busy,
+      params: KissParams::default(),
+      csma: Csma::new(),
+      max_wait: Duration::from_secs(30),
+    }
+  }
+
+  /// Replace the busy detector.
+  pub fn busy(mut self, busy: B) -> Self {
+    self.busy = busy;
+    self
+  }
+
+  pub fn csma(mut self, csma: Csma) -> Self {
+    self.csma = csma;
+    self
+  }
+
+  /// Longest wait for a clear channel before frames are dropped, 30 s by
+  /// default.
+  pub fn max_wait(mut self, max_wait: Duration) -> Self {
+    self.max_wait = max_wait;
+    self
+  }
+
+  pub fn params(&self) -> &KissParams {
+    &self.params
+  }
+
+  pub fn transmitter(&mut self) -> &mut Transmitter<P, S> {
+    &mut self.transmitter
+  }
+
+  pub fn into_transmitter(self) -> Transmitter<P, S> {
+    self.transmitter
+  }
+
+  /// Handle a command from the host, data for ports other than 0 and
+  /// commands without meaning here are ignored.
+  pub fn command(&mut self, command: &KissCommand) -> Result<(), String> {
+    match command {
+      KissCommand::Data { port: 0, frame } => self.send(&[frame.as_slice()]),
+      command => {
+        self.params.apply(command);
+        Ok(())
+      }
+    }
+  }
+
+  /// Wait for the channel and send `frames` in one transmission, each with
+  /// the TXDELAY preamble and the TXTAIL after it.
+  pub fn send(&mut self, frames: &[&[u8]]) -> Result<(), String> {
+    let modulator = AfskModulator::new(self.transmitter.sample_rate())
+      .preamble(self.params.tx_delay)
+      .tail(((self.params.tx_tail.as_secs_f64() * BAUD / 8.0).ceil() as usize).max(2));
+    let audio: Vec<f32> = frames
+      .iter()
+      .flat_map(|frame| modulator.modulate_frame(frame))
+      .collect();
+    self
+      .csma
+      .wait(&self.params, &mut self.busy, self.max_wait, thread::sleep)?;
+    self.transmitter.transmit(&audio)
+  }
+}
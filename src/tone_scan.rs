@@ -0,0 +1,141 @@
+//! Finding the CTCSS tone of received audio.
+use crate::{
+  audio::AudioSource,
+  goertzel::{hann, Goertzel},
+  group_call::{Ctcss, GroupSel},
+};
+use std::time::Duration;
+
+/// A CTCSS tone found in the audio.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneMatch {
+  pub ctcss: Ctcss,
+  /// Share of the sub-audible tone energy at this tone, 0.0 to 1.0. Across a
+  /// whole scan it is also scaled by the share of windows that agreed.
+  pub confidence: f32,
+  /// Tone amplitude relative to full scale.
+  pub level: f32,
+}
+
+impl ToneMatch {
+  pub fn group_sel(&self) -> GroupSel {
+    GroupSel::Ctcss(self.ctcss)
+  }
+}
+
+/// Goertzel filter bank over the module's CTCSS table.
+pub struct CtcssScanner {
+  sample_rate: u32,
+  window: Vec<f32>,
+  min_level: f32,
+  filters: Vec<(Ctcss, Goertzel)>,
+}
+
+impl CtcssScanner {
+  pub fn new(sample_rate: u32) -> Self {
+    Self {
+      sample_rate,
+      window: hann(sample_rate as usize),
+      min_level: 0.005,
+      filters: Ctcss::all()
+        .map(|ctcss| (ctcss, Goertzel::new(ctcss.hz(), sample_rate)))
+        .collect(),
+    }
+  }
+
+  /// Audio analysed at once, longer windows tell close tones apart better.
+  /// Defaults to one second, the closest tones are 2.5 Hz apart. It must
+  /// hold a period of the lowest tone.
+  pub fn window(mut self, window: Duration) -> Result<Self, String> {
+    let len = (self.sample_rate as f64 * window.as_secs_f64()) as usize;
+    let lowest = Ctcss::all().next().unwrap().hz();
+    if (len as f32) < self.sample_rate as f32 / lowest {
+      return Err(format!(
+        "A {} ms window is too short for a {} Hz tone",
+        window.as_millis(),
+        lowest
+      ));
+    }
+    self.window = hann(len);
+    Ok(self)
+  }
+
+  /// Tone amplitude, relative to full scale, below which nothing is found.
+  pub fn min_level(mut self, min_level: f32) -> Self {
+    self.min_level = min_level;
+    self
+  }
+
+  /// Samples needed by [`CtcssScanner::detect`].
+  pub fn window_len(&self) -> usize {
+    self.window.len()
+  }
+
+  /// Strongest tone in one window of audio, shorter input is zero padded.
+  pub fn detect(&self, samples: &[f32]) -> Option<ToneMatch> {
+    let samples = &samples[..samples.len().min(self.window.len())];
+    let powers: Vec<(Ctcss, f32)> = self
+      .filters
+      .iter()
+      .map(|(ctcss, filter)| (*ctcss, filter.windowed_power(samples, &self.window)))
+      .collect();
+    let total: f32 = powers.iter().map(|(_, power)| power).sum();
+    let (ctcss, power) = powers.into_iter().max_by(|a, b| a.1.total_cmp(&b.1))?;
+    let level = power.sqrt();
+    if level < self.min_level || total <= 0.0 {
+      return None;
+    }
+    Some(ToneMatch {
+      ctcss,
+      confidence: power / total,
+      level,
+    })
+  }
+
+  /// Detect window by window over a whole stream and report the tone most
+  /// windows agree on. Needs at least half a window of audio.
+  pub fn scan<S: AudioSource>(&self, source: &mut S) -> Result<Option<ToneMatch>, String> {
+    if source.sample_rate() != self.sample_rate {
+      return Err(format!(
+        "Audio is {} Hz, the scanner expects {} Hz",
+        source.sample_rate(),
+        self.sample_rate
+      ));
+    }
+    let mut buffer = vec![0.0; self.window.len()];
+    let mut windows = 0;
+    let mut matches: Vec<ToneMatch> = Vec::new();
+    loop {
+      let read = source.read_exact(&mut buffer)?;
+      // Skip a tail too short to resolve the tones.
+      if read < buffer.len() / 2 {
+        break;
+      }
+      windows += 1;
+      if let Some(tone) = self.detect(&buffer[..read]) {
+        matches.push(tone);
+      }
+      if read < buffer.len() {
+        break;
+      }
+    }
+
+    let mut best: Option<(Ctcss, usize)> = None;
+    for tone in &matches {
+      let votes = matches.iter().filter(|m| m.ctcss == tone.ctcss).count();
+      if best.is_none_or(|(_, most)| votes > most) {
+        best = Some((tone.ctcss, votes));
+      }
+    }
+    Ok(best.map(|(ctcss, votes)| {
+      let agreeing = matches.iter().filter(|m| m.ctcss == ctcss);
+      let (confidence, level) =
+        agreeing.fold((0.0, 0.0), |(c, l), m| (c + m.confidence, l + m.level));
+      ToneMatch {
+        ctcss,
+        confidence: confidence / windows as f32,
+        level: level / votes as f32,
+      }
+    }))
+  }
+}
@@ -0,0 +1,271 @@
+//! APRS tracker: position beacons and telemetry sent through the module.
+use crate::{
+  aprs::{self, Position, Symbol},
+  audio::AudioSink,
+  ax25::{Address, UiFrame},
+  device::Sa818,
+  kiss::KissCommand,
+  ptt::{Ptt, Transmitter},
+  tnc::{RssiBusy, Tnc},
+};
+use std::{
+  io::{Read, Write},
+  time::{Duration, Instant},
+};
+
+/// Beacon rate following speed and turns, as HamHUD and most trackers do
+/// it. Speeds are in knots.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmartBeacon {
+  /// Below this speed beacons go out every `slow_rate`.
+  pub slow_speed: f64,
+  pub slow_rate: Duration,
+  /// Above this speed beacons go out every `fast_rate`, in between the
+  /// rate scales with speed.
+  pub fast_speed: f64,
+  pub fast_rate: Duration,
+  /// Turn in degrees that triggers a beacon at high speed.
+  pub min_turn_angle: f64,
+  /// Degrees times knots added to the turn angle as speed drops.
+  pub turn_slope: f64,
+  /// Shortest time between beacons on turns.
+  pub min_turn_time: Duration,
+}
+
+impl Default for SmartBeacon {
+  fn default() -> Self {
+    Self {
+      slow_speed: 3.0,
+      slow_rate: Duration::from_secs(1800),
+      fast_speed: 50.0,
+      fast_rate: Duration::from_secs(120),
+      min_turn_angle: 28.0,
+      turn_slope: 255.0,
+      min_turn_time: Duration::from_secs(30),
+    }
+  }
+}
+
+impl SmartBeacon {
+  /// Time between beacons at `speed`.
+  pub fn rate(&self, speed: f64) -> Duration {
+    if speed <= self.slow_speed {
+      self.slow_rate
+    } else if speed >= self.fast_speed {
+      self.fast_rate
+    } else {
+      self.fast_rate.mul_f64(self.fast_speed / speed)
+    }
+  }
+
+  /// Whether to beacon at `current`, `elapsed` after beaconing at `last`.
+  pub fn due(&self, last: &Position, elapsed: Duration, current: &Position) -> bool {
+    let speed = current.speed.unwrap_or(0.0);
+    if elapsed >= self.rate(speed) {
+      return true;
+    }
+    let (Some(from), Some(to)) = (last.course, current.course) else {
+      return false;
+    };
+    let turn = (to - from).rem_euclid(360.0);
+    let turn = turn.min(360.0 - turn);
+    speed > self.slow_speed
+      && elapsed >= self.min_turn_time
+      && turn >= self.min_turn_angle + self.turn_slope / speed
+  }
+}
+
+/// When position beacons go out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Beaconing {
+  Fixed(Duration),
+  Smart(SmartBeacon),
+}
+
+/// Sends position reports, and telemetry of the RSSI and supply voltage,
+/// when they are due.
+pub struct Tracker<T, P, S> {
+  device: Sa818<T>,
+  tnc: Tnc<P, S, RssiBusy<T>>,
+  source: Address,
+  destination: Address,
+  path: Vec<Address>,
+  symbol: Symbol,
+  comment: String,
+  beaconing: Beaconing,
+  last_beacon: Option<(Instant, Position)>,
+  telemetry: Option<Duration>,
+  last_telemetry: Option<Instant>,
+  sequence: u16,
+  /// Telemetry was built at `sequence`, which moves on once it is sent.
+  telemetry_pending: bool,
+  voltage: Option<Box<dyn FnMut() -> Result<f32, String>>>,
+}
+
+impl<T: Read + Write, P: Ptt, S: AudioSink> Tracker<T, P, S> {
+  /// Tracker sending as `source`, beaconing every 10 minutes by default.
+  pub fn new(device: Sa818<T>, transmitter: Transmitter<P, S>, source: Address) -> Self {
+    let mut tnc = Tnc::new(transmitter, RssiBusy::new(device.clone(), 60));
+    // Send as soon as the channel is clear, with the modulator's preamble.
+    for command in [KissCommand::TxDelay(30), KissCommand::Persistence(255)] {
+      tnc.command(&command).unwrap();
+    }
+    Self {
+      device,
+      tnc,
+      source,
+      destination: Address::new("APZ818", 0).unwrap(),
+      path: vec![
+        Address::new("WIDE1", 1).unwrap(),
+        Address::new("WIDE2", 1).unwrap(),
+      ],
+      symbol: Symbol::default(),
+      comment: String::new(),
+      beaconing: Beaconing::Fixed(Duration::from_secs(600)),
+      last_beacon: None,
+      telemetry: None,
+      last_telemetry: None,
+      sequence: 0,
+      telemetry_pending: false,
+      voltage: None,
+    }
+  }
+
+  /// Digipeater path, WIDE1-1,WIDE2-1 by default.
+  pub fn path(mut self, path: Vec<Address>) -> Result<Self, String> {
+    if path.len() > 8 {
+      return Err(String::from("At most 8 digipeaters"));
+    }
+    self.path = path;
+    Ok(self)
+  }
+
+  pub fn symbol(mut self, symbol: Symbol) -> Self {
+    self.symbol = symbol;
+    self
+  }
+
+  pub fn comment(mut self, comment: &str) -> Self {
+    self.comment = comment.to_string();
+    self
+  }
+
+  pub fn beaconing(mut self, beaconing: Beaconing) -> Self {
+    self.beaconing = beaconing;
+    self
+  }
+
+  /// Raw RSSI at or above which the channel is busy and sending waits, 60 by
+  /// default.
+  pub fn busy_threshold(mut self, threshold: u8) -> Self {
+    self.tnc = self.tnc.busy(RssiBusy::new(self.device.clone(), threshold));
+    self
+  }
+
+  /// Send telemetry every `interval`.
+  pub fn telemetry(mut self, interval: Duration) -> Self {
+    self.telemetry = Some(interval);
+    self
+  }
+
+  /// Supply voltage in volts for the telemetry, 0 when not set.
+  pub fn voltage(mut self, voltage: impl FnMut() -> Result<f32, String> + 'static) -> Self {
+    self.voltage = Some(Box::new(voltage));
+    self
+  }
+
+  pub fn transmitter(&mut self) -> &mut Transmitter<P, S> {
+    self.tnc.transmitter()
+  }
+
+  pub fn into_transmitter(self) -> Transmitter<P, S> {
+    self.tnc.into_transmitter()
+  }
+
+  fn frame(&self, info: &str) -> UiFrame {
+    UiFrame::new(
+      self.destination.clone(),
+      self.source.clone(),
+      info.as_bytes(),
+    )
+    .path(self.path.clone())
+    .unwrap()
+  }
+
+  pub fn position_frame(&self, position: &Position) -> UiFrame {
+    self.frame(&position.report(self.symbol, &self.comment))
+  }
+
+  /// Telemetry report of the RSSI now, in A1, and the supply voltage in
+  /// 0.1 V steps, in A2. Every 10th report is preceded by the channel
+  /// names, units and scaling. The sequence only moves on once the frames
+  /// are sent.
+  pub fn telemetry_frames(&mut self) -> Result<Vec<UiFrame>, String> {
+    let rssi = self.device.get_rssi()?;
+    let volts = match &mut self.voltage {
+      Some(voltage) => voltage()?,
+      None => 0.0,
+    };
+    let supply = (volts * 10.0).round().clamp(0.0, 255.0) as u8;
+    let mut frames = Vec::new();
+    if self.sequence.is_multiple_of(10) {
+      for text in ["PARM.RSSI,Supply", "UNIT.raw,V", "EQNS.0,1,0,0,0.1,0"] {
+        frames.push(self.frame(&aprs::message(&self.source, text)));
+      }
+    }
+    frames.push(self.frame(&aprs::telemetry(self.sequence, &[rssi, supply], 0)?));
+    self.telemetry_pending = true;
+    Ok(frames)
+  }
+
+  /// Wait for the channel and send `frames` in one transmission.
+  pub fn send(&mut self, frames: &[UiFrame]) -> Result<(), String> {
+    let encoded: Vec<Vec<u8>> = frames.iter().map(|frame| frame.encode()).collect();
+    let encoded: Vec<&[u8]> = encoded.iter().map(|frame| frame.as_slice()).collect();
+    let pending = std::mem::take(&mut self.telemetry_pending);
+    self.tnc.send(&encoded)?;
+    if pending {
+      self.sequence = (self.sequence + 1) % 1000;
+    }
+    Ok(())
+  }
+
+  fn beacon_due(&self, at: Instant, position: &Position) -> bool {
+    let Some((time, last)) = &self.last_beacon else {
+      return true;
+    };
+    let elapsed = at.saturating_duration_since(*time);
+    match &self.beaconing {
+      Beaconing::Fixed(interval) => elapsed >= *interval,
+      Beaconing::Smart(smart) => smart.due(last, elapsed, position),
+    }
+  }
+
+  /// Call periodically with the latest position, if there is a fix, sends
+  /// what is due. Returns the frames sent.
+  pub fn poll(&mut self, at: Instant, position: Option<&Position>) -> Result<Vec<UiFrame>, String> {
+    let beacon = position
+      .filter(|position| self.beacon_due(at, position))
+      .copied();
+    let telemetry_due = self.telemetry.is_some_and(|interval| {
+      self
+        .last_telemetry
+        .is_none_or(|last| at.saturating_duration_since(last) >= interval)
+    });
+    let mut frames: Vec<UiFrame> = beacon.iter().map(|p| self.position_frame(p)).collect();
+    if telemetry_due {
+      frames.extend(self.telemetry_frames()?);
+    }
+    if frames.is_empty() {
+      return Ok(frames);
+    }
+    self.send(&frames)?;
+    if let Some(position) = beacon {
+      self.last_beacon = Some((at, position));
+    }
+    if telemetry_due {
+      self.last_telemetry = Some(at);
+    }
+    Ok(frames)
+  }
+}
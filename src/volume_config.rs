@@ -1,17 +1,27 @@
-use std::{fmt::Write, io::Read};
+use crate::read_string;
+use std::io::{Read, Write};
 
 pub struct VolumeConfig {
   value: u8,
 }
 
 impl VolumeConfig {
-  fn new(value: u8) -> Self {
-    return VolumeConfig { value };
+  pub fn new(value: u8) -> Result<Self, String> {
+    if !(1..=8).contains(&value) {
+      return Err(format!("Invalid volume {}", value));
+    }
+    Ok(VolumeConfig { value })
   }
 }
 
 impl VolumeConfig {
   pub fn write_config<T: Read + Write>(&self, io: &mut T) -> Result<String, String> {
-    return Err("todo".to_string());
+    io.write_all(format!("AT+DMOSETVOLUME={}\r\n", self.value).as_bytes())
+      .map_err(|e| e.to_string())?;
+    let response = read_string(io)?;
+    if response.trim() != "+DMOSETVOLUME:0" {
+      return Err(format!("Invalid Response: {}", response));
+    }
+    Ok(response)
   }
 }
@@ -0,0 +1,218 @@
+//! Minimal RIFF WAVE reading and writing.
+use crate::audio::{AudioSink, AudioSource};
+use std::{
+  fs::File,
+  io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+  path::Path,
+};
+
+const PCM: u16 = 1;
+const IEEE_FLOAT: u16 = 3;
+const EXTENSIBLE: u16 = 0xFFFE;
+
+/// Reads PCM (8 to 32 bit) or float WAV files, mixing channels down to mono.
+pub struct WavReader<R> {
+  reader: R,
+  sample_rate: u32,
+  channels: u16,
+  bits: u16,
+  float: bool,
+  /// Bytes of sample data left.
+  remaining: u64,
+  frame: Vec<u8>,
+}
+
+impl WavReader<BufReader<File>> {
+  pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    WavReader::new(BufReader::new(file))
+  }
+}
+
+impl<R: Read> WavReader<R> {
+  pub fn new(mut reader: R) -> Result<Self, String> {
+    let mut header = [0u8; 12];
+    reader
+      .read_exact(&mut header)
+      .map_err(|e| format!("Invalid WAV header: {}", e))?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+      return Err(String::from("Not a WAV file"));
+    }
+
+    let mut format = None;
+    loop {
+      let mut chunk = [0u8; 8];
+      reader
+        .read_exact(&mut chunk)
+        .map_err(|_| String::from("WAV file has no data chunk"))?;
+      let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+      match &chunk[0..4] {
+        b"fmt " => {
+          let mut fmt = vec![0u8; size as usize + (size as usize & 1)];
+          reader.read_exact(&mut fmt).map_err(|e| e.to_string())?;
+          if fmt.len() < 16 {
+            return Err(String::from("Invalid WAV format chunk"));
+          }
+          let word = |at: usize| u16::from_le_bytes([fmt[at], fmt[at + 1]]);
+          let mut tag = word(0);
+          if tag == EXTENSIBLE && fmt.len() >= 26 {
+            tag = word(24);
+          }
+          let sample_rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+          format = Some((tag, word(2), sample_rate, word(14)));
+        }
+        b"data" => {
+          let (tag, channels, sample_rate, bits) =
+            format.ok_or("WAV data chunk before format chunk")?;
+          let float = match (tag, bits) {
+            (PCM, 8 | 16 | 24 | 32) => false,
+            (IEEE_FLOAT, 32) => true,
+            _ => return Err(format!("Unsupported WAV format {} with {} bits", tag, bits)),
+          };
+          if channels == 0 || sample_rate == 0 {
+            return Err(String::from("Invalid WAV format chunk"));
+          }
+          return Ok(Self {
+            reader,
+            sample_rate,
+            channels,
+            bits,
+            float,
+            remaining: size,
+            frame: vec![0; channels as usize * bits as usize / 8],
+          });
+        }
+        _ => {
+          let skip = size + (size & 1);
+          std::io::copy(&mut (&mut reader).take(skip), &mut std::io::sink())
+            .map_err(|e| e.to_string())?;
+        }
+      }
+    }
+  }
+
+  pub fn channels(&self) -> u16 {
+    self.channels
+  }
+
+  fn sample(&self, bytes: &[u8]) -> f32 {
+    match (self.bits, self.float) {
+      (8, _) => (bytes[0] as f32 - 128.0) / 128.0,
+      (16, _) => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32768.0,
+      (24, _) => (i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8) as f32 / 8388608.0,
+      (_, true) => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+      _ => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32 / 2147483648.0,
+    }
+  }
+}
+
+impl<R: Read> AudioSource for WavReader<R> {
+  fn sample_rate(&self) -> u32 {
+    self.sample_rate
+  }
+
+  fn read(&mut self, buffer: &mut [f32]) -> Result<usize, String> {
+    let width = self.bits as usize / 8;
+    let mut frame = std::mem::take(&mut self.frame);
+    let mut read = 0;
+    for sample in buffer.iter_mut() {
+      if self.remaining < frame.len() as u64 {
+        break;
+      }
+      if let Err(e) = self.reader.read_exact(&mut frame) {
+        // A truncated data chunk ends the stream.
+        if e.kind() == std::io::ErrorKind::UnexpectedEof {
+          self.remaining = 0;
+          break;
+        }
+        self.frame = frame;
+        return Err(e.to_string());
+      }
+      self.remaining -= frame.len() as u64;
+      let sum: f32 = frame.chunks(width).map(|bytes| self.sample(bytes)).sum();
+      *sample = sum / self.channels as f32;
+      read += 1;
+    }
+    self.frame = frame;
+    Ok(read)
+  }
+}
+
+/// Writes 16 bit mono PCM WAV files.
+pub struct WavWriter<W: Write + Seek> {
+  writer: W,
+  sample_rate: u32,
+  samples: u32,
+}
+
+impl WavWriter<BufWriter<File>> {
+  pub fn create<P: AsRef<Path>>(path: P, sample_rate: u32) -> Result<Self, String> {
+    let path = path.as_ref();
+    let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    WavWriter::new(BufWriter::new(file), sample_rate)
+  }
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+  pub fn new(mut writer: W, sample_rate: u32) -> Result<Self, String> {
+    let mut header = Vec::with_capacity(44);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&36u32.to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&PCM.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&sample_rate.to_le_bytes());
+    header.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    header.extend_from_slice(&2u16.to_le_bytes());
+    header.extend_from_slice(&16u16.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&0u32.to_le_bytes());
+    writer.write_all(&header).map_err(|e| e.to_string())?;
+    Ok(Self {
+      writer,
+      sample_rate,
+      samples: 0,
+    })
+  }
+
+  /// Append samples, clipping them to -1.0..=1.0.
+  pub fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+    let bytes: Vec<u8> = samples
+      .iter()
+      .flat_map(|sample| ((sample.clamp(-1.0, 1.0) * 32767.0).round() as i16).to_le_bytes())
+      .collect();
+    self.writer.write_all(&bytes).map_err(|e| e.to_string())?;
+    self.samples += samples.len() as u32;
+    Ok(())
+  }
+
+  /// Fill in the header sizes and hand back the writer.
+  pub fn finish(mut self) -> Result<W, String> {
+    let data = self.samples * 2;
+    for (at, value) in [(4, 36 + data), (40, data)] {
+      self
+        .writer
+        .seek(SeekFrom::Start(at))
+        .and_then(|_| self.writer.write_all(&u32::to_le_bytes(value)))
+        .map_err(|e| e.to_string())?;
+    }
+    self
+      .writer
+      .seek(SeekFrom::End(0))
+      .and_then(|_| self.writer.flush())
+      .map_err(|e| e.to_string())?;
+    Ok(self.writer)
+  }
+}
+
+impl<W: Write + Seek> AudioSink for WavWriter<W> {
+  fn sample_rate(&self) -> u32 {
+    self.sample_rate
+  }
+
+  fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+    WavWriter::write(self, samples)
+  }
+}
@@ -0,0 +1,142 @@
+mod mocked_io;
+use sa818::{
+  afsk::{AfskDemodulator, AfskModulator},
+  aprs,
+  audio::AudioSource,
+  ax25::{Address, UiFrame},
+  channel::{FmBandwidth, Region},
+  device::Sa818,
+  hdlc::{self, HdlcDecoder},
+  wav::{WavReader, WavWriter},
+};
+use std::{io::Cursor, time::Duration};
+
+fn frame() -> UiFrame {
+  "N0CALL-9>APRS,WIDE1-1,WIDE2-1:!4903.50N/07201.75W-Test 001234"
+    .parse()
+    .unwrap()
+}
+
+#[test]
+fn hdlc_framing() {
+  assert_eq!(hdlc::fcs(b"123456789"), 0x906E);
+
+  // Six ones in a row get a zero stuffed after the fifth.
+  let bits = hdlc::encode(&[0xFF; 17], 1, 1);
+  assert!(!bits[8..bits.len() - 8]
+    .windows(6)
+    .any(|w| w.iter().all(|&b| b)));
+
+  let mut decoder = HdlcDecoder::new();
+  let frames: Vec<Vec<u8>> = bits.iter().filter_map(|&bit| decoder.push(bit)).collect();
+  assert_eq!(frames, vec![vec![0xFF; 17]]);
+
+  // A flipped bit fails the FCS.
+  let mut bits = hdlc::encode(&frame().encode(), 4, 1);
+  bits[100] = !bits[100];
+  let mut decoder = HdlcDecoder::new();
+  assert!(bits.iter().all(|&bit| decoder.push(bit).is_none()));
+}
+
+#[test]
+fn ax25_frames() {
+  let frame = frame();
+  assert_eq!(frame.source, "N0CALL-9".parse::<Address>().unwrap());
+  assert_eq!(frame.path.len(), 2);
+
+  let bytes = frame.encode();
+  assert_eq!(&bytes[..7], &[0x82, 0xA0, 0xA4, 0xA6, 0x40, 0x40, 0xE0]);
+  assert_eq!(&bytes[7..14], &[0x9C, 0x60, 0x86, 0x82, 0x98, 0x98, 0x72]);
+  assert_eq!((bytes[20], bytes[27]), (0x62, 0x63));
+  assert_eq!(&bytes[28..30], &[0x03, 0xF0]);
+  assert_eq!(UiFrame::decode(&bytes).unwrap(), frame);
+
+  let repeated = "N0CALL>APRS,DIGI*,WIDE2-1:hi".parse::<UiFrame>().unwrap();
+  assert!(repeated.path[0].repeated());
+  let decoded = UiFrame::decode(&repeated.encode()).unwrap();
+  assert_eq!(decoded.to_string(), "N0CALL>APRS,DIGI*,WIDE2-1:hi");
+
+  assert!("N0CALL>APRS".parse::<UiFrame>().is_err());
+  assert!("TOOLONGCALL>APRS:x".parse::<UiFrame>().is_err());
+  assert!("N0CALL-16>APRS:x".parse::<UiFrame>().is_err());
+  assert!(UiFrame::decode(&bytes[..10]).is_err());
+}
+
+#[test]
+fn afsk_round_trip() {
+  let frame = frame();
+  for rate in [8000, 11025, 22050, 44100, 48000] {
+    let audio = AfskModulator::new(rate).modulate_frame(&frame.encode());
+    let frames = AfskDemodulator::new(rate).push(&audio);
+    assert_eq!(frames.len(), 1, "{rate}");
+    assert_eq!(UiFrame::decode(&frames[0]).unwrap(), frame);
+  }
+}
+
+#[test]
+fn afsk_noisy_stream() {
+  let rate = 22050;
+  let modulator = AfskModulator::new(rate)
+    .level(0.3)
+    .preamble(Duration::from_millis(100));
+  let first = frame();
+  let second: UiFrame = "N0CALL>APRS:>status".parse().unwrap();
+  let mut audio = vec![0.0; 1000];
+  audio.extend(modulator.modulate_frame(&first.encode()));
+  audio.extend(vec![0.0; 3000]);
+  audio.extend(modulator.modulate_frame(&second.encode()));
+  audio.extend(vec![0.0; 1000]);
+
+  let mut seed = 7u32;
+  for sample in audio.iter_mut() {
+    seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+    *sample += 0.1 * ((seed >> 8) as f32 / (1 << 24) as f32 - 0.5);
+  }
+
+  let mut demodulator = AfskDemodulator::new(rate);
+  let frames: Vec<UiFrame> = audio
+    .chunks(500)
+    .flat_map(|chunk| demodulator.push(chunk))
+    .map(|bytes| UiFrame::decode(&bytes).unwrap())
+    .collect();
+  assert_eq!(frames, vec![first, second]);
+}
+
+#[test]
+fn afsk_wav_recording() {
+  let rate = 44100;
+  let audio = AfskModulator::new(rate).modulate_frame(&frame().encode());
+  let mut writer = WavWriter::new(Cursor::new(Vec::new()), rate).unwrap();
+  writer.write(&audio).unwrap();
+  let mut wav = writer.finish().unwrap();
+  wav.set_position(0);
+  let samples = WavReader::new(wav).unwrap().read_to_end().unwrap();
+  let frames = AfskDemodulator::new(rate).push(&samples);
+  assert_eq!(frames.len(), 1);
+  assert_eq!(
+    UiFrame::decode(&frames[0]).unwrap().to_string(),
+    "N0CALL-9>APRS,WIDE1-1,WIDE2-1:!4903.50N/07201.75W-Test 001234"
+  );
+}
+
+#[test]
+fn aprs_setup() {
+  let channel = aprs::region_channel(Region::One).unwrap();
+  assert_eq!(channel.rx_frequency(), Some(144.8));
+  assert_eq!(channel.tx_frequency(), Some(144.8));
+  assert_eq!(channel.fm_bandwidth(), FmBandwidth::Wide);
+  assert_eq!(
+    aprs::region_channel(Region::Two).unwrap().rx_frequency(),
+    Some(144.39)
+  );
+  assert!(aprs::region_channel(Region::Three).is_err());
+
+  let mock = mocked_io::Mock::new().responses(&["+DMOSETGROUP=0\r\n", "+DMOSETFILTER:0\r\n"]);
+  let device = Sa818::new(mock);
+  device.write_config(&channel).unwrap();
+  device.write_filter(&aprs::filter_config()).unwrap();
+  assert_eq!(
+    device.lock().unwrap().input,
+    "AT+DMOSETGROUP=0,144.8000,144.8000,0000,4,0000\r\nAT+SETFILTER=1,1,1\r\n"
+  );
+}
@@ -0,0 +1,174 @@
+mod mocked_io;
+use sa818::{
+  afsk::{AfskDemodulator, AfskModulator},
+  audio::{self, AudioSink, AudioSource, LevelMeter, MemorySink, MemorySource, Metered, RawSource},
+  ax25::UiFrame,
+  goertzel::Goertzel,
+  ptt::Transmitter,
+  resample::{ResampledSink, ResampledSource, Resampler},
+  wav::{WavReader, WavWriter},
+};
+use std::{
+  f32::consts::PI,
+  io::{self, Cursor, Read},
+  time::Duration,
+};
+
+fn sine(frequency: f32, rate: u32, level: f32, len: usize) -> Vec<f32> {
+  (0..len)
+    .map(|i| level * (2.0 * PI * frequency * i as f32 / rate as f32).sin())
+    .collect()
+}
+
+#[test]
+fn memory_backends() {
+  let mut source = MemorySource::new(8000, (0..10).map(|i| i as f32 / 10.0).collect());
+  let mut buffer = [0.0; 4];
+  assert_eq!(source.read(&mut buffer).unwrap(), 4);
+  assert_eq!(buffer[3], 0.3);
+  assert_eq!(source.read_to_end().unwrap().len(), 6);
+  assert_eq!(source.read(&mut buffer).unwrap(), 0);
+
+  let mut sink = MemorySink::new(8000);
+  sink.write(&[0.5, 1.5, -2.0]).unwrap();
+  sink.flush().unwrap();
+  assert_eq!(sink.samples(), &[0.5, 1.0, -1.0]);
+  assert_eq!(sink.flushes(), 1);
+
+  let mut boxed: Box<dyn AudioSink> = Box::new(sink);
+  boxed.write(&[0.0]).unwrap();
+  assert_eq!(boxed.sample_rate(), 8000);
+}
+
+/// Hands out its bytes a few at a time, like a pipe.
+struct Chunked {
+  bytes: Vec<u8>,
+  sizes: Vec<usize>,
+}
+
+impl Read for Chunked {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let n = self
+      .sizes
+      .pop()
+      .unwrap_or(usize::MAX)
+      .min(buf.len())
+      .min(self.bytes.len());
+    buf[..n].copy_from_slice(&self.bytes[..n]);
+    self.bytes.drain(..n);
+    Ok(n)
+  }
+}
+
+#[test]
+fn raw_source_odd_reads() {
+  let samples: Vec<i16> = vec![1000, -2000, 3000, -4000, 32767, -32768];
+  let reader = Chunked {
+    bytes: samples.iter().flat_map(|s| s.to_le_bytes()).collect(),
+    // Popped from the end, a lone byte first and then odd sized chunks.
+    sizes: vec![1, 2, 3, 5, 1],
+  };
+  let mut source = RawSource::new(reader, 8000);
+  let mut buffer = [0.0; 8];
+  let mut read = Vec::new();
+  loop {
+    match source.read(&mut buffer).unwrap() {
+      0 => break,
+      n => read.extend_from_slice(&buffer[..n]),
+    }
+  }
+  let expected: Vec<f32> = samples.iter().map(|&s| s as f32 / 32768.0).collect();
+  assert_eq!(read, expected);
+}
+
+#[test]
+fn level_metering() {
+  let mut meter = LevelMeter::new();
+  assert_eq!(meter.rms_dbfs(), f32::NEG_INFINITY);
+  meter.push(&sine(1000.0, 48000, 0.5, 4800));
+  assert!((meter.rms() - 0.5 / 2f32.sqrt()).abs() < 1e-3);
+  assert!((meter.peak_dbfs() + 6.02).abs() < 0.01);
+  assert!((meter.rms_dbfs() + 9.03).abs() < 0.01);
+  assert_eq!(meter.clipped(), 0);
+  meter.push(&[1.0, -1.2]);
+  assert_eq!((meter.clipped(), meter.count()), (2, 4802));
+  meter.reset();
+  assert_eq!(meter.count(), 0);
+  assert_eq!(audio::dbfs(0.1), -20.0);
+
+  let mut source = Metered::new(MemorySource::new(8000, vec![0.25; 100]));
+  source.read_to_end().unwrap();
+  assert_eq!(source.meter().count(), 100);
+  assert_eq!(source.meter().rms(), 0.25);
+}
+
+#[test]
+fn resampling_keeps_tones() {
+  let input = sine(1000.0, 48000, 0.5, 48000);
+  let mut resampler = Resampler::new(48000, 8000).unwrap();
+  let mut output: Vec<f32> = input
+    .chunks(777)
+    .flat_map(|chunk| resampler.process(chunk))
+    .collect();
+  output.extend(resampler.flush());
+  assert_eq!(output.len(), 8000);
+  // Same tone, same level, away from the edges.
+  let expected = sine(1000.0, 8000, 0.5, 8000);
+  assert!(output[100..7900]
+    .iter()
+    .zip(&expected[100..7900])
+    .all(|(a, b)| (a - b).abs() < 0.01));
+
+  // 6 kHz would alias to 2 kHz at 8 kHz, the filter takes it out.
+  let mut resampler = Resampler::new(48000, 8000).unwrap();
+  let aliased = resampler.process(&sine(6000.0, 48000, 0.5, 48000));
+  let power = Goertzel::new(2000.0, 8000).power(&aliased[1000..7000]);
+  assert!(power < 1e-5, "{power}");
+
+  // Up in rate, by an odd ratio.
+  let mut resampler = Resampler::new(8000, 44100).unwrap();
+  let up = resampler.process(&sine(1000.0, 8000, 0.5, 8000));
+  let power = Goertzel::new(1000.0, 44100).power(&up[1000..40100]);
+  assert!((power - 0.25).abs() < 0.01, "{power}");
+
+  assert!(Resampler::new(0, 8000).is_err());
+  let mut same = Resampler::new(8000, 8000).unwrap();
+  assert_eq!(same.process(&[0.1, 0.2]), vec![0.1, 0.2]);
+  assert!(same.flush().is_empty());
+}
+
+#[test]
+fn file_pipelines_across_rates() {
+  let frame: UiFrame = "N0CALL>APRS:>resampled".parse().unwrap();
+
+  // Received audio recorded at 44.1 kHz, decoded at 22.05 kHz.
+  let mut writer = WavWriter::new(Cursor::new(Vec::new()), 44100).unwrap();
+  writer
+    .write(&AfskModulator::new(44100).modulate_frame(&frame.encode()))
+    .unwrap();
+  let mut wav = writer.finish().unwrap();
+  wav.set_position(0);
+  let mut source = ResampledSource::new(WavReader::new(wav).unwrap(), 22050).unwrap();
+  assert_eq!(source.sample_rate(), 22050);
+  let frames = AfskDemodulator::new(22050).push(&source.read_to_end().unwrap());
+  assert_eq!(frames.len(), 1);
+  assert_eq!(UiFrame::decode(&frames[0]).unwrap(), frame);
+
+  // Transmit audio made at 48 kHz for a 16 kHz sound card.
+  let sink = ResampledSink::new(Metered::new(MemorySink::new(16000)), 48000).unwrap();
+  let mut transmitter =
+    Transmitter::new(mocked_io::RecordedPtt::default(), sink).key_delay(Duration::from_millis(20));
+  assert_eq!(transmitter.sample_rate(), 48000);
+  let audio = AfskModulator::new(48000).modulate_frame(&frame.encode());
+  transmitter.transmit(&audio).unwrap();
+  transmitter.transmit(&audio).unwrap();
+  let (ptt, sink) = transmitter.into_parts();
+  assert_eq!(ptt.0, vec![true, false, true, false]);
+  let metered = sink.into_inner();
+  assert!((metered.meter().peak() - 0.5).abs() < 0.02);
+  let sink = metered.into_inner();
+  assert_eq!(sink.flushes(), 2);
+  let frames = AfskDemodulator::new(16000).push(sink.samples());
+  assert_eq!(frames.len(), 2);
+  assert_eq!(UiFrame::decode(&frames[1]).unwrap(), frame);
+}
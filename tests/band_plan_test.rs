@@ -0,0 +1,138 @@
+mod mocked_io;
+use sa818::{
+  band_plan::{Access, Band, BandPlan},
+  channel::{Channel, FmBandwidth, FreqConf, PowerLevel, Region},
+  group_call::GroupSel,
+};
+
+fn simplex(frequency: f32) -> Channel {
+  Channel::default()
+    .rx(FreqConf::new(frequency).unwrap())
+    .tx(FreqConf::new(frequency).unwrap())
+}
+
+#[test]
+fn iaru_access() {
+  let region1 = BandPlan::iaru(Region::One);
+  assert_eq!(region1.access(&simplex(145.5)), Access::Transmit);
+  assert_eq!(region1.access(&simplex(146.0)), Access::Transmit);
+  assert!(matches!(
+    region1.access(&simplex(147.0)),
+    Access::ReceiveOnly { .. }
+  ));
+  let region2 = BandPlan::iaru(Region::Two);
+  assert_eq!(region2.access(&simplex(147.0)), Access::Transmit);
+  assert_eq!(region2.lookup(446.0).unwrap().name, "70cm");
+  assert!(region2.lookup(162.55).is_none());
+}
+
+#[test]
+fn narrow_only_bands() {
+  let pmr446 = BandPlan::pmr446();
+  let channel = simplex(446.00625).power(PowerLevel::Low);
+  assert_eq!(pmr446.access(&channel), Access::Transmit);
+  assert_eq!(
+    pmr446.access(&channel.bandwidth(FmBandwidth::Wide)),
+    Access::ReceiveOnly {
+      reason: String::from("PMR446 only allows narrow FM at 446.0063 MHz")
+    }
+  );
+  assert!(BandPlan::builtin("FRS").is_some());
+  assert!(BandPlan::builtin("cb").is_none());
+}
+
+#[test]
+fn band_plan_file() {
+  let plan = BandPlan::parse(
+    "club",
+    "# club plan\n144.0,146.0,tx,2m\n\n145.8,146.0,rx,satellite # no uplink\n430,440,tx-narrow\n",
+  )
+  .unwrap();
+  assert_eq!(plan.bands().len(), 3);
+  assert_eq!(
+    plan.bands()[1],
+    Band::new("satellite", 145.8, 146.0).receive_only()
+  );
+  assert!(plan.bands()[2].narrow_only);
+  // the receive only satellite segment restricts the 2m band around it
+  assert_eq!(
+    plan.access(&simplex(145.9)),
+    Access::ReceiveOnly {
+      reason: String::from("145.9000 MHz is receive only in the club band plan")
+    }
+  );
+  assert_eq!(plan.lookup(145.9).unwrap().name, "satellite");
+  assert_eq!(plan.access(&simplex(145.5)), Access::Transmit);
+  assert_eq!(BandPlan::parse("club", &plan.to_string()).unwrap(), plan);
+
+  assert!(BandPlan::parse("bad", "144,146\n").is_err());
+  assert!(BandPlan::parse("bad", "146,144,tx\n").is_err());
+  assert!(BandPlan::parse("bad", "144,146,maybe\n").is_err());
+}
+
+#[test]
+fn write_config_enforces_band_plan() {
+  let mut mock = mocked_io::Mock::new().responses(&["+DMOSETGROUP=0\r\n"]);
+  let channel = simplex(147.0).band_plan(BandPlan::iaru(Region::One));
+  assert_eq!(
+    channel.write_config(&mut mock),
+    Err(String::from(
+      "147.0000 MHz is outside the IARU Region 1 band plan"
+    ))
+  );
+  assert!(channel
+    .ignore_band_plan(true)
+    .write_config(&mut mock)
+    .is_ok());
+}
+
+#[test]
+fn pmr446_channels() {
+  let channel = Channel::pmr446(1, Some(GroupSel::new_ctcss(8).unwrap())).unwrap();
+  assert_eq!(channel.rx_frequency(), Some(446.00625));
+  assert_eq!(channel.tx_frequency(), Some(446.00625));
+  assert_eq!(channel.power_level(), PowerLevel::Low);
+  assert_eq!(channel.fm_bandwidth(), FmBandwidth::Narrow);
+  assert_eq!(channel.access(), Access::Transmit);
+  assert_eq!(
+    Channel::pmr446(16, None).unwrap().tx_frequency(),
+    Some(446.19375)
+  );
+  assert!(Channel::pmr446(0, None).is_err());
+  assert!(Channel::pmr446(17, None).is_err());
+
+  let rejected = |channel: Channel| matches!(channel.access(), Access::ReceiveOnly { .. });
+  let channel = || Channel::pmr446(3, None).unwrap();
+  assert!(rejected(channel().bandwidth(FmBandwidth::Wide)));
+  assert!(rejected(channel().power(PowerLevel::High)));
+  assert!(rejected(channel().offset(0.0125).unwrap()));
+  assert!(rejected(channel().split(446.1).unwrap()));
+  assert!(rejected(
+    Channel::default()
+      .power(PowerLevel::Low)
+      .rx(FreqConf::new(446.1).unwrap())
+      .tx(FreqConf::new(446.1).unwrap())
+      .band_plan(BandPlan::pmr446())
+  ));
+
+  // A band plan adds to the PMR446 rules, ignoring it doesn't lift them.
+  let planned = |channel: Channel| {
+    channel
+      .band_plan(BandPlan::iaru(Region::Two))
+      .ignore_band_plan(true)
+  };
+  assert_eq!(planned(channel()).access(), Access::Transmit);
+  assert!(rejected(planned(channel().bandwidth(FmBandwidth::Wide))));
+  assert!(rejected(channel().band_plan(BandPlan::iaru(Region::One))));
+
+  let mut mock = mocked_io::Mock::new().responses(&["+DMOSETGROUP=0\r\n"]);
+  assert_eq!(
+    planned(channel().bandwidth(FmBandwidth::Wide)).write_config(&mut mock),
+    Err(String::from("PMR446 only allows narrow FM at 446.0312 MHz"))
+  );
+  assert!(channel()
+    .power(PowerLevel::High)
+    .write_config(&mut mock)
+    .is_err());
+  assert!(channel().write_config(&mut mock).is_ok());
+}
@@ -0,0 +1,88 @@
+use sa818::{
+  channel::{Channel, FmBandwidth, FreqConf},
+  channel_bank::ChannelBank,
+  group_call::{DcsSuffix, GroupSel},
+};
+
+fn repeater() -> Channel {
+  Channel::default()
+    .bandwidth(FmBandwidth::Wide)
+    .squelch(2)
+    .unwrap()
+    .rx(FreqConf::with_group_sel(145.6, GroupSel::new_ctcss(8).unwrap()).unwrap())
+    .tx(FreqConf::with_group_sel(145.0, GroupSel::new_dcs(23, DcsSuffix::Normal).unwrap()).unwrap())
+}
+
+fn simplex(frequency: f32) -> Channel {
+  Channel::default()
+    .rx(FreqConf::new(frequency).unwrap())
+    .tx(FreqConf::new(frequency).unwrap())
+}
+
+#[test]
+fn bank_lookup() {
+  let mut bank = ChannelBank::new();
+  assert_eq!(bank.add("calling", simplex(145.5), "").unwrap(), 0);
+  assert_eq!(bank.add("R7", repeater(), "").unwrap(), 1);
+  assert!(bank.add("calling", simplex(145.55), "").is_err());
+  assert!(bank.add("", simplex(145.55), "").is_err());
+  assert!(bank.add("empty", Channel::default(), "").is_err());
+
+  assert_eq!(bank.lookup("R7").unwrap().0, 1);
+  assert_eq!(bank.lookup("0").unwrap().1.name, "calling");
+  assert!(bank.lookup("2").is_none());
+  assert!(bank.lookup("missing").is_none());
+
+  assert_eq!(bank.remove("calling").unwrap().name, "calling");
+  assert_eq!(bank.lookup("0").unwrap().1.name, "R7");
+  assert!(bank.remove("calling").is_err());
+}
+
+#[test]
+fn bank_round_trip() {
+  let mut bank = ChannelBank::new();
+  bank.add("calling", simplex(145.5), "").unwrap();
+  bank
+    .add("R7", repeater(), "club repeater, \"weekly net\"")
+    .unwrap();
+  let text = bank.to_string();
+  assert_eq!(
+    text,
+    "name,rx_frequency,tx_frequency,rx_tone,tx_tone,bandwidth,squelch,notes\n\
+     calling,145.5000,145.5000,,,narrow,4,\n\
+     R7,145.6000,145.0000,88.5,023N,wide,2,\"club repeater, \"\"weekly net\"\"\"\n"
+  );
+
+  let parsed = ChannelBank::parse(&text).unwrap();
+  assert_eq!(parsed.len(), 2);
+  let memory = parsed.get(1).unwrap();
+  assert_eq!(memory.notes, "club repeater, \"weekly net\"");
+  assert_eq!(memory.channel.fm_bandwidth(), FmBandwidth::Wide);
+  assert_eq!(memory.channel.squelch_level(), 2);
+  assert_eq!(
+    memory.channel.rx_conf().unwrap().group_sel,
+    Some(GroupSel::new_ctcss(8).unwrap())
+  );
+  assert_eq!(
+    memory.channel.tx_conf().unwrap().group_sel,
+    Some(GroupSel::new_dcs(23, DcsSuffix::Normal).unwrap())
+  );
+  assert_eq!(parsed.to_string(), text);
+}
+
+#[test]
+fn bank_parse_errors() {
+  let header = "name,rx_frequency,tx_frequency,rx_tone,tx_tone,bandwidth,squelch,notes\n";
+  assert!(ChannelBank::parse(&format!("{header}a,145.5,145.5,,,narrow,4\n")).is_err());
+  assert!(ChannelBank::parse(&format!("{header}a,145.5,145.5,,,medium,4,\n")).is_err());
+  assert!(ChannelBank::parse(&format!("{header}a,100.0,100.0,,,narrow,4,\n")).is_err());
+  assert!(ChannelBank::parse(&format!("{header}a,145.5,145.5,1.0,,narrow,4,\n")).is_err());
+  assert!(ChannelBank::parse(header).unwrap().is_empty());
+  assert!(ChannelBank::parse("").unwrap().is_empty());
+  // A file without the header doesn't lose its first channel.
+  assert!(ChannelBank::parse("a,145.5,145.5,,,narrow,4,\nb,145.6,145.6,,,narrow,4,\n").is_err());
+
+  let mut bank = ChannelBank::new();
+  assert!(bank.add("net", simplex(145.5), "first\nsecond").is_err());
+  assert!(bank.add("a\rb", simplex(145.5), "").is_err());
+}
@@ -0,0 +1,146 @@
+use sa818::{
+  channel::{Channel, FmBandwidth, FreqConf},
+  channel_bank::MemoryChannel,
+  chirp,
+  group_call::{DcsSuffix, GroupSel},
+};
+
+const HEADER: &str = "Location,Name,Frequency,Duplex,Offset,Tone,rToneFreq,cToneFreq,DtcsCode,\
+DtcsPolarity,RxDtcsCode,CrossMode,Mode,TStep,Skip,Power,Comment,URCALL,RPT1CALL,RPT2CALL,DVCODE";
+
+fn memory(name: &str, rx: FreqConf, tx: FreqConf, bandwidth: FmBandwidth) -> MemoryChannel {
+  MemoryChannel {
+    name: name.to_string(),
+    channel: Channel::default().bandwidth(bandwidth).rx(rx).tx(tx),
+    notes: String::new(),
+  }
+}
+
+#[test]
+fn chirp_import() {
+  let list = format!(
+    "{HEADER}\n\
+     0,CALL,145.500000,,0.000000,,88.5,88.5,023,NN,023,Tone->Tone,NFM,12.50,,5.0W,,,,,\n\
+     1,R7,145.775000,-,0.600000,Tone,88.5,88.5,023,NN,023,Tone->Tone,FM,12.50,,5.0W,\"club, net\",,,,\n\
+     2,TSQ,433.500000,,0.000000,TSQL,88.5,123.0,023,NN,023,Tone->Tone,NFM,12.50,,5.0W,,,,,\n\
+     3,DCS,433.525000,+,1.600000,DTCS,88.5,88.5,047,NR,047,Tone->Tone,NFM,12.50,,5.0W,,,,,\n\
+     4,X,145.600000,split,145.000000,Cross,100.0,88.5,754,NN,023,DTCS->Tone,NFM,12.50,,5.0W,,,,,\n\
+     5,AIR,118.100000,,0.000000,,88.5,88.5,023,NN,023,Tone->Tone,AM,12.50,,5.0W,,,,,\n\
+     6,HF,28.500000,,0.000000,,88.5,88.5,023,NN,023,Tone->Tone,FM,12.50,,5.0W,,,,,\n\
+     7,ODD,145.500000,,0.000000,Tone,69.3,88.5,023,NN,023,Tone->Tone,FM,12.50,,5.0W,,,,,\n\
+     8,RXO,145.500000,off,0.000000,,88.5,88.5,023,NN,023,Tone->Tone,FM,12.50,,5.0W,,,,,\n"
+  );
+  let import = chirp::import(&list).unwrap();
+  assert_eq!(import.channels.len(), 5);
+
+  let r7 = &import.channels[1];
+  assert_eq!(r7.name, "R7");
+  assert_eq!(r7.notes, "club, net");
+  assert_eq!(r7.channel.fm_bandwidth(), FmBandwidth::Wide);
+  assert!((r7.channel.tx_frequency().unwrap() - 145.175).abs() < 0.0001);
+  assert_eq!(r7.channel.rx_conf().unwrap().group_sel, None);
+  assert_eq!(
+    r7.channel.tx_conf().unwrap().group_sel,
+    Some(GroupSel::new_ctcss(8).unwrap())
+  );
+
+  let tsql = &import.channels[2].channel;
+  assert_eq!(
+    tsql.rx_conf().unwrap().group_sel,
+    Some(GroupSel::new_ctcss(18).unwrap())
+  );
+  assert_eq!(
+    tsql.tx_conf().unwrap().group_sel,
+    Some(GroupSel::new_ctcss(18).unwrap())
+  );
+
+  let dcs = &import.channels[3].channel;
+  assert_eq!(
+    dcs.rx_conf().unwrap().group_sel,
+    Some(GroupSel::new_dcs(47, DcsSuffix::Inverted).unwrap())
+  );
+  assert_eq!(
+    dcs.tx_conf().unwrap().group_sel,
+    Some(GroupSel::new_dcs(47, DcsSuffix::Normal).unwrap())
+  );
+
+  let cross = &import.channels[4].channel;
+  assert_eq!(cross.tx_frequency(), Some(145.0));
+  assert_eq!(
+    cross.rx_conf().unwrap().group_sel,
+    Some(GroupSel::new_ctcss(8).unwrap())
+  );
+  assert_eq!(
+    cross.tx_conf().unwrap().group_sel,
+    Some(GroupSel::new_dcs(754, DcsSuffix::Normal).unwrap())
+  );
+
+  let rejected: Vec<(usize, &str)> = import
+    .rejected
+    .iter()
+    .map(|r| (r.line, r.name.as_str()))
+    .collect();
+  assert_eq!(
+    rejected,
+    vec![(7, "AIR"), (8, "HF"), (9, "ODD"), (10, "RXO")]
+  );
+  assert_eq!(import.rejected[0].reason, "mode AM is not supported");
+  assert_eq!(
+    import.rejected[2].to_string(),
+    "line 9 (ODD): CTCSS 69.3 Hz is not supported"
+  );
+}
+
+#[test]
+fn chirp_missing_column() {
+  assert!(chirp::import("Location,Name,Frequency\n0,A,145.5\n").is_err());
+}
+
+#[test]
+fn chirp_round_trip() {
+  let channels = vec![
+    memory(
+      "simplex",
+      FreqConf::new(145.5).unwrap(),
+      FreqConf::new(145.5).unwrap(),
+      FmBandwidth::Narrow,
+    ),
+    memory(
+      "R7",
+      FreqConf::new(145.6).unwrap(),
+      FreqConf::with_group_sel(145.0, GroupSel::new_ctcss(8).unwrap()).unwrap(),
+      FmBandwidth::Wide,
+    ),
+    memory(
+      "dcs",
+      FreqConf::with_group_sel(433.5, GroupSel::new_dcs(23, DcsSuffix::Normal).unwrap()).unwrap(),
+      FreqConf::with_group_sel(433.5, GroupSel::new_dcs(23, DcsSuffix::Inverted).unwrap()).unwrap(),
+      FmBandwidth::Narrow,
+    ),
+    memory(
+      "cross",
+      FreqConf::with_group_sel(433.5, GroupSel::new_ctcss(12).unwrap()).unwrap(),
+      FreqConf::new(145.5).unwrap(),
+      FmBandwidth::Narrow,
+    ),
+  ];
+  let list = chirp::export(&channels);
+  let lines: Vec<&str> = list.lines().collect();
+  assert_eq!(lines[0], HEADER);
+  assert_eq!(
+    lines[2],
+    "1,R7,145.600000,-,0.600000,Tone,88.5,88.5,023,NN,023,Tone->Tone,FM,5.00,,1.0W,,,,,"
+  );
+  assert_eq!(
+    lines[3],
+    "2,dcs,433.500000,,0.000000,DTCS,88.5,88.5,023,RN,023,Tone->Tone,NFM,5.00,,1.0W,,,,,"
+  );
+  assert_eq!(
+    lines[4],
+    "3,cross,433.500000,split,145.500000,Cross,88.5,100.0,023,NN,023,->Tone,NFM,5.00,,1.0W,,,,,"
+  );
+
+  let import = chirp::import(&list).unwrap();
+  assert!(import.rejected.is_empty());
+  assert_eq!(chirp::export(&import.channels), list);
+}
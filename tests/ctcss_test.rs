@@ -0,0 +1,43 @@
+use sa818::group_call::{parse_ctcss, parse_tone, Ctcss, GroupSel};
+
+#[test]
+fn ctcss_lookup() {
+  let tone = Ctcss::from_index(8).unwrap();
+  assert_eq!(tone.hz(), 88.5);
+  assert_eq!(tone.index(), 8);
+  assert_eq!(tone.module_code(), "0008");
+  assert_eq!(tone.to_string(), "88.5 Hz");
+  assert!(Ctcss::from_index(0).is_err());
+  assert!(Ctcss::from_index(39).is_err());
+
+  assert_eq!(Ctcss::from_hz(88.5, Ctcss::TOLERANCE), Ok(tone));
+  assert_eq!(Ctcss::from_hz(88.46, Ctcss::TOLERANCE), Ok(tone));
+  assert!(Ctcss::from_hz(88.3, Ctcss::TOLERANCE).is_err());
+  assert_eq!(Ctcss::from_hz(88.3, 0.5), Ok(tone));
+  assert_eq!(Ctcss::try_from(250.3f32).unwrap().index(), 38);
+  assert_eq!(Ctcss::nearest(69.3).hz(), 67.0);
+  assert_eq!(Ctcss::nearest(1000.0).hz(), 250.3);
+
+  assert_eq!(Ctcss::all().count(), 38);
+  assert!(Ctcss::all()
+    .zip(Ctcss::all().skip(1))
+    .all(|(a, b)| a.hz() < b.hz()));
+}
+
+#[test]
+fn ctcss_parse() {
+  for tone in ["88.5", "88.5Hz", "88.5 Hz", " 88.50 hz"] {
+    assert_eq!(tone.parse::<Ctcss>().unwrap().index(), 8, "{tone}");
+  }
+  assert_eq!("100".parse::<Ctcss>().unwrap().index(), 12);
+  assert!("69.3".parse::<Ctcss>().is_err());
+  assert!("88.5kHz".parse::<Ctcss>().is_err());
+  assert!("".parse::<Ctcss>().is_err());
+  assert!("nan".parse::<Ctcss>().is_err());
+  assert!("inf Hz".parse::<Ctcss>().is_err());
+
+  let group_sel = parse_ctcss("67.0 Hz").unwrap();
+  assert_eq!(group_sel, GroupSel::Ctcss(Ctcss::from_index(1).unwrap()));
+  assert_eq!(group_sel.to_string(), "0001");
+  assert_eq!(parse_tone("123").unwrap().tone_string(), "123.0");
+}
@@ -0,0 +1,121 @@
+mod mocked_io;
+use sa818::{
+  audio::AudioSource,
+  cw_id::{morse, CwEncoder, CwId, IdSchedule},
+  goertzel::Goertzel,
+  ptt::Transmitter,
+  wav::{WavReader, WavWriter},
+};
+use std::{
+  io::Cursor,
+  time::{Duration, Instant},
+};
+
+const RATE: u32 = 8000;
+
+/// Reads the keying back, one character per dot: '#' for tone, ' ' for none.
+fn keying(samples: &[f32], dot: usize, tone: f32) -> String {
+  let filter = Goertzel::new(tone, RATE);
+  samples
+    .chunks(dot)
+    .map(|chunk| match filter.power(chunk) > 0.05 {
+      true => '#',
+      false => ' ',
+    })
+    .collect::<String>()
+    .trim_end()
+    .to_string()
+}
+
+#[test]
+fn cw_timing() {
+  assert_eq!(morse('k'), Some("-.-"));
+  assert_eq!(morse('!'), None);
+
+  let encoder = CwEncoder::new(RATE);
+  assert_eq!(encoder.dot(), Duration::from_millis(60));
+  // PARIS is 50 units with the space after the word.
+  assert_eq!(
+    encoder.duration("PARIS").unwrap(),
+    Duration::from_millis(43 * 60)
+  );
+  assert_eq!(encoder.encode("PARIS").unwrap().len(), 43 * 480);
+  assert!(encoder.encode("DE N0CALL!").is_err());
+  assert!(CwEncoder::new(RATE).wpm(0.0).encode("E").is_err());
+
+  let encoder = CwEncoder::new(RATE).wpm(12.0).tone(600.0);
+  let audio = encoder.encode("de k").unwrap();
+  assert_eq!(keying(&audio, 800, 600.0), "### # #   #       ### # ###");
+}
+
+#[test]
+fn id_schedule() {
+  let start = Instant::now();
+  let minutes = |m: u64| start + Duration::from_secs(m * 60);
+  let mut schedule = IdSchedule::new(Duration::from_secs(600));
+  assert!(!schedule.due(start));
+
+  // First key-up identifies.
+  assert!(schedule.key_up(start));
+  schedule.identified(start);
+  assert!(!schedule.key_up(minutes(1)));
+  assert!(!schedule.due(minutes(5)));
+  assert!(schedule.due(minutes(10)));
+  schedule.identified(minutes(10));
+
+  // Quiet since, nothing more to send until the next key-up.
+  assert!(!schedule.due(minutes(25)));
+  assert!(schedule.key_up(minutes(30)));
+  schedule.identified(minutes(30));
+  assert!(!schedule.due(minutes(35)));
+  assert!(schedule.due(minutes(40)));
+}
+
+#[test]
+fn cw_id_transmit() {
+  let sink = WavWriter::new(Cursor::new(Vec::new()), RATE).unwrap();
+  let transmitter = Transmitter::new(mocked_io::RecordedPtt::default(), sink)
+    .key_delay(Duration::from_millis(200))
+    .timeout(Duration::from_secs(30));
+  let encoder = CwEncoder::new(RATE).wpm(20.0);
+  let mut id = CwId::new(transmitter, &encoder, "N0CALL", Duration::from_secs(600)).unwrap();
+
+  let start = Instant::now();
+  assert!(id.key_up(start).unwrap());
+  assert!(!id.key_up(start + Duration::from_secs(60)).unwrap());
+  assert!(!id.poll(start + Duration::from_secs(300)).unwrap());
+  assert!(id.poll(start + Duration::from_secs(600)).unwrap());
+  assert_eq!(
+    id.schedule().last_id(),
+    Some(start + Duration::from_secs(600))
+  );
+
+  let (ptt, sink) = id.into_transmitter().into_parts();
+  assert_eq!(ptt.0, vec![true, false, true, false]);
+  let mut wav = sink.finish().unwrap();
+  wav.set_position(0);
+  let audio = WavReader::new(wav).unwrap().read_to_end().unwrap();
+  let single = 1600 + encoder.encode("N0CALL").unwrap().len();
+  assert_eq!(audio.len(), 2 * single);
+  assert_eq!(
+    keying(&audio[1600..single], 480, 800.0),
+    keying(&encoder.encode("N0CALL").unwrap(), 480, 800.0)
+  );
+}
+
+#[test]
+fn cw_id_time_out_timer() {
+  let sink = WavWriter::new(Cursor::new(Vec::new()), RATE).unwrap();
+  let transmitter =
+    Transmitter::new(mocked_io::RecordedPtt::default(), sink).timeout(Duration::from_secs(2));
+  let slow = CwEncoder::new(RATE).wpm(5.0);
+  assert!(CwId::new(transmitter, &slow, "N0CALL", Duration::from_secs(600)).is_err());
+
+  let sink = WavWriter::new(Cursor::new(Vec::new()), RATE).unwrap();
+  let mut transmitter =
+    Transmitter::new(mocked_io::RecordedPtt::default(), sink).timeout(Duration::from_secs(2));
+  assert!(transmitter
+    .transmit(&slow.encode("N0CALL").unwrap())
+    .is_err());
+  assert!(transmitter.ptt().0.is_empty());
+}
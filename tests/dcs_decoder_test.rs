@@ -0,0 +1,160 @@
+use sa818::{
+  audio::MemorySource,
+  dcs_decoder::{DcsDecoder, BIT_RATE},
+  group_call::{Dcs, DcsSuffix, GroupSel},
+};
+use std::{f32::consts::PI, time::Duration};
+
+const RATE: u32 = 8000;
+
+/// NRZ bit stream repeating the codeword, starting `skip` bits in.
+fn dcs(dcs: Dcs, bit_rate: f32, amplitude: f32, seconds: f32, skip: usize) -> Vec<f32> {
+  let codeword = dcs.codeword();
+  (0..(RATE as f32 * seconds) as usize)
+    .map(|i| {
+      let bit = (i as f32 * bit_rate / RATE as f32) as usize + skip;
+      match codeword >> (bit % 23) & 1 {
+        1 => amplitude,
+        _ => -amplitude,
+      }
+    })
+    .collect()
+}
+
+/// Adds a louder "voice" of a few audio frequencies and some noise.
+fn add_voice(samples: &mut [f32]) {
+  let mut seed = 1u32;
+  for (i, sample) in samples.iter_mut().enumerate() {
+    let t = i as f32 / RATE as f32;
+    for (hz, amplitude) in [(440.0, 0.3), (730.0, 0.2), (1250.0, 0.15)] {
+      *sample += amplitude * (2.0 * PI * hz * t).sin();
+    }
+    seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+    *sample += 0.05 * ((seed >> 8) as f32 / (1 << 24) as f32 - 0.5);
+  }
+}
+
+#[test]
+fn codeword_from_any_bit() {
+  let d023 = Dcs::new(23, DcsSuffix::Normal).unwrap();
+  for n in 0..23 {
+    let bits = (d023.codeword() >> n | d023.codeword() << (23 - n)) & 0x7FFFFF;
+    assert_eq!(Dcs::from_bits(bits), Some(d023));
+  }
+  // 047I puts the same bits on air, normal polarity wins.
+  let d047i = Dcs::new(47, DcsSuffix::Inverted).unwrap();
+  assert_eq!(Dcs::from_bits(d047i.codeword()), Some(d023));
+  assert_eq!(d023.with_polarity(DcsSuffix::Inverted), Some(d047i));
+  assert_eq!(d047i.with_polarity(DcsSuffix::Normal), Some(d023));
+  assert_eq!(d047i.with_polarity(DcsSuffix::Inverted), Some(d047i));
+  assert_eq!(Dcs::from_bits(0), None);
+  assert_eq!(Dcs::from_bits(0x7FFFFF), None);
+}
+
+#[test]
+fn detects_every_code() {
+  let decoder = DcsDecoder::new(RATE);
+  for code in Dcs::all() {
+    for polarity in [DcsSuffix::Normal, DcsSuffix::Inverted] {
+      let sent = Dcs::new(code.code(), polarity).unwrap();
+      let found = decoder
+        .detect(&dcs(sent, BIT_RATE, 0.1, 0.5, 5))
+        .unwrap_or_else(|| panic!("{sent} not found"));
+      assert!(
+        found.dcs == sent || found.dcs.is_alias(&sent),
+        "{sent} {}",
+        found.dcs
+      );
+      assert!(found.confidence > 0.9, "{sent} {}", found.confidence);
+      assert!((found.level - 0.1).abs() < 0.03, "{sent} {}", found.level);
+    }
+  }
+}
+
+#[test]
+fn detects_code_under_voice() {
+  let sent = Dcs::new(754, DcsSuffix::Normal).unwrap();
+  // A transmitter running 0.3% slow.
+  let mut samples = dcs(sent, 134.0, 0.1, 3.0, 11);
+  add_voice(&mut samples);
+  let found = DcsDecoder::new(RATE)
+    .scan(&mut MemorySource::new(RATE, samples))
+    .unwrap()
+    .unwrap();
+  assert_eq!(found.group_sel(), GroupSel::Dcs(sent));
+  assert!(found.confidence > 0.8, "{}", found.confidence);
+}
+
+#[test]
+fn inverted_audio() {
+  let sent = Dcs::new(754, DcsSuffix::Normal).unwrap();
+  let samples: Vec<f32> = dcs(sent, BIT_RATE, 0.1, 0.5, 0)
+    .into_iter()
+    .map(|sample| -sample)
+    .collect();
+  // Inverted audio carries the bits of 754I, the same as 116N.
+  let normal = DcsDecoder::new(RATE).detect(&samples).unwrap();
+  assert_eq!(normal.dcs, Dcs::new(116, DcsSuffix::Normal).unwrap());
+  let inverted = DcsDecoder::new(RATE)
+    .inverted(true)
+    .detect(&samples)
+    .unwrap();
+  assert_eq!(inverted.dcs, sent);
+}
+
+#[test]
+fn window_holds_a_codeword() {
+  assert!(DcsDecoder::new(RATE).window(Duration::ZERO).is_err());
+  assert!(DcsDecoder::new(RATE)
+    .window(Duration::from_millis(150))
+    .is_err());
+  let decoder = DcsDecoder::new(RATE)
+    .window(Duration::from_millis(200))
+    .unwrap();
+  assert_eq!(decoder.window_len(), 1600);
+}
+
+#[test]
+fn reports_preferred_polarity() {
+  let samples = dcs(
+    Dcs::new(23, DcsSuffix::Normal).unwrap(),
+    BIT_RATE,
+    0.1,
+    0.5,
+    3,
+  );
+  let found = DcsDecoder::new(RATE)
+    .polarity(DcsSuffix::Inverted)
+    .detect(&samples)
+    .unwrap();
+  assert_eq!(found.dcs, Dcs::new(47, DcsSuffix::Inverted).unwrap());
+}
+
+#[test]
+fn nothing_in_silence_voice_or_ctcss() {
+  let decoder = DcsDecoder::new(RATE);
+  assert_eq!(decoder.detect(&[0.0; 4000]), None);
+
+  let mut voice = vec![0.0; 3 * RATE as usize];
+  add_voice(&mut voice);
+  let mut source = MemorySource::new(RATE, voice);
+  assert!(decoder
+    .scan(&mut source)
+    .unwrap()
+    .is_none_or(|found| found.confidence < 0.1));
+
+  let ctcss: Vec<f32> = (0..3 * RATE as usize)
+    .map(|i| 0.1 * (2.0 * PI * 100.0 * i as f32 / RATE as f32).sin())
+    .collect();
+  let mut source = MemorySource::new(RATE, ctcss);
+  assert!(decoder
+    .scan(&mut source)
+    .unwrap()
+    .is_none_or(|found| found.confidence < 0.1));
+}
+
+#[test]
+fn scan_rejects_other_rates() {
+  let mut source = MemorySource::new(RATE, vec![0.0; 100]);
+  assert!(DcsDecoder::new(48000).scan(&mut source).is_err());
+}
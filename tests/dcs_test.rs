@@ -0,0 +1,74 @@
+use sa818::group_call::{parse_dcs, parse_tone, Dcs, DcsSuffix, GroupSel};
+
+#[test]
+fn dcs_table() {
+  assert_eq!(Dcs::all().count(), 104);
+  assert!(Dcs::new(23, DcsSuffix::Normal).is_ok());
+  assert!(Dcs::new(754, DcsSuffix::Inverted).is_ok());
+  for code in [24, 99, 340, 777] {
+    assert!(Dcs::new(code, DcsSuffix::Normal).is_err(), "{code}");
+  }
+  assert_eq!(
+    GroupSel::new_dcs(24, DcsSuffix::Normal),
+    Err(String::from("Invalid dcs code 024"))
+  );
+  assert!(GroupSel::new_dcs(70000, DcsSuffix::Normal).is_err());
+
+  let dcs = Dcs::new(23, DcsSuffix::Normal).unwrap();
+  assert_eq!(dcs.octal(), 0o23);
+  assert_eq!(dcs.module_code(), "023N");
+  assert_eq!(GroupSel::Dcs(dcs).to_string(), "023N");
+  assert_eq!(
+    Dcs::new(754, DcsSuffix::Inverted).unwrap().to_string(),
+    "754I"
+  );
+}
+
+#[test]
+fn dcs_parse() {
+  let normal = Dcs::new(23, DcsSuffix::Normal).unwrap();
+  let inverted = Dcs::new(23, DcsSuffix::Inverted).unwrap();
+  for (code, expected) in [
+    ("D023N", normal),
+    ("023N", normal),
+    ("23N", normal),
+    ("023", normal),
+    ("d023i", inverted),
+    ("023I", inverted),
+  ] {
+    assert_eq!(code.parse::<Dcs>(), Ok(expected), "{code}");
+  }
+  for code in ["", "N", "024N", "089N", "0023N", "023X", "D"] {
+    assert!(code.parse::<Dcs>().is_err(), "{code}");
+  }
+  assert_eq!(
+    parse_dcs(String::from("D047I")),
+    Ok(GroupSel::Dcs(Dcs::new(47, DcsSuffix::Inverted).unwrap()))
+  );
+  assert_eq!(parse_tone("23N").unwrap().tone_string(), "023N");
+  assert_eq!(parse_tone("D023").unwrap().tone_string(), "023N");
+  assert_eq!(parse_tone("023").unwrap().tone_string(), "023N");
+  assert_eq!(parse_tone("754").unwrap().tone_string(), "754N");
+  // Tones win over codes with the same digits.
+  assert_eq!(parse_tone("100").unwrap().tone_string(), "100.0");
+  assert!(parse_tone("024").is_err());
+}
+
+#[test]
+fn dcs_aliases() {
+  let d023 = Dcs::new(23, DcsSuffix::Normal).unwrap();
+  let aliases = d023.aliases();
+  assert!(aliases.contains(&(340, DcsSuffix::Normal)));
+  assert!(aliases.contains(&(766, DcsSuffix::Normal)));
+  assert!(aliases.contains(&(47, DcsSuffix::Inverted)));
+  assert!(!aliases.contains(&(23, DcsSuffix::Normal)));
+
+  assert!(d023.is_alias(&Dcs::new(47, DcsSuffix::Inverted).unwrap()));
+  assert!(d023.is_alias(&d023));
+  assert!(!d023.is_alias(&Dcs::new(25, DcsSuffix::Normal).unwrap()));
+  assert!(!d023.is_alias(&Dcs::new(23, DcsSuffix::Inverted).unwrap()));
+
+  // the codeword carries the code in its low 9 bits and the 100 marker
+  assert_eq!(d023.codeword() & 0xFFF, 0o23 | 0x800);
+  assert_eq!(d023.codeword() >> 23, 0);
+}
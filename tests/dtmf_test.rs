@@ -0,0 +1,215 @@
+mod mocked_io;
+use sa818::{
+  audio::{AudioSink, AudioSource},
+  channel::{Channel, FreqConf},
+  device::Sa818,
+  dtmf::{DtmfAction, DtmfCommands, DtmfDecoder, DtmfEncoder},
+  ptt::Transmitter,
+  volume_config::VolumeConfig,
+  wav::{WavReader, WavWriter},
+};
+use std::{
+  f32::consts::PI,
+  io::Cursor,
+  time::{Duration, Instant},
+};
+
+const RATE: u32 = 8000;
+
+#[test]
+fn dtmf_round_trip() {
+  let keys = "123A456B789C*0#D";
+  let samples = DtmfEncoder::new(RATE).encode(keys).unwrap();
+  assert_eq!(DtmfDecoder::new(RATE).decode(&samples), keys);
+
+  // Fed in small pieces, as from a sound card.
+  let mut decoder = DtmfDecoder::new(RATE);
+  let heard: String = samples
+    .chunks(37)
+    .flat_map(|chunk| decoder.push(chunk))
+    .collect();
+  assert_eq!(heard, keys);
+
+  let samples = DtmfEncoder::new(48000).encode("5 5 9").unwrap();
+  assert_eq!(DtmfDecoder::new(48000).decode(&samples), "559");
+  assert!(DtmfEncoder::new(RATE).encode("12x").is_err());
+}
+
+#[test]
+fn dtmf_timing() {
+  let short = DtmfEncoder::new(RATE)
+    .tone(Duration::from_millis(20))
+    .encode("1")
+    .unwrap();
+  assert_eq!(DtmfDecoder::new(RATE).decode(&short), "");
+
+  let fast = DtmfEncoder::new(RATE)
+    .tone(Duration::from_millis(45))
+    .gap(Duration::from_millis(45))
+    .encode("1122")
+    .unwrap();
+  assert_eq!(DtmfDecoder::new(RATE).decode(&fast), "1122");
+  assert_eq!(
+    DtmfDecoder::new(RATE)
+      .min_duration(Duration::from_millis(80))
+      .decode(&fast),
+    ""
+  );
+}
+
+#[test]
+fn dtmf_twist_and_noise() {
+  let twisted = DtmfEncoder::new(RATE).twist(6.0).encode("7").unwrap();
+  assert_eq!(DtmfDecoder::new(RATE).decode(&twisted), "");
+  assert_eq!(DtmfDecoder::new(RATE).twist(8.0, 8.0).decode(&twisted), "7");
+  let reverse = DtmfEncoder::new(RATE).twist(-6.0).encode("7").unwrap();
+  assert_eq!(DtmfDecoder::new(RATE).decode(&reverse), "7");
+
+  let quiet = DtmfEncoder::new(RATE).level(0.005).encode("7").unwrap();
+  assert_eq!(DtmfDecoder::new(RATE).decode(&quiet), "");
+
+  // "Voice" with energy near the DTMF tones.
+  let voice: Vec<f32> = (0..RATE as usize)
+    .map(|i| {
+      let t = i as f32 / RATE as f32;
+      [(300.0, 0.3), (700.0, 0.2), (1000.0, 0.2), (1200.0, 0.15)]
+        .iter()
+        .map(|(hz, amplitude)| amplitude * (2.0 * PI * hz * t).sin())
+        .sum()
+    })
+    .collect();
+  assert_eq!(DtmfDecoder::new(RATE).decode(&voice), "");
+  assert_eq!(DtmfDecoder::new(RATE).decode(&[0.0; 8000]), "");
+}
+
+fn commands() -> DtmfCommands {
+  DtmfCommands::new("1234")
+    .unwrap()
+    .command(
+      "01",
+      DtmfAction::Channel(
+        Channel::default()
+          .rx(FreqConf::new(145.5).unwrap())
+          .tx(FreqConf::new(145.5).unwrap()),
+      ),
+    )
+    .unwrap()
+    .command("7", DtmfAction::Volume(VolumeConfig::new(7).unwrap()))
+    .unwrap()
+}
+
+fn enter(commands: &mut DtmfCommands, keys: &str, at: Instant) -> Option<Result<String, String>> {
+  let mut result = None;
+  for key in keys.chars() {
+    if let Some(outcome) = commands.key(key, at) {
+      result = Some(outcome.map(|action| match action {
+        DtmfAction::Channel(_) => String::from("channel"),
+        DtmfAction::Volume(_) => String::from("volume"),
+      }));
+    }
+  }
+  result
+}
+
+#[test]
+fn dtmf_commands() {
+  let now = Instant::now();
+  let mut commands = commands();
+  assert_eq!(
+    enter(&mut commands, "*123401#", now),
+    Some(Ok("channel".into()))
+  );
+  assert_eq!(
+    enter(&mut commands, "*12347#", now),
+    Some(Ok("volume".into()))
+  );
+  assert_eq!(
+    enter(&mut commands, "*123499#", now),
+    Some(Err("Unknown command 99".into()))
+  );
+  // No entry without the leading *, a * starts over.
+  assert_eq!(enter(&mut commands, "12347#", now), None);
+  assert_eq!(
+    enter(&mut commands, "*99*12347#", now),
+    Some(Ok("volume".into()))
+  );
+
+  // Keys too far apart drop the entry.
+  assert_eq!(enter(&mut commands, "*1234", now), None);
+  assert_eq!(
+    enter(&mut commands, "7#", now + Duration::from_secs(6)),
+    None
+  );
+
+  assert!(DtmfCommands::new("12#").is_err());
+  assert!(DtmfCommands::new("").is_err());
+  assert!(commands
+    .command("0*", DtmfAction::Volume(VolumeConfig::new(1).unwrap()))
+    .is_err());
+}
+
+#[test]
+fn dtmf_pin_lockout() {
+  let now = Instant::now();
+  let mut commands = commands().lockout(2, Duration::from_secs(60));
+  assert_eq!(
+    enter(&mut commands, "*00007#", now),
+    Some(Err("Wrong PIN".into()))
+  );
+  // A good PIN resets the count.
+  assert!(enter(&mut commands, "*12347#", now).unwrap().is_ok());
+  enter(&mut commands, "*00007#", now);
+  enter(&mut commands, "*00007#", now);
+  assert!(commands.is_locked(now));
+  assert_eq!(
+    enter(&mut commands, "*12347#", now + Duration::from_secs(30)),
+    Some(Err("Commands are locked out".into()))
+  );
+  let later = now + Duration::from_secs(61);
+  assert!(!commands.is_locked(later));
+  assert_eq!(
+    enter(&mut commands, "*12347#", later),
+    Some(Ok("volume".into()))
+  );
+}
+
+#[test]
+fn dtmf_action_apply() {
+  let mock = mocked_io::Mock::new().responses(&["+DMOSETVOLUME:0\r\n", "+DMOSETGROUP=0\r\n"]);
+  let device = Sa818::new(mock);
+  let mut commands = commands();
+  let now = Instant::now();
+  for keys in ["*12347#", "*123401#"] {
+    for key in keys.chars() {
+      match commands.key(key, now) {
+        Some(action) => {
+          action.unwrap().apply(&device).unwrap();
+        }
+        None => assert_ne!(key, '#'),
+      }
+    }
+  }
+  assert_eq!(
+    device.lock().unwrap().input,
+    "AT+DMOSETVOLUME=7\r\nAT+DMOSETGROUP=1,145.5000,145.5000,0000,4,0000\r\n"
+  );
+}
+
+#[test]
+fn dtmf_transmit() {
+  let sink = WavWriter::new(Cursor::new(Vec::new()), RATE).unwrap();
+  let mut transmitter =
+    Transmitter::new(mocked_io::RecordedPtt::default(), sink).key_delay(Duration::from_millis(50));
+  let samples = DtmfEncoder::new(RATE).encode("*1234#").unwrap();
+  transmitter.transmit(&samples).unwrap();
+  assert_eq!(transmitter.sink().sample_rate(), RATE);
+  let (ptt, sink) = transmitter.into_parts();
+  assert_eq!(ptt.0, vec![true, false]);
+
+  let mut wav = sink.finish().unwrap();
+  wav.set_position(0);
+  let mut reader = WavReader::new(wav).unwrap();
+  let audio = reader.read_to_end().unwrap();
+  assert_eq!(audio.len(), samples.len() + 400);
+  assert_eq!(DtmfDecoder::new(RATE).decode(&audio), "*1234#");
+}
@@ -0,0 +1,72 @@
+mod mocked_io;
+use sa818::{
+  channel::{Channel, FreqConf},
+  device::Sa818,
+  dual_watch::{DualWatch, WatchEvent},
+};
+use std::time::Duration;
+
+const SET: &str = "+DMOSETGROUP=0\r\n";
+
+fn simplex(frequency: f32) -> Channel {
+  Channel::default()
+    .rx(FreqConf::new(frequency).unwrap())
+    .tx(FreqConf::new(frequency).unwrap())
+}
+
+#[test]
+fn dual_watch_cycle() {
+  let mock = mocked_io::Mock::new().responses(&[
+    //start on primary
+    SET,
+    //idle priority, back to primary
+    SET,
+    "RSSI=10\r\n",
+    SET,
+    //active priority, stay
+    SET,
+    "RSSI=90\r\n",
+    "RSSI=80\r\n",
+    //priority ended, back to primary
+    "RSSI=5\r\n",
+    SET,
+  ]);
+  let device = Sa818::new(mock);
+  let mut watch = DualWatch::new(device.clone(), simplex(145.5), simplex(145.6))
+    .interval(Duration::ZERO)
+    .sample_time(Duration::ZERO)
+    .threshold(50);
+  watch.start().unwrap();
+  let events: Vec<WatchEvent> = watch.by_ref().take(4).map(|e| e.unwrap()).collect();
+  assert_eq!(
+    events,
+    vec![
+      WatchEvent::PriorityIdle { rssi: 10 },
+      WatchEvent::PriorityActive { rssi: 90 },
+      WatchEvent::PriorityActive { rssi: 80 },
+      WatchEvent::PriorityEnded { rssi: 5 },
+    ]
+  );
+  assert!(!watch.on_priority());
+  let tuned: Vec<&str> = device
+    .lock()
+    .unwrap()
+    .input
+    .lines()
+    .filter(|line| line.starts_with("AT+DMOSETGROUP"))
+    .map(|line| line.split(',').nth(2).unwrap())
+    .collect::<Vec<_>>()
+    .into_iter()
+    .map(|f| {
+      if f == "145.5000" {
+        "primary"
+      } else {
+        "priority"
+      }
+    })
+    .collect();
+  assert_eq!(
+    tuned,
+    vec!["primary", "priority", "primary", "priority", "primary"]
+  );
+}
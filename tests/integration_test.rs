@@ -37,12 +37,15 @@ fn write_channel_conf() {
     .rx(FreqConf::with_ctcss(433.95, 8).unwrap());
   let mut mock = mocked_io::Mock::new().response("+DMOSETGROUP=0\r\n".to_string());
   let response = channel.write_config(&mut mock);
-  assert_eq!(mock.input, "AT+DMOSETGROUP=1,433.9250,433.9500,15,4,8\r\n");
+  assert_eq!(
+    mock.input,
+    "AT+DMOSETGROUP=1,433.9250,433.9500,0015,4,0008\r\n"
+  );
   assert!(response.is_ok());
 
   //Test dcs setting
   let normal_dcs = GroupSel::new_dcs(26, DcsSuffix::Normal).unwrap();
-  let inverted_dcs = GroupSel::new_dcs(90, DcsSuffix::Inverted).unwrap();
+  let inverted_dcs = GroupSel::new_dcs(71, DcsSuffix::Inverted).unwrap();
   let channel = Channel::default()
     .tx(FreqConf::with_group_sel(433.925, normal_dcs).unwrap())
     .rx(FreqConf::with_group_sel(433.950, inverted_dcs).unwrap());
@@ -50,7 +53,7 @@ fn write_channel_conf() {
   let response = channel.write_config(&mut mock);
   assert_eq!(
     mock.input,
-    "AT+DMOSETGROUP=1,433.9250,433.9500,26N,4,90I\r\n"
+    "AT+DMOSETGROUP=1,433.9250,433.9500,026N,4,071I\r\n"
   );
   assert!(response.is_ok());
 
@@ -60,6 +63,20 @@ fn write_channel_conf() {
   assert!(response.is_err())
 }
 
+#[test]
+fn write_channel_conf_reads_one_line() {
+  // Whatever follows the response is left for the next command.
+  let channel = Channel::default()
+    .tx(FreqConf::new(433.925).unwrap())
+    .rx(FreqConf::new(433.95).unwrap());
+  let mut mock =
+    mocked_io::Mock::new().response("+DMOSETGROUP=0\r\n+DMOSETGROUP=1\r\n".to_string());
+  assert_eq!(
+    channel.write_config(&mut mock),
+    Ok(String::from("+DMOSETGROUP=0\r\n"))
+  );
+}
+
 #[test]
 fn test_get_version() {
   //Test success
@@ -0,0 +1,201 @@
+mod mocked_io;
+use sa818::{
+  afsk::AfskDemodulator,
+  audio::AudioSource,
+  ax25::UiFrame,
+  device::Sa818,
+  kiss::{self, KissCommand, KissDecoder, KissParams, FEND, FESC, TFEND, TFESC},
+  ptt::Transmitter,
+  tnc::{ChannelBusy, Csma, RssiBusy, Tnc},
+  wav::{WavReader, WavWriter},
+};
+use std::{io::Cursor, time::Duration};
+
+#[test]
+fn kiss_escaping() {
+  let frame = [0x01, FEND, 0x02, FESC, 0x03];
+  let bytes = kiss::encode(0, &frame);
+  assert_eq!(
+    bytes,
+    vec![FEND, 0x00, 0x01, FESC, TFEND, 0x02, FESC, TFESC, 0x03, FEND]
+  );
+  assert_eq!(kiss::encode(2, &[])[1], 0x20);
+
+  let mut decoder = KissDecoder::new();
+  assert_eq!(
+    decoder.push(&bytes),
+    vec![KissCommand::Data {
+      port: 0,
+      frame: frame.to_vec()
+    }]
+  );
+}
+
+#[test]
+fn kiss_stream() {
+  let mut stream = kiss::encode(1, b"first");
+  stream.extend_from_slice(&[FEND, FEND, 0x01, 50, FEND, 0x02, 128, FEND, 0x03, FEND]);
+  stream.extend_from_slice(&[0x05, 1, FEND, 0x0E, 1, FEND, 0xFF, FEND]);
+  stream.extend(kiss::encode(0, b"second"));
+
+  // Split into single bytes, a command can arrive in any number of reads.
+  let mut decoder = KissDecoder::new();
+  let commands: Vec<KissCommand> = stream
+    .chunks(1)
+    .flat_map(|byte| decoder.push(byte))
+    .collect();
+  assert_eq!(
+    commands,
+    vec![
+      KissCommand::Data {
+        port: 1,
+        frame: b"first".to_vec()
+      },
+      KissCommand::TxDelay(50),
+      KissCommand::Persistence(128),
+      KissCommand::FullDuplex(true),
+      KissCommand::Return,
+      KissCommand::Data {
+        port: 0,
+        frame: b"second".to_vec()
+      },
+    ]
+  );
+}
+
+#[test]
+fn kiss_params() {
+  let mut params = KissParams::default();
+  assert_eq!(params.tx_delay, Duration::from_millis(500));
+  assert!(params.apply(&KissCommand::TxDelay(30)));
+  assert!(params.apply(&KissCommand::SlotTime(5)));
+  assert!(params.apply(&KissCommand::TxTail(2)));
+  assert!(params.apply(&KissCommand::Persistence(255)));
+  assert!(!params.apply(&KissCommand::Return));
+  assert_eq!(
+    params,
+    KissParams {
+      tx_delay: Duration::from_millis(300),
+      persistence: 255,
+      slot_time: Duration::from_millis(50),
+      tx_tail: Duration::from_millis(20),
+      full_duplex: false,
+    }
+  );
+}
+
+/// Busy for the first readings, then clear.
+struct BusyFor(usize);
+
+impl ChannelBusy for BusyFor {
+  fn busy(&mut self) -> Result<bool, String> {
+    self.0 = self.0.saturating_sub(1);
+    Ok(self.0 > 0)
+  }
+}
+
+#[test]
+fn csma_waits_for_clear_channel() {
+  let params = KissParams {
+    persistence: 255,
+    ..KissParams::default()
+  };
+  let mut slept = Vec::new();
+  Csma::new()
+    .wait(&params, &mut BusyFor(4), Duration::from_secs(1), |d| {
+      slept.push(d)
+    })
+    .unwrap();
+  assert_eq!(slept, vec![Duration::from_millis(100); 3]);
+
+  // A low persistence waits some slots on a clear channel, the same ones
+  // for the same seed.
+  let params = KissParams {
+    persistence: 32,
+    ..KissParams::default()
+  };
+  let slots = |seed| {
+    let mut slots = 0;
+    Csma::new()
+      .seed(seed)
+      .wait(&params, &mut BusyFor(0), Duration::from_secs(60), |_| {
+        slots += 1
+      })
+      .unwrap();
+    slots
+  };
+  assert_eq!(slots(1), slots(1));
+  assert!((1..100).map(slots).sum::<usize>() > 100);
+
+  let error = Csma::new()
+    .wait(
+      &params,
+      &mut BusyFor(usize::MAX),
+      Duration::from_secs(1),
+      |_| {},
+    )
+    .unwrap_err();
+  assert!(error.contains("busy"), "{error}");
+
+  let full_duplex = KissParams {
+    full_duplex: true,
+    ..params
+  };
+  let mut slept = 0;
+  Csma::new()
+    .wait(
+      &full_duplex,
+      &mut BusyFor(usize::MAX),
+      Duration::ZERO,
+      |_| slept += 1,
+    )
+    .unwrap();
+  assert_eq!(slept, 0);
+}
+
+#[test]
+fn rssi_busy() {
+  let mock = mocked_io::Mock::new().responses(&["RSSI=30\r\n", "RSSI=90\r\n"]);
+  let mut busy = RssiBusy::new(Sa818::new(mock), 60);
+  assert!(!busy.busy().unwrap());
+  assert!(busy.busy().unwrap());
+}
+
+#[test]
+fn tnc_sends_data_frames() {
+  let rate = 22050;
+  let sink = WavWriter::new(Cursor::new(Vec::new()), rate).unwrap();
+  let transmitter = Transmitter::new(mocked_io::RecordedPtt::default(), sink);
+  let mut tnc = Tnc::new(transmitter, BusyFor(0)).csma(Csma::new().seed(3));
+  assert_eq!(tnc.transmitter().duration(0), Duration::ZERO);
+  for command in [
+    KissCommand::TxDelay(20),
+    KissCommand::SlotTime(0),
+    KissCommand::Persistence(255),
+  ] {
+    tnc.command(&command).unwrap();
+  }
+  assert_eq!(tnc.params().tx_delay, Duration::from_millis(200));
+
+  let frame: UiFrame = "N0CALL-1>APRS:>kiss".parse().unwrap();
+  let mut decoder = KissDecoder::new();
+  for command in decoder.push(&kiss::encode(0, &frame.encode())) {
+    tnc.command(&command).unwrap();
+  }
+  // Other ports aren't on air.
+  tnc
+    .command(&KissCommand::Data {
+      port: 1,
+      frame: frame.encode(),
+    })
+    .unwrap();
+
+  let (ptt, sink) = tnc.into_transmitter().into_parts();
+  assert_eq!(ptt.0, vec![true, false]);
+  let mut wav = sink.finish().unwrap();
+  wav.set_position(0);
+  let samples = WavReader::new(wav).unwrap().read_to_end().unwrap();
+  let frames = AfskDemodulator::new(rate).push(&samples);
+  assert_eq!(frames.len(), 1);
+  assert_eq!(UiFrame::decode(&frames[0]).unwrap(), frame);
+}
@@ -1,9 +1,15 @@
-use std::io::{self, Read, Write};
+#![allow(dead_code, clippy::new_without_default)]
+use sa818::ptt::Ptt;
+use std::{
+  collections::VecDeque,
+  io::{self, Read, Write},
+};
 
 pub struct Mock {
   pub response: String,
   pub input: String,
   position: usize,
+  queued: VecDeque<String>,
 }
 impl Mock {
   pub fn new() -> Self {
@@ -11,12 +17,20 @@ impl Mock {
       response: String::new(),
       input: String::new(),
       position: 0,
+      queued: VecDeque::new(),
     }
   }
   pub fn response(mut self, response: String) -> Self {
     self.response = response;
     self
   }
+  /// Queue responses, each one becomes readable after the next write.
+  pub fn responses(mut self, responses: &[&str]) -> Self {
+    self
+      .queued
+      .extend(responses.iter().map(|response| response.to_string()));
+    self
+  }
 }
 
 impl Read for Mock {
@@ -35,6 +49,12 @@ impl Write for Mock {
   fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
     let string = String::from_utf8(buf.to_vec()).unwrap();
     self.input.push_str(&string);
+    if self.position >= self.response.len() {
+      if let Some(next) = self.queued.pop_front() {
+        self.response = next;
+        self.position = 0;
+      }
+    }
     Ok(buf.len())
   }
 
@@ -42,3 +62,14 @@ impl Write for Mock {
     Ok(())
   }
 }
+
+/// PTT that records every keying change.
+#[derive(Default)]
+pub struct RecordedPtt(pub Vec<bool>);
+
+impl Ptt for RecordedPtt {
+  fn set_keyed(&mut self, keyed: bool) -> Result<(), String> {
+    self.0.push(keyed);
+    Ok(())
+  }
+}
@@ -0,0 +1,110 @@
+use sa818::pocsag::{self, Message, Page, PocsagEncoder, IDLE, PREAMBLE_BITS, SYNC};
+
+#[test]
+fn bch_codewords() {
+  assert!(pocsag::check(SYNC));
+  assert!(pocsag::check(IDLE));
+  assert_eq!(pocsag::codeword(SYNC >> 11), SYNC);
+  for bit in 0..32 {
+    assert!(!pocsag::check(IDLE ^ 1 << bit), "{bit}");
+  }
+}
+
+/// Message text back from the codewords following an address.
+fn decode(words: &[u32], numeric: bool) -> String {
+  let bits: Vec<bool> = words
+    .iter()
+    .take_while(|&&word| word >> 31 == 1)
+    .flat_map(|word| (11..31).rev().map(move |bit| word >> bit & 1 == 1))
+    .collect();
+  let width = if numeric { 4 } else { 7 };
+  bits
+    .chunks_exact(width)
+    .map(|c| c.iter().rev().fold(0u8, |v, &b| v << 1 | b as u8))
+    .map(|v| match (numeric, v) {
+      (true, 0..=9) => (b'0' + v) as char,
+      (true, 0xC) => ' ',
+      (true, 0xD) => '-',
+      (true, _) => '?',
+      (false, v) => v as char,
+    })
+    .collect::<String>()
+    .trim_end_matches([' ', '\0'])
+    .to_string()
+}
+
+#[test]
+fn batches() {
+  let page = Page::numeric(1234567, "555-0123").unwrap();
+  let words = pocsag::codewords(std::slice::from_ref(&page));
+  assert_eq!(words.len(), 34);
+  assert_eq!((words[0], words[17]), (SYNC, SYNC));
+  assert!(words.iter().all(|&word| pocsag::check(word)));
+  // RIC 1234567 goes in frame 7, the last two slots.
+  assert!(words[1..15].iter().all(|&word| word == IDLE));
+  assert_eq!(words[15] >> 13, 1234567 >> 3);
+  assert_eq!(words[15] >> 11 & 3, 0);
+  // Its two message codewords straddle the next sync codeword.
+  assert_eq!(decode(&[words[16], words[18]], true), "555-0123");
+  assert!(words[19..].iter().all(|&word| word == IDLE));
+
+  // A long message runs on into the next batch.
+  let text = "The quick brown fox jumps over the lazy dog";
+  let pages = [
+    Page::alphanumeric(8, text).unwrap(),
+    Page::tone(3, 2).unwrap(),
+  ];
+  let words = pocsag::codewords(&pages);
+  assert_eq!(words.len(), 34);
+  assert_eq!(words[1] >> 11 & 3, 3);
+  assert_eq!(words[17], SYNC);
+  let message: Vec<u32> = words[2..17].iter().chain(&words[18..]).copied().collect();
+  assert_eq!(decode(&message, false), text);
+  // The tone page waits for frame 3 of the second batch.
+  let tone = words[18..]
+    .iter()
+    .position(|&word| word != IDLE && word >> 31 == 0)
+    .unwrap();
+  assert_eq!(tone, 6);
+  assert_eq!(words[18 + tone] >> 11 & 3, 2);
+}
+
+#[test]
+fn page_validation() {
+  assert!(Page::numeric(1, "12a").is_err());
+  assert!(Page::alphanumeric(1, "café").is_err());
+  assert!(Page::tone(pocsag::MAX_RIC + 1, 0).is_err());
+  assert!(Page::tone(1, 4).is_err());
+  let page = Page::alphanumeric(1, "hi").unwrap().function(1).unwrap();
+  assert_eq!(page.message(), &Message::Alphanumeric(String::from("hi")));
+  assert!(PocsagEncoder::new(48000).baud(600).is_err());
+  assert!(PocsagEncoder::new(9600).baud(2400).is_ok());
+  assert!(PocsagEncoder::new(8000).baud(2400).is_err());
+}
+
+#[test]
+fn baseband_audio() {
+  let pages = [Page::alphanumeric(1234567, "Test").unwrap()];
+  let bits = pocsag::bits(&pages);
+  assert_eq!(bits.len(), PREAMBLE_BITS + 34 * 32);
+  for (rate, baud) in [(48000, 512), (48000, 1200), (22050, 2400)] {
+    for inverted in [false, true] {
+      let audio = PocsagEncoder::new(rate)
+        .baud(baud)
+        .unwrap()
+        .inverted(inverted)
+        .encode(&pages);
+      let samples_per_bit = rate as f64 / baud as f64;
+      assert_eq!(
+        audio.len(),
+        (bits.len() as f64 * samples_per_bit).round() as usize
+      );
+      let sampled: Vec<bool> = (0..bits.len())
+        .map(|i| audio[((i as f64 + 0.5) * samples_per_bit) as usize])
+        .map(|sample| (sample > 0.0) == inverted)
+        .collect();
+      assert_eq!(sampled, bits, "{rate} {baud} {inverted}");
+      assert!(audio.iter().all(|s| s.abs() <= 0.5 + 1e-6));
+    }
+  }
+}
@@ -0,0 +1,283 @@
+use sa818::{
+  audio::AudioSource,
+  channel::{Channel, FreqConf},
+  flac::FlacWriter,
+  group_call::parse_tone,
+  recorder::{ChannelInfo, RecordFormat, Recorder, RecorderEvent},
+  wav::WavReader,
+};
+use std::{
+  fs,
+  io::Cursor,
+  path::PathBuf,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+fn temp_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("sa818-{}-{}", name, std::process::id()));
+  let _ = fs::remove_dir_all(&dir);
+  dir
+}
+
+/// Reads bits most significant first.
+struct Bits<'a> {
+  bytes: &'a [u8],
+  at: usize,
+}
+
+impl Bits<'_> {
+  fn read(&mut self, bits: u32) -> u64 {
+    (0..bits).fold(0, |value, _| {
+      let bit = self.bytes[self.at / 8] >> (7 - self.at % 8) & 1;
+      self.at += 1;
+      value << 1 | bit as u64
+    })
+  }
+
+  fn signed(&mut self, bits: u32) -> i32 {
+    let value = self.read(bits) as i64;
+    (value << (64 - bits) >> (64 - bits)) as i32
+  }
+
+  fn unary(&mut self) -> u32 {
+    let mut zeros = 0;
+    while self.read(1) == 0 {
+      zeros += 1;
+    }
+    zeros
+  }
+
+  fn byte(&self) -> usize {
+    self.at / 8
+  }
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+  bytes.iter().fold(0u8, |mut crc, &byte| {
+    crc ^= byte;
+    for _ in 0..8 {
+      crc = if crc & 0x80 != 0 {
+        crc << 1 ^ 7
+      } else {
+        crc << 1
+      };
+    }
+    crc
+  })
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+  bytes.iter().fold(0u16, |mut crc, &byte| {
+    crc ^= (byte as u16) << 8;
+    for _ in 0..8 {
+      crc = if crc & 0x8000 != 0 {
+        crc << 1 ^ 0x8005
+      } else {
+        crc << 1
+      };
+    }
+    crc
+  })
+}
+
+/// Just enough of a FLAC decoder for what `FlacWriter` writes, returning
+/// the STREAMINFO block sizes, sample rate, total and the samples.
+fn decode_flac(bytes: &[u8]) -> ((u64, u64), u64, u64, Vec<i32>) {
+  assert_eq!(&bytes[..4], b"fLaC");
+  assert_eq!(&bytes[4..8], &[0x80, 0, 0, 34]);
+  let mut bits = Bits { bytes, at: 64 };
+  let blocks = (bits.read(16), bits.read(16));
+  bits.read(48);
+  let rate = bits.read(20);
+  assert_eq!((bits.read(3), bits.read(5)), (0, 15));
+  let total = bits.read(36);
+  bits.at = 42 * 8;
+
+  let mut samples = Vec::new();
+  while bits.byte() < bytes.len() {
+    let start = bits.byte();
+    assert_eq!(bits.read(16), 0xFFF8);
+    let size_code = bits.read(4);
+    bits.read(4);
+    assert_eq!(bits.read(8), 0b0000_1000);
+    let first = bits.read(8);
+    for _ in 1..(first as u8).leading_ones().max(1) {
+      bits.read(8);
+    }
+    let n = match size_code {
+      0b1100 => 4096,
+      0b0111 => bits.read(16) as usize + 1,
+      code => panic!("block size code {code}"),
+    };
+    let header_end = bits.byte();
+    assert_eq!(bits.read(8) as u8, crc8(&bytes[start..header_end]));
+
+    let kind = bits.read(8) >> 1;
+    let mut block: Vec<i32> = Vec::with_capacity(n);
+    if kind == 1 {
+      block.extend((0..n).map(|_| bits.signed(16)));
+    } else {
+      let order = (kind - 8) as usize;
+      block.extend((0..order).map(|_| bits.signed(16)));
+      assert_eq!(bits.read(6), 0);
+      let k = bits.read(4) as u32;
+      for i in order..n {
+        let u = (bits.unary() << k) | bits.read(k) as u32;
+        let r = (u >> 1) as i32 ^ -((u & 1) as i32);
+        let s = |back: usize| block[i - back];
+        let prediction = match order {
+          0 => 0,
+          1 => s(1),
+          2 => 2 * s(1) - s(2),
+          3 => 3 * s(1) - 3 * s(2) + s(3),
+          _ => 4 * s(1) - 6 * s(2) + 4 * s(3) - s(4),
+        };
+        block.push(prediction + r);
+      }
+    }
+    bits.at = bits.at.div_ceil(8) * 8;
+    let end = bits.byte();
+    assert_eq!(bits.read(16) as u16, crc16(&bytes[start..end]));
+    samples.extend(block);
+  }
+  (blocks, rate, total, samples)
+}
+
+#[test]
+fn flac_round_trip() {
+  let mut seed = 1u32;
+  let audio: Vec<f32> = (0..10000)
+    .map(|i| {
+      seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+      let noise = (seed >> 8) as f32 / (1 << 24) as f32 - 0.5;
+      0.6 * (i as f32 * 0.05).sin() + 0.05 * noise
+    })
+    .collect();
+  let mut writer = FlacWriter::new(Cursor::new(Vec::new()), 8000).unwrap();
+  writer.write(&audio[..3000]).unwrap();
+  writer.write(&audio[3000..]).unwrap();
+  let bytes = writer.finish().unwrap().into_inner();
+  // Smaller than the same audio as 16 bit WAV.
+  assert!(bytes.len() < 2 * audio.len(), "{}", bytes.len());
+
+  let (blocks, rate, total, samples) = decode_flac(&bytes);
+  assert_eq!((blocks, rate, total), ((4096, 4096), 8000, 10000));
+  let expected: Vec<i32> = audio.iter().map(|s| (s * 32767.0).round() as i32).collect();
+  assert_eq!(samples, expected);
+
+  // Full scale noise doesn't predict, it is stored verbatim.
+  let noise: Vec<f32> = (0..100)
+    .map(|i| if i % 3 == 0 { 1.0 } else { -1.0 })
+    .collect();
+  let mut writer = FlacWriter::new(Cursor::new(Vec::new()), 11025).unwrap();
+  writer.write(&noise).unwrap();
+  let (blocks, rate, _, samples) = decode_flac(&writer.finish().unwrap().into_inner());
+  assert_eq!((blocks, rate), ((100, 100), 11025));
+  assert_eq!(samples[..3], [32767, -32767, -32767]);
+}
+
+fn channel_info() -> ChannelInfo {
+  let channel = Channel::default()
+    .rx(FreqConf::with_group_sel(145.6, parse_tone("88.5").unwrap()).unwrap())
+    .tx(FreqConf::with_group_sel(145.0, parse_tone("023N").unwrap()).unwrap());
+  ChannelInfo::new(Some("Local rpt"), &channel)
+}
+
+#[test]
+fn squelch_triggered_recordings() {
+  let dir = temp_dir("recorder");
+  let rate = 8000;
+  let mut recorder = Recorder::new(&dir, rate)
+    .unwrap()
+    .channel(channel_info())
+    .hang_time(Duration::from_millis(500));
+  let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+  let block = vec![0.25; 800];
+  // Closed, open, a pause shorter than the hang time, open again, closed.
+  let plan = [(3, false), (10, true), (3, false), (2, true), (5, false)];
+  let mut events = Vec::new();
+  let mut at = start;
+  let mut rssi = 60;
+  for (blocks, open) in plan {
+    for _ in 0..blocks {
+      rssi += 1;
+      events.extend(recorder.push(&block, open, Some(rssi), at).unwrap());
+      at += Duration::from_millis(100);
+    }
+  }
+  assert!(!recorder.is_recording());
+  assert_eq!(events.len(), 2);
+  let path = dir.join("20231114T221320Z_Local_rpt.wav");
+  assert!(matches!(&events[0], RecorderEvent::Started(started) if *started == path));
+  let RecorderEvent::Finished(info) = &events[1] else {
+    panic!("{:?}", events[1]);
+  };
+  assert_eq!(info.path, path);
+  assert_eq!(info.duration, Duration::from_secs(2));
+  assert_eq!(info.peak_rssi, Some(78));
+  assert_eq!(info.started, start + Duration::from_millis(300));
+
+  let samples = WavReader::open(&path).unwrap().read_to_end().unwrap();
+  assert_eq!(samples.len(), 16000);
+  assert_eq!(
+    fs::read_to_string(dir.join("20231114T221320Z_Local_rpt.json")).unwrap(),
+    "{\"file\":\"20231114T221320Z_Local_rpt.wav\",\"channel\":\"Local rpt\",\
+     \"frequency\":145.6000,\"rx_tone\":\"88.5\",\"tx_tone\":\"023N\",\
+     \"start\":\"2023-11-14T22:13:20.300Z\",\"duration\":2.000,\"peak_rssi\":78,\
+     \"sample_rate\":8000}\n"
+  );
+
+  // Another transmission in the same second gets its own file.
+  recorder
+    .push(&block, true, None, start + Duration::from_millis(300))
+    .unwrap();
+  let Some(RecorderEvent::Finished(info)) = recorder.finish().unwrap() else {
+    panic!("nothing recorded");
+  };
+  assert!(info.path.ends_with("20231114T221320Z_Local_rpt-1.wav"));
+  assert!(info.to_json().contains("\"peak_rssi\":null"));
+  assert!(recorder.finish().unwrap().is_none());
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn short_and_flac_recordings() {
+  let dir = temp_dir("recorder-flac");
+  let mut recorder = Recorder::new(&dir, 8000)
+    .unwrap()
+    .format(RecordFormat::Flac)
+    .hang_time(Duration::from_millis(100))
+    .min_duration(Duration::from_millis(500));
+  let at = SystemTime::now();
+  let block = vec![0.5; 800];
+
+  // A squelch burst, open for one block.
+  recorder.push(&block, true, Some(90), at).unwrap();
+  let events = recorder.push(&block, false, Some(10), at).unwrap();
+  let [RecorderEvent::Discarded(path)] = &events[..] else {
+    panic!("{events:?}");
+  };
+  assert!(!path.exists());
+
+  for open in [true, true, true, true, false] {
+    recorder.push(&block, open, Some(90), at).unwrap();
+  }
+  let files: Vec<PathBuf> = fs::read_dir(&dir)
+    .unwrap()
+    .map(|entry| entry.unwrap().path())
+    .collect();
+  assert_eq!(files.len(), 2);
+  let flac = files
+    .iter()
+    .find(|path| path.extension().unwrap() == "flac")
+    .unwrap();
+  let (_, _, total, samples) = decode_flac(&fs::read(flac).unwrap());
+  assert_eq!(total, 4000);
+  assert!(samples.iter().all(|&s| s == 16384));
+  let json = fs::read_to_string(flac.with_extension("json")).unwrap();
+  assert!(json.contains("\"channel\":null,\"frequency\":null"));
+  fs::remove_dir_all(&dir).unwrap();
+
+  assert_eq!("FLAC".parse::<RecordFormat>().unwrap(), RecordFormat::Flac);
+  assert!("mp3".parse::<RecordFormat>().is_err());
+}
@@ -0,0 +1,52 @@
+use sa818::{
+  channel::{default_offset, Channel, FreqConf, Region},
+  group_call::GroupSel,
+};
+
+fn repeater(frequency: f32) -> Channel {
+  Channel::default()
+    .rx(FreqConf::with_group_sel(frequency, GroupSel::new_ctcss(8).unwrap()).unwrap())
+    .tx(FreqConf::with_group_sel(frequency, GroupSel::new_ctcss(12).unwrap()).unwrap())
+}
+
+#[test]
+fn band_offsets() {
+  assert_eq!(default_offset(145.6, Region::One), Some(0.6));
+  assert_eq!(default_offset(438.65, Region::One), Some(7.6));
+  assert_eq!(default_offset(444.0, Region::Two), Some(5.0));
+  assert_eq!(default_offset(155.0, Region::Two), None);
+}
+
+#[test]
+fn repeater_shift() {
+  let channel = repeater(145.6).offset(-0.6).unwrap();
+  assert_eq!(channel.rx_frequency(), Some(145.6));
+  assert_eq!(channel.tx_frequency(), Some(145.0));
+  assert_eq!(
+    channel.tx_conf().unwrap().group_sel,
+    Some(GroupSel::new_ctcss(12).unwrap())
+  );
+
+  let channel = repeater(438.65).shift_down(Region::One).unwrap();
+  assert_eq!(channel.tx_frequency(), Some(431.05));
+  let channel = repeater(442.1).shift_up(Region::Two).unwrap();
+  assert_eq!(channel.tx_frequency(), Some(447.1));
+
+  assert!(repeater(155.0).shift_up(Region::One).is_err());
+  assert!(repeater(173.8).offset(0.6).is_err());
+  assert!(Channel::default().offset(0.6).is_err());
+
+  let channel = repeater(145.6).split(145.1).unwrap();
+  assert_eq!(channel.tx_frequency(), Some(145.1));
+}
+
+#[test]
+fn repeater_reverse() {
+  let channel = repeater(145.6).offset(-0.6).unwrap().reverse();
+  assert_eq!(channel.rx_frequency(), Some(145.0));
+  assert_eq!(channel.tx_frequency(), Some(145.6));
+  assert_eq!(
+    channel.rx_conf().unwrap().group_sel,
+    Some(GroupSel::new_ctcss(12).unwrap())
+  );
+}
@@ -0,0 +1,85 @@
+use sa818::{
+  rssi_log::{LogFormat, LogRecord, RssiLogger},
+  timestamp,
+};
+use std::{
+  fs,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+fn record(rssi: u8) -> LogRecord {
+  LogRecord {
+    timestamp: UNIX_EPOCH + Duration::from_millis(1_709_296_205_250),
+    frequency: Some(145.5),
+    rssi,
+    dbm: Some(-91.25),
+    squelch_open: Some(true),
+  }
+}
+
+#[test]
+fn timestamps() {
+  let time = UNIX_EPOCH + Duration::from_millis(1_709_296_205_250);
+  assert_eq!(timestamp::rfc3339(time), "2024-03-01T12:30:05.250Z");
+  assert_eq!(timestamp::compact(time), "20240301T123005Z");
+  assert_eq!(timestamp::rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+}
+
+#[test]
+fn record_formats() {
+  assert_eq!(
+    record(87).to_csv(),
+    "2024-03-01T12:30:05.250Z,145.5000,87,-91.2,open"
+  );
+  assert_eq!(
+    record(87).to_json(),
+    "{\"timestamp\":\"2024-03-01T12:30:05.250Z\",\"frequency\":145.5000,\"rssi\":87,\"dbm\":-91.2,\"squelch\":\"open\"}"
+  );
+  let bare = LogRecord::new(UNIX_EPOCH, 3);
+  assert_eq!(bare.to_csv(), "1970-01-01T00:00:00.000Z,,3,,");
+  assert_eq!(
+    bare.to_json(),
+    "{\"timestamp\":\"1970-01-01T00:00:00.000Z\",\"frequency\":null,\"rssi\":3,\"dbm\":null,\"squelch\":null}"
+  );
+  assert_eq!(LogFormat::from_path("survey.jsonl"), LogFormat::JsonLines);
+  assert_eq!(LogFormat::from_path("survey.csv"), LogFormat::Csv);
+  assert_eq!("jsonl".parse::<LogFormat>(), Ok(LogFormat::JsonLines));
+}
+
+#[test]
+fn logger_rotates_by_size() {
+  let dir = std::env::temp_dir().join(format!("sa818-log-{}", std::process::id()));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+  let path = dir.join("survey.csv");
+
+  let mut logger = RssiLogger::create(&path, LogFormat::Csv)
+    .unwrap()
+    .rotate_size(100);
+  for rssi in 0..4 {
+    logger.write(&record(rssi)).unwrap();
+  }
+  drop(logger);
+
+  let mut names: Vec<String> = fs::read_dir(&dir)
+    .unwrap()
+    .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+    .collect();
+  names.sort();
+  assert_eq!(names.len(), 2);
+  assert_eq!(names[1], "survey.csv");
+  assert!(names[0].starts_with("survey-") && names[0].ends_with(".csv"));
+  let current = fs::read_to_string(&path).unwrap();
+  assert!(current.starts_with("timestamp,frequency,rssi,dbm,squelch\n"));
+  assert_eq!(current.lines().count(), 3);
+
+  //Rotation by age
+  let mut logger = RssiLogger::create(dir.join("aged.jsonl"), LogFormat::JsonLines)
+    .unwrap()
+    .rotate_every(Duration::from_secs(60));
+  let mut late = record(1);
+  late.timestamp = SystemTime::now() + Duration::from_secs(61);
+  logger.write(&late).unwrap();
+  assert_eq!(fs::read_dir(&dir).unwrap().count(), 4);
+  fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,92 @@
+mod mocked_io;
+use sa818::{
+  device::Sa818,
+  rssi::{Calibration, Rssi, RssiMonitor, RssiSample, RssiWindow},
+};
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn window_stats() {
+  let start = SystemTime::UNIX_EPOCH;
+  let mut window = RssiWindow::new(Duration::from_secs(2));
+  assert!(window.stats().is_none());
+  for (i, value) in [10, 50, 20, 40, 30].into_iter().enumerate() {
+    window.push(RssiSample {
+      timestamp: start + Duration::from_millis(500 * i as u64),
+      value,
+    });
+  }
+  let stats = window.stats().unwrap();
+  assert_eq!(stats.count, 5);
+  assert_eq!(stats.min, 10);
+  assert_eq!(stats.max, 50);
+  assert_eq!(stats.mean, 30.0);
+  assert_eq!(stats.median, 30);
+  assert_eq!(stats.p90, 50);
+  assert_eq!(window.percentile(0.0), Some(10));
+
+  //Samples older than the span are dropped
+  window.push(RssiSample {
+    timestamp: start + Duration::from_millis(3000),
+    value: 60,
+  });
+  let values: Vec<u8> = window.samples().map(|s| s.value).collect();
+  assert_eq!(values, vec![20, 40, 30, 60]);
+}
+
+#[test]
+fn monitor_iterator() {
+  let mock = mocked_io::Mock::new().responses(&["RSSI=10\r\n", "RSSI=30\r\n", "RSSI=abc\r\n"]);
+  let device = Sa818::new(mock);
+  let mut monitor = RssiMonitor::new(device.clone()).interval(Duration::from_millis(1));
+  let first = monitor.next().unwrap().unwrap();
+  assert_eq!(first.sample.value, 10);
+  let second = monitor.next().unwrap().unwrap();
+  assert_eq!(second.stats.count, 2);
+  assert_eq!(second.stats.mean, 20.0);
+  assert!(monitor.next().unwrap().is_err());
+  assert_eq!(device.lock().unwrap().input, "RSSI?\r\n".repeat(3));
+}
+
+#[test]
+fn monitor_channel() {
+  let mock = mocked_io::Mock::new().responses(&["RSSI=1\r\n", "RSSI=2\r\n", "RSSI=3\r\n"]);
+  let monitor = RssiMonitor::new(Sa818::new(mock)).interval(Duration::from_millis(1));
+  let (handle, readings) = monitor.channel();
+  let values: Vec<u8> = readings
+    .iter()
+    .take(3)
+    .map(|reading| reading.unwrap().sample.value)
+    .collect();
+  handle.stop();
+  assert_eq!(values, vec![1, 2, 3]);
+}
+
+#[test]
+fn rssi_calibration() {
+  let default = Calibration::default();
+  assert_eq!(Rssi(0).dbm(&default), -135.0);
+  assert_eq!(Rssi(84).dbm(&default), -93.0);
+  assert_eq!(Rssi(84).s_meter(&default).to_string(), "S9");
+  assert_eq!(Rssi(124).s_meter(&default).to_string(), "S9+20");
+  assert_eq!(Rssi(60).s_meter(&default).to_string(), "S7");
+
+  let calibration =
+    Calibration::parse("# raw,dbm\n100,-90\n20,-120\n\n200,-50 # strong\n").unwrap();
+  assert_eq!(calibration.points()[0], (20.0, -120.0));
+  assert_eq!(Rssi(60).dbm(&calibration), -105.0);
+  assert_eq!(Rssi(150).dbm(&calibration), -70.0);
+  //Extrapolated from the nearest segment
+  assert_eq!(Rssi(0).dbm(&calibration), -127.5);
+  assert_eq!(Rssi(250).dbm(&calibration), -30.0);
+  assert_eq!(
+    Calibration::parse(&calibration.to_string()),
+    Ok(calibration)
+  );
+
+  assert!(Calibration::parse("10,-100").is_err());
+  assert!(Calibration::parse("10,-100\n10,-90").is_err());
+  assert!(Calibration::parse("10;-100\n20,-90").is_err());
+  assert!(Calibration::new(vec![(10.0, -100.0), (f32::NAN, -90.0)]).is_err());
+  assert!(Calibration::parse("10,-100\n20,inf").is_err());
+}
@@ -0,0 +1,107 @@
+mod mocked_io;
+use sa818::{
+  channel::{Channel, FmBandwidth, FreqConf},
+  device::Sa818,
+  scanner::{self, CarrierMode, Scanner},
+};
+use std::time::Duration;
+
+const SET: &str = "+DMOSETGROUP=0\r\n";
+
+#[test]
+fn range_sweep() {
+  let mock =
+    mocked_io::Mock::new().responses(&[SET, "RSSI=10\r\n", SET, "RSSI=90\r\n", SET, "RSSI=20\r\n"]);
+  let device = Sa818::new(mock);
+  let mut scanner = Scanner::range(device.clone(), 145.0, 145.025, 0.0125, FmBandwidth::Wide)
+    .unwrap()
+    .dwell(Duration::ZERO)
+    .threshold(50);
+  assert_eq!(scanner.channel_count(), 3);
+  assert_eq!(scanner::range_count(145.0, 145.025, 0.0125), Ok(3));
+  assert!(scanner::range_count(145.0, 146.0, 0.0).is_err());
+  let steps = scanner.sweep().unwrap();
+  let active: Vec<bool> = steps.iter().map(|step| step.active).collect();
+  assert_eq!(active, vec![false, true, false]);
+  assert_eq!(scanner.results()[1].hits, 1);
+  assert_eq!(scanner.results()[1].peak, 90);
+  assert!(scanner.results()[1].last_active.is_some());
+  assert_eq!(
+    device.lock().unwrap().input,
+    "AT+DMOSETGROUP=0,145.0000,145.0000,0000,4,0000\r\nRSSI?\r\n\
+     AT+DMOSETGROUP=0,145.0125,145.0125,0000,4,0000\r\nRSSI?\r\n\
+     AT+DMOSETGROUP=0,145.0250,145.0250,0000,4,0000\r\nRSSI?\r\n"
+  );
+
+  assert!(Scanner::range(
+    Sa818::new(mocked_io::Mock::new()),
+    146.0,
+    145.0,
+    0.0125,
+    FmBandwidth::Narrow
+  )
+  .is_err());
+  assert!(Scanner::range(
+    Sa818::new(mocked_io::Mock::new()),
+    170.0,
+    180.0,
+    5.0,
+    FmBandwidth::Narrow
+  )
+  .is_err());
+}
+
+#[test]
+fn lockout_and_hold() {
+  let channels: Vec<Channel> = [433.5, 433.6, 433.7]
+    .iter()
+    .map(|&f| {
+      Channel::default()
+        .rx(FreqConf::new(f).unwrap())
+        .tx(FreqConf::new(f).unwrap())
+    })
+    .collect();
+  let mock = mocked_io::Mock::new().responses(&[
+    SET,
+    "RSSI=5\r\n",
+    SET,
+    "RSSI=80\r\n",
+    "RSSI=70\r\n",
+    SET,
+    "RSSI=1\r\n",
+  ]);
+  let device = Sa818::new(mock);
+  let mut scanner = Scanner::channels(device.clone(), channels)
+    .unwrap()
+    .dwell(Duration::ZERO)
+    .mode(CarrierMode::Hold);
+  scanner.lockout(433.6);
+  assert!(scanner.is_locked_out(433.6));
+  assert_eq!(scanner.unlocked_count(), 2);
+
+  assert_eq!(scanner.step().unwrap().frequency, 433.5);
+  let step = scanner.step().unwrap();
+  assert_eq!(step.frequency, 433.7);
+  assert!(step.active);
+  assert_eq!(scanner.held(), Some(2));
+  //Held channel is sampled again without retuning
+  assert_eq!(scanner.step().unwrap().rssi, 70);
+  scanner.resume();
+  scanner.unlock(433.6);
+  assert_eq!(scanner.step().unwrap().frequency, 433.5);
+  assert_eq!(
+    device
+      .lock()
+      .unwrap()
+      .input
+      .matches("AT+DMOSETGROUP")
+      .count(),
+    3
+  );
+  assert_eq!(scanner.results()[2].hits, 1);
+
+  scanner.lockout(433.5);
+  scanner.lockout(433.6);
+  scanner.lockout(433.7);
+  assert!(scanner.step().is_err());
+}
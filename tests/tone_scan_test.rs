@@ -0,0 +1,85 @@
+use sa818::{
+  audio::{AudioSource, MemorySource},
+  group_call::{parse_ctcss, Ctcss},
+  tone_scan::CtcssScanner,
+  wav::{WavReader, WavWriter},
+};
+use std::{f32::consts::PI, io::Cursor, time::Duration};
+
+const RATE: u32 = 8000;
+
+fn tone(hz: f32, amplitude: f32, seconds: f32) -> Vec<f32> {
+  (0..(RATE as f32 * seconds) as usize)
+    .map(|i| amplitude * (2.0 * PI * hz * i as f32 / RATE as f32).sin())
+    .collect()
+}
+
+/// Tone under a louder "voice" made of a few audio frequencies.
+fn with_voice(hz: f32) -> Vec<f32> {
+  let mut samples = tone(hz, 0.1, 3.0);
+  for (voice, amplitude) in [(440.0, 0.3), (730.0, 0.2), (1250.0, 0.15)] {
+    for (sample, v) in samples.iter_mut().zip(tone(voice, amplitude, 3.0)) {
+      *sample += v;
+    }
+  }
+  samples
+}
+
+#[test]
+fn detects_every_tone() {
+  let scanner = CtcssScanner::new(RATE);
+  for ctcss in Ctcss::all() {
+    let found = scanner.detect(&tone(ctcss.hz(), 0.1, 1.0)).unwrap();
+    assert_eq!(found.ctcss, ctcss);
+    assert!(found.confidence > 0.9, "{} {}", ctcss, found.confidence);
+    assert!((found.level - 0.1).abs() < 0.01);
+  }
+}
+
+#[test]
+fn detects_tone_under_voice() {
+  let mut source = MemorySource::new(RATE, with_voice(71.9));
+  let found = CtcssScanner::new(RATE).scan(&mut source).unwrap().unwrap();
+  assert_eq!(found.ctcss.hz(), 71.9);
+  assert!(found.confidence > 0.8);
+  // what the cli prints can be passed back as --ctcss
+  assert_eq!(parse_ctcss(&found.ctcss.to_string()), Ok(found.group_sel()));
+}
+
+#[test]
+fn nothing_in_silence_or_voice() {
+  let scanner = CtcssScanner::new(RATE);
+  assert!(scanner.detect(&vec![0.0; RATE as usize]).is_none());
+  let voice: Vec<f32> = with_voice(71.9)
+    .iter()
+    .zip(tone(71.9, 0.1, 3.0))
+    .map(|(mixed, tone)| mixed - tone)
+    .collect();
+  assert!(scanner.detect(&voice).is_none());
+}
+
+#[test]
+fn scans_wav_file() {
+  let mut writer = WavWriter::new(Cursor::new(Vec::new()), RATE).unwrap();
+  writer.write(&with_voice(136.5)).unwrap();
+  let wav = writer.finish().unwrap().into_inner();
+  assert_eq!(wav.len(), 44 + 2 * 3 * RATE as usize);
+
+  let mut reader = WavReader::new(Cursor::new(wav.clone())).unwrap();
+  assert_eq!(reader.sample_rate(), RATE);
+  let found = CtcssScanner::new(RATE).scan(&mut reader).unwrap().unwrap();
+  assert_eq!(found.ctcss.hz(), 136.5);
+
+  let mut reader = WavReader::new(Cursor::new(wav)).unwrap();
+  assert!(CtcssScanner::new(48000).scan(&mut reader).is_err());
+  // Shorter than a 67 Hz period, the scan would never end.
+  assert!(CtcssScanner::new(RATE).window(Duration::ZERO).is_err());
+  assert!(CtcssScanner::new(RATE)
+    .window(Duration::from_millis(10))
+    .is_err());
+  let scanner = CtcssScanner::new(RATE)
+    .window(Duration::from_millis(500))
+    .unwrap();
+  assert_eq!(scanner.window_len(), 4000);
+  assert!(WavReader::new(Cursor::new(b"RIFF\0\0\0\0WAVE".to_vec())).is_err());
+}
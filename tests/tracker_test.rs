@@ -0,0 +1,200 @@
+mod mocked_io;
+use sa818::{
+  afsk::AfskDemodulator,
+  aprs::{self, Position, Symbol},
+  audio::AudioSource,
+  ax25::{Address, UiFrame},
+  device::Sa818,
+  nmea::{NmeaParser, NmeaReader},
+  ptt::Transmitter,
+  tracker::{Beaconing, SmartBeacon, Tracker},
+  wav::{WavReader, WavWriter},
+};
+use std::{
+  io::Cursor,
+  time::{Duration, Instant},
+};
+
+#[test]
+fn position_reports() {
+  let mut position = Position::new(49.0583333, -72.0291667).unwrap();
+  assert_eq!(
+    position.report(Symbol::default(), "hi"),
+    "!4903.50N/07201.75W>hi"
+  );
+  position.course = Some(0.0);
+  position.speed = Some(36.4);
+  position.altitude = Some(376.4);
+  let symbol: Symbol = "/-".parse().unwrap();
+  assert_eq!(
+    position.report(symbol, ""),
+    "!4903.50N/07201.75W-360/036/A=001235"
+  );
+  let south = Position::new(-33.8688, 151.2093).unwrap();
+  assert_eq!(south.report(symbol, ""), "!3352.13S/15112.56E-");
+
+  assert!(Position::new(91.0, 0.0).is_err());
+  assert!("/".parse::<Symbol>().is_err());
+  assert!("x>".parse::<Symbol>().is_err());
+  assert_eq!(
+    "\\k".parse::<Symbol>().unwrap(),
+    Symbol {
+      table: '\\',
+      code: 'k'
+    }
+  );
+}
+
+#[test]
+fn telemetry_reports() {
+  assert_eq!(
+    aprs::telemetry(1005, &[60, 126], 0b101).unwrap(),
+    "T#005,060,126,000,000,000,10100000"
+  );
+  assert!(aprs::telemetry(0, &[0; 6], 0).is_err());
+  let call: Address = "N0CALL-9".parse().unwrap();
+  assert_eq!(aprs::message(&call, "UNIT.raw,V"), ":N0CALL-9 :UNIT.raw,V");
+}
+
+#[test]
+fn nmea_positions() {
+  let mut parser = NmeaParser::new();
+  assert!(parser
+    .push("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47")
+    .is_none());
+  let position = parser
+    .push("$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A\r\n")
+    .unwrap();
+  assert!((position.latitude - 48.1173).abs() < 1e-4);
+  assert!((position.longitude - 11.516667).abs() < 1e-4);
+  assert_eq!(position.speed, Some(22.4));
+  assert_eq!(position.course, Some(84.4));
+  assert_eq!(position.altitude, Some(545.4));
+
+  // Void fixes and bad checksums give nothing.
+  assert!(parser
+    .push("$GPRMC,123519,V,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*7D")
+    .is_none());
+  assert!(parser
+    .push("$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6B")
+    .is_none());
+
+  let stream = "junk\n{\"class\":\"VERSION\"}\n\
+    $GNRMC,001,A,3352.128,S,15112.558,E,,,010125,,*2A\n";
+  let mut reader = NmeaReader::new(Cursor::new(stream));
+  let position = reader.next_position().unwrap().unwrap();
+  assert!(position.latitude < 0.0);
+  assert_eq!(position.speed, None);
+  assert_eq!(reader.next_position().unwrap(), None);
+}
+
+#[test]
+fn smart_beaconing() {
+  let smart = SmartBeacon::default();
+  assert_eq!(smart.rate(0.0), Duration::from_secs(1800));
+  assert_eq!(smart.rate(60.0), Duration::from_secs(120));
+  assert_eq!(smart.rate(25.0), Duration::from_secs(240));
+
+  let moving = |course, speed| Position {
+    course: Some(course),
+    speed: Some(speed),
+    ..Position::default()
+  };
+  let last = moving(90.0, 50.0);
+  assert!(!smart.due(&last, Duration::from_secs(60), &moving(90.0, 50.0)));
+  assert!(smart.due(&last, Duration::from_secs(120), &moving(90.0, 50.0)));
+  // At 50 knots a turn of 28 + 255 / 50 degrees counts, wrapping past north.
+  assert!(smart.due(&last, Duration::from_secs(30), &moving(124.0, 50.0)));
+  assert!(smart.due(
+    &moving(350.0, 50.0),
+    Duration::from_secs(30),
+    &moving(24.0, 50.0)
+  ));
+  assert!(!smart.due(&last, Duration::from_secs(30), &moving(120.0, 50.0)));
+  assert!(!smart.due(&last, Duration::from_secs(10), &moving(180.0, 50.0)));
+  // Slower takes a sharper turn.
+  assert!(!smart.due(&last, Duration::from_secs(30), &moving(150.0, 5.0)));
+  assert!(smart.due(&last, Duration::from_secs(30), &moving(180.0, 5.0)));
+}
+
+#[test]
+fn tracker_beacons_and_telemetry() {
+  let rate = 22050;
+  // Telemetry reads the RSSI, then each transmission checks the channel.
+  let mock = mocked_io::Mock::new().responses(&["RSSI=42\r\n", "RSSI=10\r\n", "RSSI=10\r\n"]);
+  let sink = WavWriter::new(Cursor::new(Vec::new()), rate).unwrap();
+  let transmitter = Transmitter::new(mocked_io::RecordedPtt::default(), sink);
+  let mut tracker = Tracker::new(Sa818::new(mock), transmitter, "N0CALL-9".parse().unwrap())
+    .comment(" sa818")
+    .path(vec!["WIDE2-1".parse().unwrap()])
+    .unwrap()
+    .beaconing(Beaconing::Fixed(Duration::from_secs(600)))
+    .telemetry(Duration::from_secs(1200))
+    .voltage(|| Ok(12.64));
+
+  let start = Instant::now();
+  let position = Position::new(49.0583333, -72.0291667).unwrap();
+  let sent: Vec<String> = tracker
+    .poll(start, Some(&position))
+    .unwrap()
+    .iter()
+    .map(|frame| frame.to_string())
+    .collect();
+  assert_eq!(
+    sent,
+    vec![
+      "N0CALL-9>APZ818,WIDE2-1:!4903.50N/07201.75W> sa818",
+      "N0CALL-9>APZ818,WIDE2-1::N0CALL-9 :PARM.RSSI,Supply",
+      "N0CALL-9>APZ818,WIDE2-1::N0CALL-9 :UNIT.raw,V",
+      "N0CALL-9>APZ818,WIDE2-1::N0CALL-9 :EQNS.0,1,0,0,0.1,0",
+      "N0CALL-9>APZ818,WIDE2-1:T#000,042,126,000,000,000,00000000",
+    ]
+  );
+
+  assert!(tracker
+    .poll(start + Duration::from_secs(300), Some(&position))
+    .unwrap()
+    .is_empty());
+  // No fix, no beacon.
+  assert!(tracker
+    .poll(start + Duration::from_secs(700), None)
+    .unwrap()
+    .is_empty());
+  let sent = tracker
+    .poll(start + Duration::from_secs(701), Some(&position))
+    .unwrap();
+  assert_eq!(sent.len(), 1);
+
+  let (ptt, sink) = tracker.into_transmitter().into_parts();
+  assert_eq!(ptt.0, vec![true, false, true, false]);
+  let mut wav = sink.finish().unwrap();
+  wav.set_position(0);
+  let samples = WavReader::new(wav).unwrap().read_to_end().unwrap();
+  let frames: Vec<UiFrame> = AfskDemodulator::new(rate)
+    .push(&samples)
+    .iter()
+    .map(|bytes| UiFrame::decode(bytes).unwrap())
+    .collect();
+  assert_eq!(frames.len(), 6);
+  assert_eq!(frames[5], sent[0]);
+}
+
+#[test]
+fn telemetry_sequence_waits_for_a_send() {
+  // The first transmission fails its channel check, the retry starts over.
+  let mock =
+    mocked_io::Mock::new().responses(&["RSSI=42\r\n", "ERROR\r\n", "RSSI=42\r\n", "RSSI=10\r\n"]);
+  let sink = WavWriter::new(Cursor::new(Vec::new()), 22050).unwrap();
+  let transmitter = Transmitter::new(mocked_io::RecordedPtt::default(), sink);
+  let mut tracker = Tracker::new(Sa818::new(mock), transmitter, "N0CALL-9".parse().unwrap())
+    .telemetry(Duration::from_secs(1200));
+
+  let start = Instant::now();
+  assert!(tracker.poll(start, None).is_err());
+  let sent = tracker.poll(start + Duration::from_secs(1), None).unwrap();
+  assert_eq!(sent.len(), 4);
+  assert_eq!(
+    sent[3].to_string(),
+    "N0CALL-9>APZ818,WIDE1-1,WIDE2-1:T#000,042,000,000,000,000,00000000"
+  );
+}